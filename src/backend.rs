@@ -1,151 +1,1064 @@
-use crate::client::api::{ApiRepository, ApiRunnerGroupCreate, Client, RunnerGroupVisibility};
+use crate::client::api::{ApiRepository, ApiRunnerGroup, ApiRunnerGroupCreate, ApiRunnerGroupUpdate, Client, ClientError, JitConfig, RateLimit, RegistrationToken, RunnerApplication, RunnerGroupVisibility};
 use crate::model::runners::{Runner, RunnerGroup};
-use crate::utils::config::Config;
-use cli_log::debug;
+use crate::utils::audit::AuditLog;
+use crate::utils::cache::default_cache_dir;
+use crate::utils::config::{ApiScope, Config};
+use crate::utils::export::{write_groups_csv, write_markdown_report};
+use cli_log::{debug, info};
+use anyhow::{anyhow, Context, Result};
 use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::StatusCode;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use futures::TryFutureExt;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
 
 pub enum BackendMessage {
-    FetchRunners,
-    FetchGroups,
+    FetchRunners { skip_cache: bool },
+    FetchGroups { skip_cache: bool },
     AddLabel(usize, String),
+    AddLabelBulk(Vec<usize>, String),
     DeleteLabel(usize, String),
+    SetLabels(usize, Vec<String>),
     ChangeGroup(usize, String),
+    RemoveFromGroup(usize),
     AddRepoToGroup(String, usize),
     GetGroupRepos(usize),
+    GetGroupRunners(usize),
     CreateRunnerGroup(Box<ApiRunnerGroupCreate>),
+    DeleteRunnerGroup(usize),
+    UpdateRunnerGroup(usize, Box<ApiRunnerGroupUpdate>),
+    ExportMarkdown,
+    ExportGroupsCsv,
+    FetchGroupAccess,
+    SwitchProfile(String),
+    SwitchOrg(String),
+    RefreshGroup(usize),
+    FindDuplicateGroups(usize),
+    MergeGroups(usize, usize),
+    DeleteRunner(usize),
+    GetRunner(usize),
+    FetchRepos,
+    RemoveRepoFromGroup(usize, usize),
+    CreateRegistrationToken,
+    FetchRunnerDownloads,
+    /// Registers a just-in-time runner with this name in the group (by id), with these labels.
+    GenerateJitConfig(String, usize, Vec<String>),
 }
 
 pub enum ApiMessage {
     Ok,
-    RunnerList(Box<Vec<Runner>>),
-    RunnerGroupList(Box<Vec<RunnerGroup>>),
-    GroupRepos(Box<Vec<ApiRepository>>)
+    RunnerList(Vec<Runner>),
+    RunnerGroupList(Vec<RunnerGroup>),
+    GroupRepos(Vec<ApiRepository>),
+    RepoList(Vec<ApiRepository>),
+    GroupRunners(String, Vec<Runner>),
+    GroupMembers(Vec<Runner>),
+    LabelChanged(usize, String, bool),
+    /// Fresh state of a runner, `None` if it no longer exists.
+    RunnerDetails(usize, Option<Box<Runner>>),
+    DuplicateGroups(Result<Vec<RunnerGroup>, String>),
+    GroupsMerged(Result<String, String>),
+    GroupUpdated(Box<RunnerGroup>),
+    /// A change went through, with a message for the user.
+    Success(String),
+    Error(String),
+    /// The token was rejected before anything could be fetched, with what to check.
+    InvalidToken(String),
+    Exported(Result<String, String>),
+    GroupsExported(Result<String, String>),
+    GroupAccess(Result<Vec<(RunnerGroup, Vec<ApiRepository>)>, String>),
+    RegistrationToken(Result<RegistrationToken, String>),
+    RunnerDownloads(Result<Vec<RunnerApplication>, String>),
+    JitConfig(Result<Box<JitConfig>, String>),
+    /// API quota left, as of the last response that reported it.
+    RateLimit(RateLimit),
 }
 
+/// What to check when GitHub rejects the configured token.
+fn invalid_token_message(config: &Config) -> String {
+    let owner = match config.scope {
+        ApiScope::Org => format!("organization {}", config.organization),
+        ApiScope::Enterprise => format!("enterprise {}", config.enterprise),
+    };
+    format!("GitHub rejected the token (401 Unauthorized). Check that it hasn't expired or been revoked \
+             and that it may manage the self-hosted runners of the {}: classic tokens need the admin:org \
+             scope, fine-grained ones the manage_runners:org permission.", owner)
+}
+
+/// A runner in the audit log, by name and id since names are reused once a runner is gone.
+fn runner_target(name: &str, runner_id: usize) -> String {
+    format!("runner {} (#{})", name, runner_id)
+}
+
+/// Awaits `futures` keeping at most `limit` of them in flight, in their original order.
+/// Stops at the first error.
+async fn run_bounded<T>(futures: impl IntoIterator<Item = impl Future<Output = Result<T>>>, limit: usize) -> Result<Vec<T>> {
+    stream::iter(futures)
+        .buffered(limit.max(1))
+        .try_collect()
+        .await
+}
+
+/// Receiving end of the commands, outliving any one worker: commands sent while a worker is
+/// down wait for the one restarted in its place, and sending never fails while the UI holds it.
+pub type SharedReceiver = Arc<Mutex<mpsc::UnboundedReceiver<BackendMessage>>>;
+
 pub struct Worker {
     pub client: Arc<Client>,
+    /// Clients already built, by organization API URL and token, so their caches survive switching
+    /// orgs or profiles. Profiles of the same organization may use different tokens.
+    clients: HashMap<String, Arc<Client>>,
     pub config: Config,
-    pub rx: mpsc::UnboundedReceiver<BackendMessage>,
+    pub rx: SharedReceiver,
     pub tx: mpsc::UnboundedSender<ApiMessage>,
+    /// Names of the runners last fetched, by id, to describe them in success messages.
+    runner_names: HashMap<usize, String>,
+    /// Whether a request went through, after which a 401 is no longer blamed on the token setup.
+    authenticated: bool,
+    /// Where changes that went through are recorded, if anywhere.
+    audit_log: Option<AuditLog>,
 }
 
 impl Worker {
-    pub fn new(rx: mpsc::UnboundedReceiver<BackendMessage>, tx: mpsc::UnboundedSender<ApiMessage>, config: Config) -> Self {
+    pub fn new(rx: SharedReceiver, tx: mpsc::UnboundedSender<ApiMessage>, config: Config) -> Self {
+        let client = Arc::new(Worker::build_client(&config));
+        let mut clients = HashMap::new();
+        clients.insert(Worker::client_key(&config), Arc::clone(&client));
+        let audit_log = config.audit_log.as_ref().map(AuditLog::new);
+        Worker { client, clients, rx, tx, config, runner_names: HashMap::new(), authenticated: false, audit_log }
+    }
+
+    fn client_key(config: &Config) -> String {
+        let url = config.api_url().map(|url| url.to_string()).unwrap_or_default();
+        format!("{} {}", url, config.token)
+    }
+
+    /// Points the worker at the current `config`, reusing the previous client of that org.
+    fn use_client_for_config(&mut self) {
+        let config = &self.config;
+        self.client = Arc::clone(self.clients.entry(Worker::client_key(config))
+            .or_insert_with(|| Arc::new(Worker::build_client(config))));
+    }
+
+    fn build_client(config: &Config) -> Client {
         let mut headers = HeaderMap::new();
         headers.insert("User-Agent", HeaderValue::from_str("curl").unwrap());
         headers.insert("Authorization", HeaderValue::from_str(&format!("Bearer {}", config.token)).unwrap());
-        let github_client = Client::new(&format!("https://api.github.com/orgs/{}/", config.organization), headers)
-            .expect("Failed to create github client");
-        let client = Arc::new(github_client);
-        Worker { client, rx, tx, config }
+        let api_url = config.api_url().expect("Invalid api_base");
+        let client = Client::new(api_url.as_str(), headers)
+            .expect("Failed to create github client")
+            .with_timeout(Duration::from_secs(config.request_timeout))
+            .with_cache_ttls(config.runners_cache_ttl, config.groups_cache_ttl)
+            .with_server_error_retries(config.server_error_retries)
+            .with_scope(config.scope);
+        match default_cache_dir() {
+            Some(dir) if config.persist_cache => client.with_persistent_cache(&dir.join(Worker::cache_name(config))),
+            _ => client,
+        }
+    }
+
+    /// Directory of the persistent cache of a client, one per client like `clients`: the same
+    /// organization on another host, or with another token, may see different runners.
+    /// The key is hashed to keep the token out of the file name.
+    fn cache_name(config: &Config) -> String {
+        let host = config.api_url().ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let owner = match config.scope {
+            ApiScope::Org => config.organization.clone(),
+            ApiScope::Enterprise => format!("enterprise-{}", config.enterprise),
+        };
+        let mut hasher = DefaultHasher::new();
+        Worker::client_key(config).hash(&mut hasher);
+        format!("{}-{}-{:016x}", host, owner, hasher.finish())
     }
 
-    pub async fn get_runner_groups(&mut self) -> Vec<RunnerGroup> {
-        let groups_api = self.client.runner_groups().get_all(false).await.unwrap();
-        groups_api.runner_groups
+    pub async fn get_runner_groups(&mut self, skip_cache: bool) -> Result<Vec<RunnerGroup>> {
+        let groups_api = self.client.runner_groups().get_all(skip_cache).await?;
+        Ok(groups_api.runner_groups
             .into_iter()
             .map(|g|RunnerGroup::from(g))
-            .collect()
+            .collect())
     }
 
-    pub async fn get_runners(&mut self, skip_cache: Option<bool>) -> Vec<Runner> {
+    pub async fn get_runners(&mut self, skip_cache: Option<bool>) -> Result<Vec<Runner>> {
         let dirty = skip_cache.unwrap_or(false);
-        let groups_api = self.client.runner_groups().get_all(dirty).await.unwrap();
+        let groups_api = self.client.runner_groups().get_all(dirty).await?;
         let group_ids: Vec<(usize, String)> = groups_api.runner_groups.iter().map(|g| (g.id, g.name.clone())).collect();
         let groups = groups_api.runner_groups
             .into_iter()
             .map(|g|RunnerGroup::from(g))
             .collect();
-        self.tx.send(ApiMessage::RunnerGroupList(Box::new(groups)))
+        self.tx.send(ApiMessage::RunnerGroupList(groups))
             .expect("Could not sent command to frontend worker");
         let futures = group_ids
             .into_iter()
             .map(|(id, name)| {
                 let client_clone = Arc::clone(&self.client);
                 async move {
-                    let runners_api = client_clone.runner_groups().get_runners(id, dirty).await.unwrap().runners;
-                    runners_api.into_iter().map(|r| {
+                    let runners_api = client_clone.runner_groups().get_runners(id, dirty).await
+                        .with_context(|| format!("Could not list runners of group {}", name))?
+                        .runners;
+                    Ok::<Vec<Runner>, anyhow::Error>(runners_api.into_iter().map(|r| {
                         let mut runner = Runner::from(r);
                         runner.group = Some(name.clone());
                         runner
-                    }).collect()
+                    }).collect())
                 }
             } );
-        let results: Vec<Vec<Runner>> = futures::future::join_all(futures).await;
-        let runners: Vec<Runner> = results.into_iter()
-            .flatten().collect();
+        let results: Vec<Vec<Runner>> = run_bounded(futures, self.config.fetch_concurrency).await?;
+        // A runner listed by several groups is kept once, in the first group listing it.
+        let mut seen = HashSet::new();
+        let mut runners: Vec<Runner> = results.into_iter()
+            .flatten()
+            .filter(|r| seen.insert(r.id))
+            .collect();
+        // Runners no group listing returned are still shown, without a group.
+        let ungrouped = self.client.runners().get_all(dirty).await
+            .context("Could not list all runners")?
+            .runners;
+        runners.extend(ungrouped.into_iter()
+            .filter(|r| seen.insert(r.id))
+            .map(Runner::from));
         debug!("Fetched runners {:?}", runners);
-        runners
+        self.runner_names = runners.iter().map(|r| (r.id, r.name.clone())).collect();
+        Ok(runners)
     }
 
-    pub async fn refresh_runners(&mut self) {
-        let runners = self.get_runners(Some(true)).await;
-        self.tx.send(ApiMessage::RunnerList(Box::new(runners)))
+    pub async fn refresh_runners(&mut self) -> Result<()> {
+        let runners = self.get_runners(Some(true)).await?;
+        self.tx.send(ApiMessage::RunnerList(runners))
             .expect("Could not send refreshed runner list to frontend");
+        Ok(())
+    }
+
+    /// Re-fetches the runners of a single group instead of fanning out over every group.
+    pub async fn refresh_group(&mut self, group_id: usize) -> Result<()> {
+        let groups = self.client.runner_groups().get_all(false).await?;
+        let Some(group) = groups.runner_groups.into_iter().find(|g| g.id == group_id) else {
+            debug!("Group {} not found, doing a full refresh", group_id);
+            return self.refresh_runners().await;
+        };
+        let runners = self.client.runner_groups().get_runners(group_id, true).await?.runners
+            .into_iter()
+            .map(|r| {
+                let mut runner = Runner::from(r);
+                runner.group = Some(group.name.clone());
+                runner
+            })
+            .collect();
+        self.tx.send(ApiMessage::GroupRunners(group.name, runners))
+            .expect("Could not send group runners to frontend");
+        Ok(())
+    }
+
+    pub async fn refresh_groups(&mut self) -> Result<()> {
+        let groups = self.client.runner_groups().get_all(true).await?.runner_groups
+            .into_iter()
+            .map(RunnerGroup::from)
+            .collect();
+        self.tx.send(ApiMessage::RunnerGroupList(groups))
+            .expect("Could not send runner groups to frontend");
+        Ok(())
+    }
+
+    async fn group_repo_ids(&self, group: &ApiRunnerGroup) -> Result<HashSet<usize>> {
+        if group.visibility == RunnerGroupVisibility::All {
+            return Ok(HashSet::new());
+        }
+        let repos = self.client.runner_groups().get_group_repos(group.id).await?.repositories;
+        Ok(repos.into_iter().map(|r| r.id).collect())
+    }
+
+    /// Groups granting exactly the same repository access as `group_id`.
+    /// Default and inherited groups are never offered since they can't be deleted.
+    pub async fn find_duplicate_groups(&self, group_id: usize) -> Result<Vec<RunnerGroup>> {
+        let groups = self.client.runner_groups().get_all(false).await?.runner_groups;
+        let Some(target) = groups.iter().find(|g| g.id == group_id) else {
+            return Ok(vec![]);
+        };
+        let target_repos = self.group_repo_ids(target).await?;
+        let mut duplicates = vec![];
+        for group in groups.iter().filter(|g| g.id != group_id && !g.default && !g.inherited && g.visibility == target.visibility) {
+            if self.group_repo_ids(group).await? == target_repos {
+                duplicates.push(RunnerGroup::from(group.clone()));
+            }
+        }
+        Ok(duplicates)
+    }
+
+    /// Moves every runner of `from` into `into` and deletes `from`.
+    /// The source group is kept if any runner could not be moved.
+    pub async fn merge_groups(&mut self, from: usize, into: usize) -> Result<String, String> {
+        let runners = self.client.runner_groups().get_runners(from, true).await
+            .map_err(|e| format!("Could not list runners of group {}: {}", from, e))?
+            .runners;
+        let mut failures = vec![];
+        for runner in &runners {
+            let request = format!("PUT actions/runner-groups/{}/runners/{}", into, runner.id);
+            match self.mutate(request, self.client.runner_groups().add_runner_to_group(runner.id, into)).await {
+                Ok(()) => self.audit(&runner_target(&runner.name, runner.id), &format!("move to runner group #{}", into)),
+                Err(e) => failures.push(format!("{}: {}", runner.name, e)),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(format!("Moved {} of {} runners, the duplicate group was not deleted.\n{}",
+                               runners.len() - failures.len(), runners.len(), failures.join("\n")));
+        }
+        self.mutate(format!("DELETE actions/runner-groups/{}", from), self.client.runner_groups().delete_runner_group(from)).await
+            .map_err(|e| format!("Moved {} runners but could not delete the duplicate group: {}", runners.len(), e))?;
+        self.audit(&format!("runner group #{}", from), &format!("delete, merged into runner group #{}", into));
+        Ok(format!("Moved {} runners and deleted the duplicate group", runners.len()))
+    }
+
+    /// The group as `patch` would leave it, shown instead of GitHub's answer in dry-run mode.
+    async fn dry_run_group_update(&self, group_id: usize, patch: ApiRunnerGroupUpdate) -> Result<ApiRunnerGroup> {
+        let mut group = self.client.runner_groups().get_all(false).await
+            .with_context(|| format!("Could not get runner group {}", group_id))?
+            .runner_groups
+            .into_iter()
+            .find(|g| g.id == group_id)
+            .ok_or_else(|| anyhow!("There is no runner group {}", group_id))?;
+        if let Some(name) = patch.name {
+            group.name = name;
+        }
+        if let Some(visibility) = patch.visibility {
+            group.visibility = visibility;
+        }
+        if let Some(restricted) = patch.restricted_to_workflows {
+            group.restricted_to_workflows = restricted;
+        }
+        if let Some(workflows) = patch.selected_workflows {
+            group.selected_workflows = workflows;
+        }
+        Ok(group)
+    }
+
+    /// Every runner group with the repositories allowed to use it. Groups open to all
+    /// repositories have no access list, they are returned with an empty one.
+    async fn groups_with_repos(&self) -> Result<Vec<(RunnerGroup, Vec<ApiRepository>)>> {
+        let groups = self.client.runner_groups().get_all(false).await
+            .context("Could not fetch runner groups")?
+            .runner_groups;
+        let futures = groups.into_iter().map(|group| {
+            let client = Arc::clone(&self.client);
+            async move {
+                let repos = match group.visibility {
+                    RunnerGroupVisibility::All => vec![],
+                    RunnerGroupVisibility::Selected => client.runner_groups().get_group_repos(group.id).await
+                        .with_context(|| format!("Could not get the repos of group {}", group.name))?
+                        .repositories,
+                };
+                Ok((RunnerGroup::from(group), repos))
+            }
+        });
+        run_bounded(futures, self.config.fetch_concurrency).await
+    }
+
+    fn runner_name(&self, runner_id: usize) -> String {
+        self.runner_names.get(&runner_id).cloned().unwrap_or_else(|| format!("#{}", runner_id))
+    }
+
+    /// The runner as named in the audit log, by id alone before the runners were fetched.
+    fn audit_runner(&self, runner_id: usize) -> String {
+        match self.runner_names.get(&runner_id) {
+            Some(name) => runner_target(name, runner_id),
+            None => format!("runner #{}", runner_id),
+        }
+    }
+
+    /// Appends a change that went through to the audit log, when one is configured.
+    /// Dry runs change nothing and aren't recorded.
+    fn audit(&mut self, target: &str, operation: &str) {
+        if self.config.dry_run {
+            return;
+        }
+        let Some(log) = self.audit_log.as_mut() else {
+            return;
+        };
+        let owner = match self.config.scope {
+            ApiScope::Org => &self.config.organization,
+            ApiScope::Enterprise => &self.config.enterprise,
+        };
+        if let Err(e) = log.record(owner, target, operation) {
+            let message = format!("Could not write to the audit log {}: {}", log.path().display(), e);
+            self.send_error(message);
+        }
+    }
+
+    /// Sends a change to GitHub. In dry-run mode `request` is only logged and the change is
+    /// answered as if it went through, without building the `call` request at all.
+    async fn mutate<T: Default, E>(&self, request: String, call: impl Future<Output = Result<T, E>>) -> Result<T, E> {
+        if self.config.dry_run {
+            info!("Dry run, not sent: {}", request);
+            return Ok(T::default());
+        }
+        call.await
+    }
+
+    /// Confirms a change that went through, shown briefly by the UI.
+    fn send_success(&self, message: String) {
+        let message = if self.config.dry_run { format!("Dry run: {}", message) } else { message };
+        self.tx.send(ApiMessage::Success(message))
+            .expect("Could not send success message to frontend");
+    }
+
+    fn send_error(&self, message: String) {
+        debug!("Backend error: {}", message);
+        self.tx.send(ApiMessage::Error(message))
+            .expect("Could not send error to frontend");
     }
 
     pub async fn run(&mut self) {
-        while let Some(message) = self.rx.recv().await {
-            match message {
-                    BackendMessage::FetchGroups => {
-                        let groups = self.get_runner_groups().await;
-                        self.tx.send(ApiMessage::RunnerGroupList(Box::new(groups)))
-                            .expect("Could not sent command to frontend worker");
-                    }
-                    BackendMessage::FetchRunners => {
-                        let runners = self.get_runners(None).await;
-                        self.tx.send(ApiMessage::RunnerList(Box::new(runners)))
-                            .expect("Could not send runner list to ui");
-                    }
-                    BackendMessage::AddLabel(runner_id, label) => {
-                        debug!("Updating label: {} for runner: {}", label, runner_id);
-                        let labels = vec![label];
-                        self.client.runners().add_label(runner_id, labels).await
-                            .expect("Could not add label");
-                        self.refresh_runners().await;
-                    }
-                    BackendMessage::DeleteLabel(runner_id, label) => {
-                        debug!("Removing label: {} for runner {}", label, runner_id);
-                        self.client.runners().remove_label(runner_id, label).await
-                            .expect("Could not remove label");
-                        self.refresh_runners().await;
+        loop {
+            // The lock is only held while waiting, a panic handling the message doesn't keep it.
+            let Some(message) = self.rx.lock().await.recv().await else {
+                break;
+            };
+            match self.handle_message(message).await {
+                Ok(()) => self.authenticated = true,
+                Err(e) if !self.authenticated && matches!(e.downcast_ref::<ClientError>(), Some(ClientError::Unauthorized)) => {
+                    self.tx.send(ApiMessage::InvalidToken(invalid_token_message(&self.config)))
+                        .expect("Could not send invalid token message to frontend");
+                }
+                Err(e) => self.send_error(format!("{:#}", e)),
+            }
+            if let Some(rate_limit) = self.client.rate_limit() {
+                self.tx.send(ApiMessage::RateLimit(rate_limit))
+                    .expect("Could not send rate limit to frontend");
+            }
+        }
+    }
+
+    async fn handle_message(&mut self, message: BackendMessage) -> Result<()> {
+        match message {
+            BackendMessage::FetchGroups { skip_cache } => {
+                let groups = self.get_runner_groups(skip_cache).await
+                    .context("Could not fetch runner groups")?;
+                self.tx.send(ApiMessage::RunnerGroupList(groups))
+                    .expect("Could not sent command to frontend worker");
+            }
+            BackendMessage::FetchRunners { skip_cache } => {
+                let runners = self.get_runners(Some(skip_cache)).await
+                    .context("Could not fetch runners")?;
+                self.tx.send(ApiMessage::RunnerList(runners))
+                    .expect("Could not send runner list to ui");
+            }
+            BackendMessage::AddLabel(runner_id, label) => {
+                debug!("Updating label: {} for runner: {}", label, runner_id);
+                let labels = vec![label.clone()];
+                self.mutate(format!("POST actions/runners/{}/labels {:?}", runner_id, labels), self.client.runners().add_label(runner_id, labels)).await
+                    .with_context(|| format!("Could not add label {}", label))?;
+                self.audit(&self.audit_runner(runner_id), &format!("add label {}", label));
+                self.tx.send(ApiMessage::LabelChanged(runner_id, label.clone(), true))
+                    .expect("Could not send label change to frontend");
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                self.send_success(format!("Added label '{}' to runner {}", label, self.runner_name(runner_id)));
+            }
+            BackendMessage::AddLabelBulk(runner_ids, label) => {
+                debug!("Adding label: {} to runners: {:?}", label, runner_ids);
+                let total = runner_ids.len();
+                let mut failed = vec![];
+                for runner_id in runner_ids {
+                    let request = format!("POST actions/runners/{}/labels [{:?}]", runner_id, label);
+                    match self.mutate(request, self.client.runners().add_label(runner_id, vec![label.clone()])).await {
+                        Ok(()) => {
+                            self.audit(&self.audit_runner(runner_id), &format!("add label {}", label));
+                            self.tx.send(ApiMessage::LabelChanged(runner_id, label.clone(), true))
+                                .expect("Could not send label change to frontend");
+                        }
+                        Err(e) => failed.push(format!("{}: {}", runner_id, e)),
                     }
-                    BackendMessage::ChangeGroup(runner_id, group_name) => {
-                        debug!("Changing group of runner {} to group {}", runner_id, group_name);
-                        let group = match self.client.runner_groups().get_all(false).await {
-                            Ok(response) => response.runner_groups.into_iter().find(|r|r.name == group_name).unwrap(),
-                            Err(e) => panic!("Error getting runner group {}: {}", group_name, e),
-                        };
-                        self.client.runner_groups().add_runner_to_group(runner_id, group.id).await
-                            .expect("Could not add runner to group");
-                        self.refresh_runners().await;
+                }
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                if !failed.is_empty() {
+                    return Err(anyhow!("Could not add label {} to some runners:\n{}", label, failed.join("\n")));
+                }
+                self.send_success(format!("Added label '{}' to {} runners", label, total));
+            }
+            BackendMessage::DeleteLabel(runner_id, label) => {
+                debug!("Removing label: {} for runner {}", label, runner_id);
+                self.mutate(format!("DELETE actions/runners/{}/labels/{}", runner_id, label), self.client.runners().remove_label(runner_id, label.clone())).await
+                    .with_context(|| format!("Could not remove label {}", label))?;
+                self.audit(&self.audit_runner(runner_id), &format!("remove label {}", label));
+                self.tx.send(ApiMessage::LabelChanged(runner_id, label.clone(), false))
+                    .expect("Could not send label change to frontend");
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                self.send_success(format!("Removed label '{}' from runner {}", label, self.runner_name(runner_id)));
+            }
+            BackendMessage::SetLabels(runner_id, labels) => {
+                debug!("Setting labels {:?} for runner {}", labels, runner_id);
+                let operation = format!("set labels {}", labels.join(", "));
+                self.mutate(format!("PUT actions/runners/{}/labels {:?}", runner_id, labels), self.client.runners().set_labels(runner_id, labels)).await
+                    .with_context(|| format!("Could not set the labels of runner {}", runner_id))?;
+                self.audit(&self.audit_runner(runner_id), &operation);
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                self.send_success(format!("Updated the labels of runner {}", self.runner_name(runner_id)));
+            }
+            BackendMessage::ChangeGroup(runner_id, group_name) => {
+                debug!("Changing group of runner {} to group {}", runner_id, group_name);
+                let group = self.client.runner_groups().get_all(false).await
+                    .with_context(|| format!("Error getting runner group {}", group_name))?
+                    .runner_groups
+                    .into_iter()
+                    .find(|r|r.name == group_name)
+                    .ok_or_else(|| anyhow!("There is no runner group named {}", group_name))?;
+                let request = format!("PUT actions/runner-groups/{}/runners/{}", group.id, runner_id);
+                self.mutate(request, self.client.runner_groups().add_runner_to_group(runner_id, group.id)).await
+                    .with_context(|| format!("Could not add runner to group {}", group_name))?;
+                self.audit(&self.audit_runner(runner_id), &format!("move to runner group {}", group_name));
+                self.client.invalidate_runners();
+                self.refresh_group(group.id).await?;
+                self.send_success(format!("Moved runner {} to group {}", self.runner_name(runner_id), group_name));
+            }
+            BackendMessage::RemoveFromGroup(runner_id) => {
+                debug!("Moving runner {} to the default group", runner_id);
+                let request = format!("PUT actions/runner-groups/<default>/runners/{}", runner_id);
+                self.mutate(request, self.client.runner_groups().move_runner_to_default(runner_id)).await
+                    .with_context(|| format!("Could not move runner {} to the default group", runner_id))?;
+                self.audit(&self.audit_runner(runner_id), "move to the default runner group");
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                self.send_success(format!("Moved runner {} to the default group", self.runner_name(runner_id)));
+            }
+            BackendMessage::AddRepoToGroup(repo_name, group_id) => {
+                debug!("Adding repo {} to group id {}", repo_name, group_id);
+                // Enterprise runner groups are shared with whole organizations instead.
+                let repo = match self.config.scope {
+                    ApiScope::Org => self.client.repos().get_repo(&self.config.organization, &repo_name).await,
+                    ApiScope::Enterprise => self.client.repos().get_org(&repo_name).await,
+                }.with_context(|| format!("Could not get repo {}", repo_name))?;
+                let request = format!("PUT actions/runner-groups/{}/{}/{}", group_id, self.config.scope.access_segment(), repo.id);
+                self.mutate(request, self.client.runner_groups().add_repo_access(group_id, repo.id)).await
+                    .with_context(|| format!("Could not add repo {} to group", repo_name))?;
+                self.audit(&format!("runner group #{}", group_id), &format!("grant access to {}", repo_name));
+                self.client.invalidate_groups();
+                self.tx.send(ApiMessage::Ok)
+                    .expect("Could not send response to frontend");
+                self.send_success(format!("Gave repository {} access to the group", repo_name));
+            }
+            BackendMessage::CreateRunnerGroup(runner_group) => {
+                debug!("Creating runner group {:?}", runner_group);
+                let name = runner_group.name.clone();
+                let request = format!("POST actions/runner-groups {:?}", runner_group);
+                self.mutate(request, self.client.runner_groups().create_runner_group(*runner_group).map_ok(drop)).await
+                    .context("Could not create runner group")?;
+                self.audit(&format!("runner group {}", name), "create");
+                self.client.invalidate_groups();
+                self.refresh_runners().await?;
+                self.send_success(format!("Created runner group {}", name));
+            },
+            BackendMessage::DeleteRunnerGroup(runner_group_id) => {
+                debug!("Deleting runner group {}", runner_group_id);
+                let name = self.client.runner_groups().get_all(false).await.ok()
+                    .and_then(|groups| groups.runner_groups.into_iter().find(|g| g.id == runner_group_id))
+                    .map_or_else(|| format!("#{}", runner_group_id), |g| g.name);
+                let request = format!("DELETE actions/runner-groups/{}", runner_group_id);
+                match self.mutate(request, self.client.runner_groups().delete_runner_group(runner_group_id)).await {
+                    Err(ClientError::Http(StatusCode::BAD_REQUEST)) => {
+                        return Err(anyhow!("Runner group {} can't be deleted, GitHub refuses to delete the default group", name));
                     }
-                    BackendMessage::AddRepoToGroup(repo_name, group_id) => {
-                        debug!("Adding repo {} to group id {}", repo_name, group_id);
-                        let repo = self.client.repos().get_repo(&self.config.organization, &repo_name).await
-                            .expect("Could not get repo");
-                        self.client.runner_groups().add_repo_access(group_id, repo.id).await
-                            .expect("Could not add repo to group");
-                        self.tx.send(ApiMessage::Ok)
-                            .expect("Could not send response to frontend");
+                    result => result.with_context(|| format!("Could not delete runner group {}", name))?,
+                }
+                self.audit(&format!("runner group #{}", runner_group_id), "delete");
+                // Its runners fall back to the default group.
+                self.client.invalidate_groups();
+                self.client.invalidate_runners();
+                self.refresh_groups().await?;
+                self.refresh_runners().await?;
+                self.send_success(format!("Deleted runner group {}", name));
+            }
+            BackendMessage::UpdateRunnerGroup(runner_group_id, patch) => {
+                debug!("Updating runner group {} with {:?}", runner_group_id, patch);
+                let group = if self.config.dry_run {
+                    info!("Dry run, not sent: PATCH actions/runner-groups/{} {:?}", runner_group_id, patch);
+                    self.dry_run_group_update(runner_group_id, *patch).await?
+                } else {
+                    let operation = format!("update {:?}", patch);
+                    let group = self.client.runner_groups().update_runner_group(runner_group_id, *patch).await
+                        .with_context(|| format!("Could not update runner group {}", runner_group_id))?;
+                    self.audit(&format!("runner group #{}", runner_group_id), &operation);
+                    group
+                };
+                self.client.invalidate_groups();
+                self.refresh_groups().await?;
+                self.tx.send(ApiMessage::GroupUpdated(Box::new(RunnerGroup::from(group))))
+                    .expect("Could not send updated group to frontend");
+            }
+            BackendMessage::GetGroupRepos(runner_group_id) => {
+                debug!("Getting group repos {}", runner_group_id);
+                let result = self.client.runner_groups().get_group_repos(runner_group_id).await
+                    .context("Could not get group repos")?;
+                debug!("Fetched repos {:?}", result.repositories);
+                self.tx.send(ApiMessage::GroupRepos(result.repositories))
+                    .expect("Could not send group repos response to frontend");
+            }
+            BackendMessage::GetGroupRunners(runner_group_id) => {
+                debug!("Getting group runners {}", runner_group_id);
+                let runners = self.client.runner_groups().get_runners(runner_group_id, true).await
+                    .context("Could not get group runners")?
+                    .runners
+                    .into_iter()
+                    .map(Runner::from)
+                    .collect();
+                self.tx.send(ApiMessage::GroupMembers(runners))
+                    .expect("Could not send group runners response to frontend");
+            }
+            BackendMessage::RemoveRepoFromGroup(group_id, repo_id) => {
+                debug!("Removing repo {} from group id {}", repo_id, group_id);
+                let request = format!("DELETE actions/runner-groups/{}/{}/{}", group_id, self.config.scope.access_segment(), repo_id);
+                self.mutate(request, self.client.runner_groups().remove_repo_access(group_id, repo_id)).await
+                    .context("Could not remove repo from group")?;
+                self.audit(&format!("runner group #{}", group_id), &format!("revoke access from #{}", repo_id));
+                self.client.invalidate_groups();
+                let result = self.client.runner_groups().get_group_repos(group_id).await
+                    .context("Could not get group repos")?;
+                self.tx.send(ApiMessage::GroupRepos(result.repositories))
+                    .expect("Could not send group repos response to frontend");
+            }
+            BackendMessage::DeleteRunner(runner_id) => {
+                debug!("Deleting runner {}", runner_id);
+                let name = self.runner_name(runner_id);
+                let deleted = match self.mutate(format!("DELETE actions/runners/{}", runner_id), self.client.runners().delete_runner(runner_id)).await {
+                    Err(ClientError::NotFound) => {
+                        self.send_error(format!("Runner {} was already removed", runner_id));
+                        false
                     }
-                    BackendMessage::CreateRunnerGroup(runner_group) => {
-                        debug!("Creating runner group {:?}", runner_group);
-                        self.client.runner_groups().create_runner_group(*runner_group).await
-                            .expect("Could not create runner group");
-                        self.refresh_runners().await;
-                    },
-                    BackendMessage::GetGroupRepos(runner_group_id) => {
-                        debug!("Getting group repos {}", runner_group_id);
-                        let result = self.client.runner_groups().get_group_repos(runner_group_id).await
-                            .expect("Could not get group repos");
-                        debug!("Fetched repos {:?}", result.repositories);
-                        self.tx.send(ApiMessage::GroupRepos(Box::new(result.repositories)))
-                            .expect("Could not send group repos response to frontend");
+                    result => {
+                        result.with_context(|| format!("Could not delete runner {}", runner_id))?;
+                        self.audit(&self.audit_runner(runner_id), "delete");
+                        true
                     }
+                };
+                self.client.invalidate_runners();
+                self.refresh_runners().await?;
+                if deleted {
+                    self.send_success(format!("Deleted runner {}", name));
+                }
+            }
+            BackendMessage::GetRunner(runner_id) => {
+                debug!("Fetching runner {}", runner_id);
+                let runner = match self.client.runners().get_one(runner_id).await {
+                    Err(ClientError::NotFound) => None,
+                    result => Some(Box::new(Runner::from(result.with_context(|| format!("Could not get runner {}", runner_id))?))),
+                };
+                self.tx.send(ApiMessage::RunnerDetails(runner_id, runner))
+                    .expect("Could not send runner details to frontend");
+            }
+            BackendMessage::CreateRegistrationToken => {
+                // A token is of no use without registering a runner, which a dry run must not do.
+                let result = if self.config.dry_run {
+                    info!("Dry run, not sent: POST actions/runners/registration-token");
+                    Err(String::from("Dry run: no registration token was created"))
+                } else {
+                    self.client.runners().create_registration_token().await
+                        .map_err(|e| format!("Could not create a registration token: {}", e))
+                };
+                if result.is_ok() {
+                    self.audit("registration token", "create");
                 }
+                self.tx.send(ApiMessage::RegistrationToken(result))
+                    .expect("Could not send registration token to frontend");
+            }
+            BackendMessage::GenerateJitConfig(name, group_id, labels) => {
+                debug!("Generating a JIT config for runner {} in group {}", name, group_id);
+                // The config registers a runner, which a dry run must not do.
+                let result = if self.config.dry_run {
+                    info!("Dry run, not sent: POST actions/runners/generate-jitconfig {} {:?}", name, labels);
+                    Err(String::from("Dry run: no JIT config was generated"))
+                } else {
+                    self.client.runners().generate_jitconfig(&name, group_id, labels.clone(), None).await
+                        .map(Box::new)
+                        .map_err(|e| format!("Could not generate a JIT config for runner {}: {}", name, e))
+                };
+                let generated = result.is_ok();
+                if generated {
+                    self.audit(&format!("runner {}", name), &format!("register just-in-time in runner group #{} with labels {}", group_id, labels.join(", ")));
+                }
+                self.tx.send(ApiMessage::JitConfig(result))
+                    .expect("Could not send JIT config to frontend");
+                if generated {
+                    self.client.invalidate_runners();
+                    self.refresh_runners().await?;
+                }
+            }
+            BackendMessage::FetchRunnerDownloads => {
+                let result = self.client.runners().download_urls().await
+                    .map_err(|e| format!("Could not list the runner downloads: {}", e));
+                self.tx.send(ApiMessage::RunnerDownloads(result))
+                    .expect("Could not send runner downloads to frontend");
+            }
+            BackendMessage::FetchRepos => {
+                if self.config.organization.is_empty() {
+                    return Err(anyhow!("Set `organization` to list the repositories of one of the enterprise organizations"));
+                }
+                let repos = self.client.repos().list_repos(&self.config.organization).await
+                    .context("Could not list repositories")?;
+                self.tx.send(ApiMessage::RepoList(repos))
+                    .expect("Could not send repositories to frontend");
+            }
+            BackendMessage::SwitchProfile(name) => {
+                debug!("Switching to profile {}", name);
+                if self.config.use_profile(&name) {
+                    self.use_client_for_config();
+                }
+                self.refresh_runners().await?;
+            }
+            BackendMessage::SwitchOrg(organization) => {
+                debug!("Switching to organization {}", organization);
+                self.config.organization = organization;
+                self.use_client_for_config();
+                // Cached responses are fine here, that's what makes switching back instant.
+                let runners = self.get_runners(Some(false)).await?;
+                self.tx.send(ApiMessage::RunnerList(runners))
+                    .expect("Could not send runner list to ui");
+            }
+            BackendMessage::RefreshGroup(group_id) => self.refresh_group(group_id).await?,
+            BackendMessage::FindDuplicateGroups(group_id) => {
+                debug!("Looking for duplicates of group {}", group_id);
+                let result = self.find_duplicate_groups(group_id).await
+                    .map_err(|e| e.to_string());
+                self.tx.send(ApiMessage::DuplicateGroups(result))
+                    .expect("Could not send duplicate groups to frontend");
+            }
+            BackendMessage::MergeGroups(from, into) => {
+                debug!("Merging group {} into group {}", from, into);
+                let result = self.merge_groups(from, into).await;
+                self.client.invalidate_groups();
+                self.client.invalidate_runners();
+                let refreshed = match self.refresh_groups().await {
+                    Ok(()) => self.refresh_group(into).await,
+                    Err(e) => Err(e),
+                };
+                self.tx.send(ApiMessage::GroupsMerged(result))
+                    .expect("Could not send merge result to frontend");
+                refreshed.context("Could not refresh groups after merging")?;
+            }
+            BackendMessage::ExportGroupsCsv => {
+                let result = match self.groups_with_repos().await {
+                    Ok(groups) => write_groups_csv(&self.config.organization, &groups)
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(format!("{:#}", e)),
+                };
+                self.tx.send(ApiMessage::GroupsExported(result))
+                    .expect("Could not send export result to frontend");
+            }
+            BackendMessage::FetchGroupAccess => {
+                let result = self.groups_with_repos().await
+                    .map_err(|e| format!("{:#}", e));
+                self.tx.send(ApiMessage::GroupAccess(result))
+                    .expect("Could not send group access to frontend");
+            }
+            BackendMessage::ExportMarkdown => {
+                let result = match self.get_runners(None).await {
+                    Ok(runners) => write_markdown_report(&self.config.organization, &runners)
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(format!("Could not fetch runners: {:#}", e)),
+                };
+                debug!("Markdown report export: {:?}", result);
+                self.tx.send(ApiMessage::Exported(result))
+                    .expect("Could not send export result to frontend");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::runner_group_json;
+
+    #[tokio::test]
+    async fn failing_api_is_reported_instead_of_panicking() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", mockito::Matcher::Any)
+            .with_status(500)
+            .create_async().await;
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("token"),
+            api_base: Some(server.url()),
+            server_error_retries: 0,
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, mut api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+
+        tx.send(BackendMessage::FetchRunners { skip_cache: false }).unwrap();
+        drop(tx);
+        worker.run().await;
+
+        match api_rx.try_recv() {
+            Ok(ApiMessage::Error(message)) => assert!(message.starts_with("Could not fetch runners"), "{}", message),
+            _ => panic!("expected an error message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_token_is_reported_with_the_required_scopes() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", mockito::Matcher::Any)
+            .with_status(401)
+            .create_async().await;
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("expired"),
+            api_base: Some(server.url()),
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, mut api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+
+        tx.send(BackendMessage::FetchRunners { skip_cache: false }).unwrap();
+        drop(tx);
+        worker.run().await;
+
+        match api_rx.try_recv() {
+            Ok(ApiMessage::InvalidToken(message)) => {
+                assert!(message.contains("organization acme"), "{}", message);
+                assert!(message.contains("admin:org") && message.contains("manage_runners:org"), "{}", message);
+            }
+            _ => panic!("expected an invalid token message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn runners_are_listed_once_and_ungrouped_ones_are_kept() {
+        let mut server = mockito::Server::new_async().await;
+        let group = |id: usize, name: &str| runner_group_json(id, name, "all", id == 1);
+        let runners = |ids: &[usize]| serde_json::json!({
+            "total_count": ids.len(),
+            "runners": ids.iter().map(|id| serde_json::json!({
+                "id": id, "name": format!("runner-{}", id), "os": "linux", "status": "online", "busy": false, "labels": [],
+            })).collect::<Vec<_>>(),
+        }).to_string();
+        server.mock("GET", "/orgs/acme/actions/runner-groups")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({ "total_count": 2, "runner_groups": [group(1, "Default"), group(2, "gpu")] }).to_string())
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runner-groups/1/runners")
+            .match_query(mockito::Matcher::Any)
+            .with_body(runners(&[1, 2]))
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runner-groups/2/runners")
+            .match_query(mockito::Matcher::Any)
+            .with_body(runners(&[2, 3]))
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runners")
+            .match_query(mockito::Matcher::Any)
+            .with_body(runners(&[1, 2, 3, 4]))
+            .create_async().await;
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("token"),
+            api_base: Some(server.url()),
+            ..Config::default()
+        };
+        let (_tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, _api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+
+        let runners = worker.get_runners(Some(true)).await.unwrap();
+
+        let listed: Vec<(usize, Option<&str>)> = runners.iter().map(|r| (r.id, r.group.as_deref())).collect();
+        assert_eq!(listed, vec![(1, Some("Default")), (2, Some("Default")), (3, Some("gpu")), (4, None)]);
+    }
+
+    #[tokio::test]
+    async fn dry_run_sends_no_mutation() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/orgs/acme/actions/runner-groups")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 1,
+                "runner_groups": [runner_group_json(1, "Default", "all", true)],
+            }).to_string())
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runner-groups/1/runners")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 1,
+                "runners": [{ "id": 7, "name": "runner-7", "os": "linux", "status": "online", "busy": false, "labels": [] }],
+            }).to_string())
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runners")
+            .match_query(mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 1,
+                "runners": [{ "id": 7, "name": "runner-7", "os": "linux", "status": "online", "busy": false, "labels": [] }],
+            }).to_string())
+            .create_async().await;
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("audit.jsonl");
+        let mut mutations = vec![];
+        for method in ["POST", "PUT", "PATCH", "DELETE"] {
+            mutations.push(server.mock(method, mockito::Matcher::Any).expect(0).create_async().await);
+        }
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("token"),
+            api_base: Some(server.url()),
+            dry_run: true,
+            audit_log: Some(audit_path.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, mut api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+
+        tx.send(BackendMessage::AddLabel(7, String::from("gpu"))).unwrap();
+        tx.send(BackendMessage::DeleteRunner(7)).unwrap();
+        tx.send(BackendMessage::UpdateRunnerGroup(1, Box::new(ApiRunnerGroupUpdate { name: Some(String::from("Renamed")), ..ApiRunnerGroupUpdate::default() }))).unwrap();
+        drop(tx);
+        worker.run().await;
+
+        for mock in &mutations {
+            mock.assert_async().await;
+        }
+        let mut successes = vec![];
+        let mut renamed = None;
+        while let Ok(message) = api_rx.try_recv() {
+            match message {
+                ApiMessage::Success(message) => successes.push(message),
+                ApiMessage::GroupUpdated(group) => renamed = Some(group.name),
+                ApiMessage::Error(message) => panic!("unexpected error: {}", message),
+                _ => {}
             }
         }
-}
\ No newline at end of file
+        assert_eq!(successes, vec!["Dry run: Added label 'gpu' to runner runner-7", "Dry run: Deleted runner runner-7"]);
+        assert_eq!(renamed.as_deref(), Some("Renamed"));
+        assert!(!audit_path.exists());
+    }
+
+    #[tokio::test]
+    async fn mutation_appends_one_line_to_the_audit_log() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", mockito::Matcher::Any)
+            .with_body(serde_json::json!({ "total_count": 0, "runner_groups": [], "runners": [] }).to_string())
+            .create_async().await;
+        let added = server.mock("POST", "/orgs/acme/actions/runners/7/labels")
+            .with_body(serde_json::json!({ "total_count": 1, "labels": [] }).to_string())
+            .create_async().await;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("token"),
+            api_base: Some(server.url()),
+            audit_log: Some(path.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, mut api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+        worker.runner_names.insert(7, String::from("runner-7"));
+
+        tx.send(BackendMessage::AddLabel(7, String::from("gpu"))).unwrap();
+        drop(tx);
+        worker.run().await;
+
+        added.assert_async().await;
+        while let Ok(message) = api_rx.try_recv() {
+            if let ApiMessage::Error(message) = message {
+                panic!("unexpected error: {}", message);
+            }
+        }
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", log);
+        assert!(log.ends_with('\n'));
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["organization"], "acme");
+        assert_eq!(entry["target"], "runner runner-7 (#7)");
+        assert_eq!(entry["operation"], "add label gpu");
+        let timestamp = entry["timestamp"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok(), "{}", timestamp);
+    }
+
+    #[tokio::test]
+    async fn deleted_group_is_named_in_the_success_message() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", mockito::Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 1,
+                "runner_groups": [runner_group_json(2, "gpu", "all", false)],
+                "runners": [],
+            }).to_string())
+            .create_async().await;
+        server.mock("DELETE", "/orgs/acme/actions/runner-groups/2")
+            .with_status(204)
+            .create_async().await;
+        let config = Config {
+            organization: String::from("acme"),
+            token: String::from("token"),
+            api_base: Some(server.url()),
+            ..Config::default()
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (api_tx, mut api_rx) = mpsc::unbounded_channel();
+        let mut worker = Worker::new(Arc::new(Mutex::new(rx)), api_tx, config);
+
+        tx.send(BackendMessage::DeleteRunnerGroup(2)).unwrap();
+        drop(tx);
+        worker.run().await;
+
+        let mut successes = vec![];
+        while let Ok(message) = api_rx.try_recv() {
+            match message {
+                ApiMessage::Success(message) => successes.push(message),
+                ApiMessage::Error(message) => panic!("unexpected error: {}", message),
+                _ => {}
+            }
+        }
+        assert_eq!(successes, vec!["Deleted runner group gpu"]);
+    }
+
+    #[test]
+    fn cache_name_tells_hosts_and_tokens_apart() {
+        let config = Config { organization: String::from("acme"), token: String::from("ghp_first"), ..Config::default() };
+        let name = Worker::cache_name(&config);
+        assert!(name.starts_with("api.github.com-acme-"), "{}", name);
+        assert!(!name.contains(&config.token));
+        assert_eq!(Worker::cache_name(&config.clone()), name);
+
+        let other_token = Config { token: String::from("ghp_second"), ..config.clone() };
+        assert_ne!(Worker::cache_name(&other_token), name);
+        let other_host = Config { api_base: Some(String::from("https://ghe.internal/api/v3")), ..config.clone() };
+        assert!(Worker::cache_name(&other_host).starts_with("ghe.internal-acme-"));
+    }
+
+    #[tokio::test]
+    async fn run_bounded_keeps_at_most_limit_futures_in_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+        let futures = (0..20).map(|i| {
+            let in_flight = Arc::clone(&in_flight);
+            let max_seen = Arc::clone(&max_seen);
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_seen.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(i)
+            }
+        });
+
+        let results = run_bounded(futures, 3).await.unwrap();
+
+        assert_eq!(results, (0..20).collect::<Vec<_>>());
+        assert_eq!(max_seen.load(Ordering::SeqCst), 3);
+    }
+}
@@ -1,31 +1,288 @@
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::ops::Deref;
-use anyhow::Result;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 use cli_log::*;
-use reqwest::header::HeaderMap;
-use reqwest::{Url};
+use reqwest::header::{HeaderMap, CACHE_CONTROL, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use std::error::Error;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use crate::utils::cache::Cache;
+use crate::utils::cache::{Cache, DEFAULT_TTL};
+use crate::utils::config::ApiScope;
+
+/// Retries of a rate limited request before giving up and returning the error response.
+const MAX_RETRIES: u32 = 4;
+/// Longest wait between retries, even if GitHub asks for more.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+/// Retries of a GET answered with a server error, unless configured otherwise.
+pub const DEFAULT_SERVER_ERROR_RETRIES: u32 = 3;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// API quota as reported by the `X-RateLimit-*` headers of the last response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Unix time when the quota is refilled.
+    pub reset: u64,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name)?.to_str().ok()?.parse::<u64>().ok();
+        Some(RateLimit {
+            limit: header("x-ratelimit-limit")? as u32,
+            remaining: header("x-ratelimit-remaining")? as u32,
+            reset: header("x-ratelimit-reset")?,
+        })
+    }
+}
+
+/// How long to wait before retrying, or `None` if the response isn't a rate limit.
+/// Primary limits are `403` with no quota left, secondary ones `403`/`429` with `Retry-After`.
+fn retry_wait(status: StatusCode, headers: &HeaderMap, attempt: u32, backoff_base: Duration) -> Option<Duration> {
+    let retry_after = headers.get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    let rate_limit = RateLimit::from_headers(headers);
+    let exhausted = rate_limit.is_some_and(|r| r.remaining == 0);
+    let limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && (exhausted || retry_after.is_some()));
+    if !limited {
+        return None;
+    }
+    let wait = match (retry_after, rate_limit) {
+        (Some(seconds), _) => Duration::from_secs(seconds),
+        (None, Some(r)) if exhausted => {
+            let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+            Duration::from_secs(r.reset.saturating_sub(now))
+        }
+        _ => backoff_base.saturating_mul(2u32.saturating_pow(attempt)),
+    };
+    Some(wait.min(MAX_RETRY_WAIT))
+}
+
+/// Exponential backoff for the given attempt plus up to half of it again at random, so
+/// requests failing together don't all come back at the same time.
+fn jittered_backoff(attempt: u32, backoff_base: Duration) -> Duration {
+    let backoff = backoff_base.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_WAIT);
+    let nanos = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().subsec_nanos();
+    let jitter = backoff.mul_f64(f64::from(nanos % 1000) / 2000.0);
+    (backoff + jitter).min(MAX_RETRY_WAIT)
+}
+
+/// Why a request failed, so callers can tell a bad token from a missing resource or a
+/// rate limit without digging into status codes.
+#[derive(Debug)]
+pub enum ClientError {
+    /// `401`: the token is missing, wrong or expired.
+    Unauthorized,
+    NotFound,
+    /// Still rate limited once the retries ran out, with the wait GitHub asked for if any.
+    RateLimited { retry_after: Option<Duration> },
+    /// Any other error status.
+    Http(StatusCode),
+    /// The request couldn't be sent or its response couldn't be read.
+    Transport(reqwest::Error),
+    /// The endpoint URL couldn't be built, e.g. from an invalid `api_base`.
+    InvalidUrl(String),
+    /// The organization has no default runner group to move a runner back to.
+    NoDefaultGroup,
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Unauthorized => write!(f, "GitHub rejected the token (401 Unauthorized)"),
+            ClientError::NotFound => write!(f, "Not found (404)"),
+            ClientError::RateLimited { retry_after: Some(wait) } => write!(f, "Rate limited by GitHub, try again in {}s", wait.as_secs()),
+            ClientError::RateLimited { retry_after: None } => write!(f, "Rate limited by GitHub"),
+            ClientError::Http(status) => write!(f, "GitHub answered {}", status),
+            ClientError::Transport(e) => write!(f, "{}", e),
+            ClientError::InvalidUrl(reason) => write!(f, "Invalid URL: {}", reason),
+            ClientError::NoDefaultGroup => write!(f, "The organization has no default runner group"),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            // The reqwest error itself is already shown by `Display`.
+            ClientError::Transport(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Transport(e)
+    }
+}
+
+/// Passes successful (and `304`) responses through and turns error statuses into the
+/// matching `ClientError`.
+fn check_status(response: Response) -> Result<Response, ClientError> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+    let headers = response.headers();
+    Err(match status {
+        StatusCode::UNAUTHORIZED => ClientError::Unauthorized,
+        StatusCode::NOT_FOUND => ClientError::NotFound,
+        _ if retry_wait(status, headers, 0, Duration::ZERO).is_some() => {
+            let retry_after = headers.get(RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .or_else(|| RateLimit::from_headers(headers).filter(|r| r.remaining == 0).map(|r| {
+                    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+                    r.reset.saturating_sub(now)
+                }))
+                .map(Duration::from_secs);
+            ClientError::RateLimited { retry_after }
+        }
+        _ => ClientError::Http(status),
+    })
+}
+
+fn join(base: &Url, path: &str) -> Result<Url, ClientError> {
+    base.join(path).map_err(|e| ClientError::InvalidUrl(e.to_string()))
+}
 
 pub struct Client {
     api_base: Url,
     client: Arc<reqwest::Client>,
     runners: Arc<Mutex<Cache<RunnersResponse>>>,
     runner_groups: Arc<Mutex<Cache<RunnersGroupResponse>>>,
+    /// Repository access lists, keyed by the group repositories URL and so by group id.
+    group_repos: Arc<Mutex<Cache<ApiRepositoriesResponse>>>,
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    backoff_base: Duration,
+    timeout: Duration,
+    /// Seconds cached responses stay fresh, runner status changes more often than groups.
+    runners_ttl: usize,
+    groups_ttl: usize,
+    /// Retries of a GET answered with a 5xx. Mutations are never retried on server errors.
+    server_error_retries: u32,
+    /// Whether `api_base` points at an organization or an enterprise.
+    scope: ApiScope,
 }
 
 impl Client {
-    pub fn new(api_base: &str, default_headers: HeaderMap) -> Result<Self> {
-        let api_base = Url::parse(api_base)?;
+    pub fn new(api_base: &str, default_headers: HeaderMap) -> Result<Self, ClientError> {
+        let api_base = Url::parse(api_base).map_err(|e| ClientError::InvalidUrl(e.to_string()))?;
         let client = Arc::new(reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
             .default_headers(default_headers).build()?);
         Ok(Client {
             api_base,
             client,
             runners: Arc::new(Mutex::new(Cache::new())),
-            runner_groups: Arc::new(Mutex::new(Cache::new())) })
+            runner_groups: Arc::new(Mutex::new(Cache::new())),
+            group_repos: Arc::new(Mutex::new(Cache::new())),
+            rate_limit: Arc::new(Mutex::new(None)),
+            backoff_base: Duration::from_secs(1),
+            timeout: DEFAULT_TIMEOUT,
+            runners_ttl: DEFAULT_TTL,
+            groups_ttl: DEFAULT_TTL,
+            server_error_retries: DEFAULT_SERVER_ERROR_RETRIES,
+            scope: ApiScope::Org,
+        })
+    }
+
+    /// Seconds the runner and runner group responses are served from cache, 300 by default.
+    pub fn with_cache_ttls(mut self, runners_ttl: usize, groups_ttl: usize) -> Self {
+        self.runners_ttl = runners_ttl;
+        self.groups_ttl = groups_ttl;
+        self
+    }
+
+    /// Times a GET answered with a 5xx is sent again, 3 by default.
+    pub fn with_server_error_retries(mut self, retries: u32) -> Self {
+        self.server_error_retries = retries;
+        self
+    }
+
+    /// Scope of `api_base`, organization by default. Enterprise runner groups grant access
+    /// to organizations instead of repositories.
+    pub fn with_scope(mut self, scope: ApiScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Gives up on requests that haven't completed after `timeout`, 30 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Quota left according to the last response, `None` before the first request.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Sends `request`, sleeping and retrying up to `MAX_RETRIES` times while GitHub answers
+    /// with a rate limit. Waits for `Retry-After` or the quota reset when given, and backs off
+    /// exponentially otherwise.
+    /// GETs answered with a server error are also retried, up to `server_error_retries` times
+    /// with a jittered backoff. Other methods aren't, a 5xx doesn't mean nothing was changed.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ClientError> {
+        let is_get = request.try_clone()
+            .and_then(|r| r.build().ok())
+            .is_some_and(|r| r.method() == Method::GET);
+        let mut attempt = 0;
+        let mut server_errors = 0;
+        loop {
+            // Streamed bodies can't be cloned for a retry, those are sent once.
+            let Some(current) = request.try_clone() else {
+                return check_status(request.timeout(self.timeout).send().await?);
+            };
+            let response = current.timeout(self.timeout).send().await?;
+            if let Some(rate_limit) = RateLimit::from_headers(response.headers()) {
+                *self.rate_limit.lock().unwrap() = Some(rate_limit);
+            }
+            let wait = retry_wait(response.status(), response.headers(), attempt, self.backoff_base);
+            match wait {
+                Some(wait) if attempt < MAX_RETRIES => {
+                    warn!("Rate limited by {}, retrying in {:?}", response.url(), wait);
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                None if is_get && response.status().is_server_error() && server_errors < self.server_error_retries => {
+                    let wait = jittered_backoff(server_errors, self.backoff_base);
+                    warn!("{} answered {}, retrying in {:?}", response.url(), response.status(), wait);
+                    tokio::time::sleep(wait).await;
+                    server_errors += 1;
+                }
+                _ => return check_status(response),
+            }
+        }
+    }
+
+    /// Keeps the runner and group caches in JSON files under `dir` so they survive restarts.
+    pub fn with_persistent_cache(mut self, dir: &Path) -> Self {
+        self.runners = Arc::new(Mutex::new(Cache::new_persistent(dir.join("runners.json"))));
+        self.runner_groups = Arc::new(Mutex::new(Cache::new_persistent(dir.join("runner_groups.json"))));
+        self.group_repos = Arc::new(Mutex::new(Cache::new_persistent(dir.join("group_repos.json"))));
+        self
+    }
+
+    /// Forgets the cached runner lists after a change to any runner.
+    pub fn invalidate_runners(&self) {
+        self.runners.lock().unwrap().invalidate_all();
+    }
+
+    /// Forgets the cached runner groups and their repository access lists after a group was
+    /// created, changed or removed, or a repository gained or lost access to it.
+    pub fn invalidate_groups(&self) {
+        self.runner_groups.lock().unwrap().invalidate_all();
+        self.group_repos.lock().unwrap().invalidate_all();
     }
 
     pub fn runners(&self) -> RunnersEndpoint {
@@ -39,11 +296,82 @@ impl Client {
     pub fn repos(&self) -> RepoEndpoint {
         RepoEndpoint(self)
     }
+
+    /// API root (e.g. `https://api.github.com/`), for endpoints outside the organization.
+    /// Keeps the path prefix of GitHub Enterprise Server URLs like `/api/v3/`.
+    fn api_root(&self) -> Result<Url, ClientError> {
+        join(&self.api_base, "../../")
+    }
+
+    /// GET through the given cache. Stale entries are revalidated with `If-None-Match` so an
+    /// unchanged resource costs a `304` instead of a full body.
+    async fn cached_get<T: Serialize + DeserializeOwned + Clone>(&self, cache: &Mutex<Cache<T>>, endpoint: Url, skip_cache: bool, ttl: usize) -> Result<T, ClientError> {
+        let key = endpoint.as_str().to_string();
+        if !skip_cache && let Some(result) = cache.lock().unwrap().get(&key) {
+            debug!("Cache hit: {}", endpoint);
+            return Ok(result.clone());
+        }
+        let etag = cache.lock().unwrap().etag(&key).map(str::to_string);
+        let mut request = self.client.get(endpoint.clone());
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        debug!("GET {}", endpoint);
+        let mut response = self.send(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some(result) = cache.lock().unwrap().revalidate(&key) {
+                debug!("Not modified: {}", endpoint);
+                return Ok(result.clone());
+            }
+            // Nothing to reuse, e.g. the entry was invalidated meanwhile or a proxy answered:
+            // ask again for the whole body.
+            debug!("Not modified but nothing cached, fetching again: {}", endpoint);
+            response = self.send(self.client.get(endpoint.clone()).header(CACHE_CONTROL, "no-cache")).await?;
+        }
+        let etag = response.headers().get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let response = response.json::<T>().await?;
+        cache.lock().unwrap().insert_with_ttl_and_etag(key, response.clone(), Some(ttl), etag);
+        Ok(response)
+    }
+
+    /// Follows `page=N` until every item reported by `total_count` has been fetched,
+    /// merging all pages into the first response. Each page is cached on its own.
+    async fn paginated_get<T: Paginated + Serialize + DeserializeOwned + Clone>(&self, cache: &Mutex<Cache<T>>, endpoint: Url, skip_cache: bool, ttl: usize) -> Result<T, ClientError> {
+        let page_url = |page: usize| {
+            let mut url = endpoint.clone();
+            url.query_pairs_mut()
+                .append_pair("per_page", &PAGE_SIZE.to_string())
+                .append_pair("page", &page.to_string());
+            url
+        };
+        let mut result = self.cached_get(cache, page_url(1), skip_cache, ttl).await?;
+        let mut page = 1;
+        while result.len() < result.total_count() {
+            page += 1;
+            let next = self.cached_get(cache, page_url(page), skip_cache, ttl).await?;
+            if next.len() == 0 {
+                break;
+            }
+            result.extend(next);
+        }
+        Ok(result)
+    }
+}
+
+const PAGE_SIZE: usize = 100;
+
+/// List responses that GitHub splits in pages.
+trait Paginated {
+    fn total_count(&self) -> usize;
+    fn len(&self) -> usize;
+    fn extend(&mut self, page: Self);
 }
 
 trait CustomEndpoint {
-    fn endpoint(&self, base_url: &Url, path: &str) -> Result<Url> {
-        Ok(base_url.join(path)?)
+    fn endpoint(&self, base_url: &Url, path: &str) -> Result<Url, ClientError> {
+        join(base_url, path)
     }
 }
 
@@ -52,9 +380,20 @@ pub struct LabelsBody {
     labels: Vec<String>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
+struct JitConfigBody<'a> {
+    name: &'a str,
+    runner_group_id: usize,
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    work_folder: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiRepository {
     pub id: usize,
+    /// Organizations are read into this struct too at the enterprise scope, named by their login.
+    #[serde(alias = "login")]
     pub name: String,
 }
 
@@ -64,20 +403,63 @@ impl Display for ApiRepository {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ApiRepositoriesResponse {
     pub total_count: usize,
+    #[serde(alias = "organizations")]
     pub repositories: Vec<ApiRepository>,
 }
 
+impl Paginated for ApiRepositoriesResponse {
+    fn total_count(&self) -> usize {
+        self.total_count
+    }
+
+    fn len(&self) -> usize {
+        self.repositories.len()
+    }
+
+    fn extend(&mut self, page: Self) {
+        self.repositories.extend(page.repositories);
+    }
+}
+
 pub struct RepoEndpoint<'c>(&'c Client);
 impl CustomEndpoint for RepoEndpoint<'_> {}
 
 impl <'c> RepoEndpoint<'c> {
-    pub async fn get_repo(&self, org: &str, repo: &str) -> Result<ApiRepository>{
-        let endpoint = self.0.api_base.join(&format!("/repos/{}/{}", org, repo))?;
+    pub async fn get_repo(&self, org: &str, repo: &str) -> Result<ApiRepository, ClientError>{
+        let endpoint = join(&self.0.api_root()?, &format!("repos/{}/{}", org, repo))?;
+        debug!("GET {}", endpoint);
+        Ok(self.0.send(self.0.client.get(endpoint)).await?.json::<ApiRepository>().await?)
+    }
+
+    /// The organization itself, e.g. to give it access to an enterprise runner group.
+    pub async fn get_org(&self, org: &str) -> Result<ApiRepository, ClientError> {
+        let endpoint = join(&self.0.api_root()?, &format!("orgs/{}", org))?;
         debug!("GET {}", endpoint);
-        Ok(self.0.client.get(endpoint).send().await?.json::<ApiRepository>().await?)
+        Ok(self.0.send(self.0.client.get(endpoint)).await?.json::<ApiRepository>().await?)
+    }
+
+    /// Every repository of the organization, following `page=N` until a short page.
+    pub async fn list_repos(&self, org: &str) -> Result<Vec<ApiRepository>, ClientError> {
+        let endpoint = join(&self.0.api_root()?, &format!("orgs/{}/repos", org))?;
+        let mut repos = vec![];
+        for page in 1.. {
+            let mut url = endpoint.clone();
+            url.query_pairs_mut()
+                .append_pair("per_page", &PAGE_SIZE.to_string())
+                .append_pair("page", &page.to_string());
+            debug!("GET {}", url);
+            let page_repos = self.0.send(self.0.client.get(url)).await?
+                .json::<Vec<ApiRepository>>().await?;
+            let last_page = page_repos.len() < PAGE_SIZE;
+            repos.extend(page_repos);
+            if last_page {
+                break;
+            }
+        }
+        Ok(repos)
     }
 }
 
@@ -86,35 +468,141 @@ pub struct RunnersEndpoint<'c>(&'c Client);
 impl CustomEndpoint for RunnersEndpoint<'_> {}
 
 impl<'c> RunnersEndpoint<'c> {
-    pub async fn get_all(&self) -> Result<RunnersResponse> {
+    /// Every runner of the organization (or enterprise), whatever its group.
+    pub async fn get_all(&self, skip_cache: bool) -> Result<RunnersResponse, ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, "actions/runners")?;
+        self.0.paginated_get(&self.0.runners, endpoint, skip_cache, self.0.runners_ttl).await
+    }
+
+    /// Current state of a single runner, always fetched from the server.
+    pub async fn get_one(&self, id: usize) -> Result<ApiRunner, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runners/{}", id))?;
         debug!("GET {}", endpoint);
-        Ok(self.0.client.get(endpoint).send().await?.json::<RunnersResponse>().await?)
+        let runner = self.0.send(self.0.client.get(endpoint)).await?
+            .json::<ApiRunner>().await?;
+        Ok(runner)
     }
 
-    pub async fn add_label(&self, id: usize, labels: Vec<String>) -> Result<()> {
+    pub async fn add_label(&self, id: usize, labels: Vec<String>) -> Result<(), ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runners/{}/labels", id))?;
         debug!("POST {}", endpoint);
         let body = LabelsBody { labels };
-        self.0.client.post(endpoint).json(&body).send().await?.error_for_status()?;
+        self.0.send(self.0.client.post(endpoint).json(&body)).await?;
         Ok(())
     }
 
-    pub async fn remove_label(&self, id: usize, label: String) -> Result<()> {
+    /// Replaces every custom label of the runner with `labels`.
+    pub async fn set_labels(&self, id: usize, labels: Vec<String>) -> Result<(), ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runners/{}/labels", id))?;
+        debug!("PUT {}", endpoint);
+        let body = LabelsBody { labels };
+        self.0.send(self.0.client.put(endpoint).json(&body)).await?;
+        Ok(())
+    }
+
+    pub async fn remove_label(&self, id: usize, label: String) -> Result<(), ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runners/{}/labels/{}", id, label))?;
         debug!("DELETE {}", endpoint);
-        self.0.client.delete(endpoint).send().await?.error_for_status()?;
+        self.0.send(self.0.client.delete(endpoint)).await?;
+        Ok(())
+    }
+
+    /// Token for `config.sh` when registering a new self-hosted runner. Valid for one hour.
+    pub async fn create_registration_token(&self) -> Result<RegistrationToken, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, "actions/runners/registration-token")?;
+        debug!("POST {}", endpoint);
+        Ok(self.0.send(self.0.client.post(endpoint)).await?.json::<RegistrationToken>().await?)
+    }
+
+    /// Registers a runner that takes a single job and returns the config to start it with,
+    /// `run.sh --jitconfig <encoded_jit_config>`. `work_folder` defaults to `_work`.
+    pub async fn generate_jitconfig(&self, name: &str, group_id: usize, labels: Vec<String>, work_folder: Option<String>) -> Result<JitConfig, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, "actions/runners/generate-jitconfig")?;
+        debug!("POST {}", endpoint);
+        let body = JitConfigBody { name, runner_group_id: group_id, labels, work_folder };
+        Ok(self.0.send(self.0.client.post(endpoint).json(&body)).await?.json::<JitConfig>().await?)
+    }
+
+    /// Runner application packages that can be installed on a new self-hosted runner.
+    pub async fn download_urls(&self) -> Result<Vec<RunnerApplication>, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, "actions/runners/downloads")?;
+        debug!("GET {}", endpoint);
+        Ok(self.0.send(self.0.client.get(endpoint)).await?.json::<Vec<RunnerApplication>>().await?)
+    }
+
+    pub async fn delete_runner(&self, id: usize) -> Result<(), ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runners/{}", id))?;
+        debug!("DELETE {}", endpoint);
+        self.0.send(self.0.client.delete(endpoint)).await?;
         Ok(())
     }
 }
 
+/// Runner package for one platform, e.g. `linux`/`x64`.
 #[derive(Debug, Clone, Deserialize)]
+pub struct RunnerApplication {
+    pub os: String,
+    pub architecture: String,
+    pub download_url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub sha256_checksum: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct JitConfig {
+    /// The runner registered for the config, offline until started with it.
+    pub runner: ApiRunner,
+    pub encoded_jit_config: String,
+}
+
+// The config holds the runner's credentials, like a registration token.
+impl std::fmt::Debug for JitConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JitConfig")
+            .field("runner", &self.runner)
+            .field("encoded_jit_config", &"***")
+            .finish()
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct RegistrationToken {
+    pub token: String,
+    pub expires_at: String,
+}
+
+// Keeps the secret out of logs.
+impl std::fmt::Debug for RegistrationToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistrationToken")
+            .field("token", &"***")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunnersGroupResponse {
     pub total_count: usize,
     pub runner_groups: Vec<ApiRunnerGroup>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Paginated for RunnersGroupResponse {
+    fn total_count(&self) -> usize {
+        self.total_count
+    }
+
+    fn len(&self) -> usize {
+        self.runner_groups.len()
+    }
+
+    fn extend(&mut self, page: Self) {
+        self.runner_groups.extend(page.runner_groups);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum RunnerGroupVisibility {
     #[serde(rename = "selected")]
     Selected,
@@ -122,19 +610,40 @@ pub enum RunnerGroupVisibility {
     All,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ApiRunnerGroup {
     pub id: usize,
     pub name: String,
     pub visibility: RunnerGroupVisibility,
-    default: bool,
-    selected_repositories_url: Option<String>,
-    runners_url: String,
-    inherited: bool,
-    allows_public_repositories: bool,
-    restricted_to_workflows: bool,
-    selected_workflows: Vec<String>,
-    workflow_restrictions_read_only: bool,
+    pub default: bool,
+    /// Only sent for groups visible to selected repositories.
+    pub selected_repositories_url: Option<String>,
+    pub runners_url: String,
+    /// Not sent for enterprise runner groups.
+    #[serde(default)]
+    pub inherited: bool,
+    pub allows_public_repositories: bool,
+    pub restricted_to_workflows: bool,
+    pub selected_workflows: Vec<String>,
+    pub workflow_restrictions_read_only: bool,
+}
+
+/// An organization runner group as GitHub sends it, unrestricted and with no URLs to follow.
+#[cfg(test)]
+pub fn runner_group_json(id: usize, name: &str, visibility: &str, default: bool) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "name": name,
+        "visibility": visibility,
+        "default": default,
+        "selected_repositories_url": null,
+        "runners_url": "",
+        "inherited": false,
+        "allows_public_repositories": false,
+        "restricted_to_workflows": false,
+        "selected_workflows": [],
+        "workflow_restrictions_read_only": false,
+    })
 }
 
 #[derive(Debug, Serialize)]
@@ -145,65 +654,92 @@ pub struct ApiRunnerGroupCreate {
     pub runners: Vec<usize>,
 }
 
+/// Fields left as `None` are not sent, so GitHub keeps their current value.
+#[derive(Debug, Default, Serialize)]
+pub struct ApiRunnerGroupUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<RunnerGroupVisibility>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restricted_to_workflows: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected_workflows: Option<Vec<String>>,
+}
+
 pub struct RunnersGroupsEndpoint<'c>(&'c Client);
 impl CustomEndpoint for RunnersGroupsEndpoint<'_> {}
 impl<'c> RunnersGroupsEndpoint<'c> {
-    pub async fn get_all(&self, skip_cache: bool) -> Result<RunnersGroupResponse> {
+    pub async fn get_all(&self, skip_cache: bool) -> Result<RunnersGroupResponse, ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, "actions/runner-groups")?;
-        let key = endpoint.as_str().to_string();
-        if !skip_cache {
-            if let Some(result) = self.0.runner_groups.lock().unwrap().get(&key) {
-                debug!("Cache hit: {}", endpoint);
-                return Ok(result.clone());
-            }
-        }
-        debug!("GET {}", endpoint);
-        let response = self.0.client.get(endpoint).send().await?.json::<RunnersGroupResponse>().await?;
-        let response_clone = response.clone();
-        self.0.runner_groups.lock().unwrap().insert(key.to_string(), response);
-        Ok(response_clone)
+        self.0.paginated_get(&self.0.runner_groups, endpoint, skip_cache, self.0.groups_ttl).await
     }
 
-    pub async fn get_runners(&self, group_id: usize, skip_cache: bool) -> Result<RunnersResponse> {
+    pub async fn get_runners(&self, group_id: usize, skip_cache: bool) -> Result<RunnersResponse, ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}/runners", group_id))?;
-        let key = endpoint.as_str().to_string();
-        if !skip_cache {
-            if let Some(result) = self.0.runners.lock().unwrap().get(&key) {
-                debug!("Cache hit: {}", endpoint);
-                return Ok(result.clone())
-            }
-        }
-        debug!("GET {}", endpoint);
-        let response = self.0.client.get(endpoint).send().await?.json::<RunnersResponse>().await?;
-        let response_clone = response.clone();
-        self.0.runners.lock().unwrap().insert(key.to_string(), response);
-        Ok(response_clone)
+        self.0.paginated_get(&self.0.runners, endpoint, skip_cache, self.0.runners_ttl).await
     }
 
-    pub async fn create_runner_group(&self, runner_group: ApiRunnerGroupCreate) -> Result<ApiRunnerGroup> {
+    pub async fn create_runner_group(&self, runner_group: ApiRunnerGroupCreate) -> Result<ApiRunnerGroup, ClientError> {
         let endpoint = self.endpoint(&self.0.api_base, "actions/runner-groups")?;
         debug!("POST {} : {:?}", endpoint, runner_group);
-        Ok(self.0.client.post(endpoint).json(&runner_group).send().await?.json::<ApiRunnerGroup>().await?)
+        Ok(self.0.send(self.0.client.post(endpoint).json(&runner_group)).await?.json::<ApiRunnerGroup>().await?)
+    }
+
+    pub async fn update_runner_group(&self, runner_group_id: usize, patch: ApiRunnerGroupUpdate) -> Result<ApiRunnerGroup, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}", runner_group_id))?;
+        debug!("PATCH {} : {:?}", endpoint, patch);
+        Ok(self.0.send(self.0.client.patch(endpoint).json(&patch)).await?.json::<ApiRunnerGroup>().await?)
+    }
+
+    pub async fn delete_runner_group(&self, runner_group_id: usize) -> Result<(), ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}", runner_group_id))?;
+        debug!("DELETE {}", endpoint);
+        self.0.send(self.0.client.delete(endpoint)).await?;
+        Ok(())
     }
 
-    pub async fn add_runner_to_group(&self, runner_id: usize, runner_group_id: usize) -> Result<()>{
+    pub async fn add_runner_to_group(&self, runner_id: usize, runner_group_id: usize) -> Result<(), ClientError>{
         let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}/runners/{}", runner_group_id, runner_id))?;
         debug!("PUT {}", endpoint);
-        self.0.client.put(endpoint).send().await?.error_for_status()?;
+        self.0.send(self.0.client.put(endpoint)).await?;
         Ok(())
     }
 
-    pub async fn add_repo_access(&self, runner_group_id: usize, repo_id: usize) -> Result<()> {
-        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}/repositories/{}", runner_group_id, repo_id))?;
+    /// Moves a runner back to the org default group. GitHub has no "remove from group"
+    /// endpoint, the runner has to be added to the default group instead.
+    pub async fn move_runner_to_default(&self, runner_id: usize) -> Result<(), ClientError> {
+        let default_group = self.get_all(false).await?
+            .runner_groups
+            .into_iter()
+            .find(|group| group.default)
+            .ok_or(ClientError::NoDefaultGroup)?;
+        self.add_runner_to_group(runner_id, default_group.id).await
+    }
+
+    fn access_path(&self, runner_group_id: usize) -> String {
+        format!("actions/runner-groups/{}/{}", runner_group_id, self.0.scope.access_segment())
+    }
+
+    pub async fn add_repo_access(&self, runner_group_id: usize, repo_id: usize) -> Result<(), ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("{}/{}", self.access_path(runner_group_id), repo_id))?;
         debug!("PUT {}", endpoint);
-        self.0.client.put(endpoint).send().await?.error_for_status()?;
+        self.0.send(self.0.client.put(endpoint)).await?;
         Ok(())
     }
 
-    pub async fn get_group_repos(&self, runner_group_id: usize) -> Result<ApiRepositoriesResponse> {
-        let endpoint = self.endpoint(&self.0.api_base, &format!("actions/runner-groups/{}/repositories", runner_group_id))?;
-        debug!("GET {}", endpoint);
-        Ok(self.0.client.get(endpoint).send().await?.json::<ApiRepositoriesResponse>().await?)
+    pub async fn remove_repo_access(&self, runner_group_id: usize, repo_id: usize) -> Result<(), ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &format!("{}/{}", self.access_path(runner_group_id), repo_id))?;
+        debug!("DELETE {}", endpoint);
+        self.0.send(self.0.client.delete(endpoint)).await?;
+        Ok(())
+    }
+
+    /// Repositories allowed to use the group, every page of them.
+    /// At the enterprise scope these are the organizations allowed instead.
+    pub async fn get_group_repos(&self, runner_group_id: usize) -> Result<ApiRepositoriesResponse, ClientError> {
+        let endpoint = self.endpoint(&self.0.api_base, &self.access_path(runner_group_id))?;
+        self.0.paginated_get(&self.0.group_repos, endpoint, false, self.0.groups_ttl).await
     }
 
 }
@@ -232,4 +768,465 @@ pub struct ApiRunner {
 pub struct RunnersResponse{
     pub total_count: usize,
     pub runners: Vec<ApiRunner>
-}
\ No newline at end of file
+}
+
+impl Paginated for RunnersResponse {
+    fn total_count(&self) -> usize {
+        self.total_count
+    }
+
+    fn len(&self) -> usize {
+        self.runners.len()
+    }
+
+    fn extend(&mut self, page: Self) {
+        self.runners.extend(page.runners);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+
+    fn runners_page(ids: std::ops::Range<usize>, total_count: usize) -> String {
+        let runners: Vec<serde_json::Value> = ids.map(|id| serde_json::json!({
+            "id": id,
+            "name": format!("runner-{}", id),
+            "os": "linux",
+            "status": "online",
+            "busy": false,
+            "labels": [],
+        })).collect();
+        serde_json::json!({ "total_count": total_count, "runners": runners }).to_string()
+    }
+
+    #[tokio::test]
+    async fn stale_entry_is_revalidated_with_its_etag() {
+        let mut server = mockito::Server::new_async().await;
+        let not_modified = server.mock("GET", "/orgs/acme/thing")
+            .match_header("if-none-match", "\"abc\"")
+            .with_status(304)
+            .create_async().await;
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let endpoint = join(&client.api_base, "thing").unwrap();
+        let cache = Mutex::new(Cache::new());
+        cache.lock().unwrap().insert_with_ttl_and_etag(endpoint.to_string(), serde_json::json!({ "n": 1 }), Some(0), Some(String::from("\"abc\"")));
+
+        let value: serde_json::Value = client.cached_get(&cache, endpoint, false, 60).await.unwrap();
+
+        assert_eq!(value, serde_json::json!({ "n": 1 }));
+        not_modified.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn not_modified_without_a_cached_entry_fetches_again() {
+        let mut server = mockito::Server::new_async().await;
+        let not_modified = server.mock("GET", "/orgs/acme/thing")
+            .match_header("cache-control", Matcher::Missing)
+            .with_status(304)
+            .create_async().await;
+        let full = server.mock("GET", "/orgs/acme/thing")
+            .match_header("cache-control", "no-cache")
+            .with_header("etag", "\"def\"")
+            .with_body(serde_json::json!({ "n": 2 }).to_string())
+            .create_async().await;
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let endpoint = join(&client.api_base, "thing").unwrap();
+        let cache = Mutex::new(Cache::new());
+
+        let value: serde_json::Value = client.cached_get(&cache, endpoint.clone(), false, 60).await.unwrap();
+
+        assert_eq!(value, serde_json::json!({ "n": 2 }));
+        assert_eq!(cache.lock().unwrap().etag(endpoint.as_str()), Some("\"def\""));
+        not_modified.assert_async().await;
+        full.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_runners_merges_every_page() {
+        let mut server = mockito::Server::new_async().await;
+        let path = "/orgs/acme/actions/runner-groups/1/runners";
+        let first = server.mock("GET", path)
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("per_page".into(), "100".into()),
+                Matcher::UrlEncoded("page".into(), "1".into()),
+            ]))
+            .with_body(runners_page(0..100, 130))
+            .create_async().await;
+        let second = server.mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_body(runners_page(100..130, 130))
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let response = client.runner_groups().get_runners(1, true).await.unwrap();
+
+        assert_eq!(response.runners.len(), 130);
+        assert_eq!(response.runners.last().unwrap().id, 129);
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn pagination_stops_on_an_empty_page() {
+        let mut server = mockito::Server::new_async().await;
+        let path = "/orgs/acme/actions/runner-groups/1/runners";
+        server.mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+            .with_body(runners_page(0..2, 5))
+            .create_async().await;
+        let empty = server.mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_body(runners_page(0..0, 5))
+            .expect(1)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let response = client.runner_groups().get_runners(1, true).await.unwrap();
+
+        assert_eq!(response.runners.len(), 2);
+        empty.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn download_urls_are_deserialized() {
+        let mut server = mockito::Server::new_async().await;
+        let downloads = server.mock("GET", "/orgs/acme/actions/runners/downloads")
+            .with_body(serde_json::json!([
+                {
+                    "os": "linux",
+                    "architecture": "x64",
+                    "download_url": "https://github.com/actions/runner/releases/download/v2.320.0/actions-runner-linux-x64-2.320.0.tar.gz",
+                    "filename": "actions-runner-linux-x64-2.320.0.tar.gz",
+                    "sha256_checksum": "93ac1b7c",
+                },
+                {
+                    "os": "win",
+                    "architecture": "arm64",
+                    "download_url": "https://github.com/actions/runner/releases/download/v2.320.0/actions-runner-win-arm64-2.320.0.zip",
+                    "filename": "actions-runner-win-arm64-2.320.0.zip",
+                },
+            ]).to_string())
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let apps = client.runners().download_urls().await.unwrap();
+
+        assert_eq!(apps.len(), 2);
+        assert_eq!((apps[0].os.as_str(), apps[0].architecture.as_str()), ("linux", "x64"));
+        assert_eq!(apps[0].filename, "actions-runner-linux-x64-2.320.0.tar.gz");
+        assert!(apps[0].download_url.ends_with(&apps[0].filename));
+        assert_eq!(apps[0].sha256_checksum.as_deref(), Some("93ac1b7c"));
+        assert_eq!(apps[1].sha256_checksum, None);
+        downloads.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn jit_config_is_generated_for_the_group_and_kept_out_of_debug_output() {
+        let mut server = mockito::Server::new_async().await;
+        let generate = server.mock("POST", "/orgs/acme/actions/runners/generate-jitconfig")
+            .match_body(Matcher::Json(serde_json::json!({
+                "name": "ephemeral-01",
+                "runner_group_id": 2,
+                "labels": ["self-hosted", "gpu"],
+            })))
+            .with_status(201)
+            .with_body(serde_json::json!({
+                "runner": {
+                    "id": 23, "name": "ephemeral-01", "os": "unknown", "status": "offline", "busy": false,
+                    "labels": [{ "id": 1, "name": "self-hosted", "type": "read-only" }],
+                },
+                "encoded_jit_config": "abc123secret==",
+            }).to_string())
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let labels = vec![String::from("self-hosted"), String::from("gpu")];
+        let config = client.runners().generate_jitconfig("ephemeral-01", 2, labels, None).await.unwrap();
+
+        assert_eq!(config.runner.id, 23);
+        assert_eq!(config.encoded_jit_config, "abc123secret==");
+        assert!(!format!("{:?}", config).contains("abc123secret"));
+        generate.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn list_repos_reads_until_a_short_page() {
+        let mut server = mockito::Server::new_async().await;
+        let repos = |ids: std::ops::Range<usize>| serde_json::Value::Array(
+            ids.map(|id| serde_json::json!({ "id": id, "name": format!("repo-{}", id) })).collect()
+        ).to_string();
+        server.mock("GET", "/api/v3/orgs/acme/repos")
+            .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+            .with_body(repos(0..100))
+            .create_async().await;
+        server.mock("GET", "/api/v3/orgs/acme/repos")
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_body(repos(100..101))
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/api/v3/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let repos = client.repos().list_repos("acme").await.unwrap();
+
+        assert_eq!(repos.len(), 101);
+    }
+
+    #[tokio::test]
+    async fn move_runner_to_default_adds_it_to_the_default_group() {
+        let mut server = mockito::Server::new_async().await;
+        let group = |id: usize, name: &str, default: bool| runner_group_json(id, name, "all", default);
+        server.mock("GET", "/orgs/acme/actions/runner-groups")
+            .match_query(Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 2,
+                "runner_groups": [group(2, "gpu", false), group(1, "Default", true)],
+            }).to_string())
+            .create_async().await;
+        let put = server.mock("PUT", "/orgs/acme/actions/runner-groups/1/runners/42")
+            .with_status(204)
+            .expect(1)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        client.runner_groups().move_runner_to_default(42).await.unwrap();
+
+        put.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_one_fetches_a_single_runner_and_reports_missing_ones() {
+        let mut server = mockito::Server::new_async().await;
+        server.mock("GET", "/orgs/acme/actions/runners/7")
+            .with_body(serde_json::json!({
+                "id": 7,
+                "name": "runner-7",
+                "os": "linux",
+                "status": "online",
+                "busy": true,
+                "labels": [],
+            }).to_string())
+            .create_async().await;
+        server.mock("GET", "/orgs/acme/actions/runners/8")
+            .with_status(404)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        let runner = client.runners().get_one(7).await.unwrap();
+        assert_eq!((runner.id, runner.name.as_str(), runner.busy), (7, "runner-7", true));
+
+        let err = client.runners().get_one(8).await.unwrap_err();
+        assert!(matches!(err, ClientError::NotFound));
+    }
+
+    #[tokio::test]
+    async fn group_repos_are_paginated_and_cached_until_groups_change() {
+        let mut server = mockito::Server::new_async().await;
+        let path = "/orgs/acme/actions/runner-groups/3/repositories";
+        let page = |ids: std::ops::Range<usize>| {
+            let repos: Vec<serde_json::Value> = ids.map(|id| serde_json::json!({ "id": id, "name": format!("repo-{}", id) })).collect();
+            serde_json::json!({ "total_count": 120, "repositories": repos }).to_string()
+        };
+        let first = server.mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "1".into()))
+            .with_body(page(0..100))
+            .expect(2)
+            .create_async().await;
+        let second = server.mock("GET", path)
+            .match_query(Matcher::UrlEncoded("page".into(), "2".into()))
+            .with_body(page(100..120))
+            .expect(2)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        assert_eq!(client.runner_groups().get_group_repos(3).await.unwrap().repositories.len(), 120);
+        assert_eq!(client.runner_groups().get_group_repos(3).await.unwrap().repositories.len(), 120);
+        client.invalidate_groups();
+        assert_eq!(client.runner_groups().get_group_repos(3).await.unwrap().repositories.len(), 120);
+
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn enterprise_scope_uses_the_enterprise_endpoints() {
+        let mut server = mockito::Server::new_async().await;
+        let groups = server.mock("GET", "/enterprises/acme-corp/actions/runner-groups")
+            .match_query(Matcher::Any)
+            .with_body(serde_json::json!({
+                "total_count": 1,
+                "runner_groups": [{
+                    "id": 3,
+                    "name": "shared",
+                    "visibility": "selected",
+                    "default": false,
+                    "selected_organizations_url": "",
+                    "runners_url": "",
+                    "allows_public_repositories": false,
+                    "restricted_to_workflows": false,
+                    "selected_workflows": [],
+                    "workflow_restrictions_read_only": false,
+                }],
+            }).to_string())
+            .create_async().await;
+        let runners = server.mock("GET", "/enterprises/acme-corp/actions/runner-groups/3/runners")
+            .match_query(Matcher::Any)
+            .with_body(runners_page(0..1, 1))
+            .create_async().await;
+        let access = server.mock("GET", "/enterprises/acme-corp/actions/runner-groups/3/organizations")
+            .match_query(Matcher::Any)
+            .with_body(serde_json::json!({ "total_count": 1, "organizations": [{ "id": 9, "login": "acme" }] }).to_string())
+            .create_async().await;
+        let add = server.mock("PUT", "/enterprises/acme-corp/actions/runner-groups/3/organizations/9")
+            .with_status(204)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/enterprises/acme-corp/", server.url()), HeaderMap::new()).unwrap()
+            .with_scope(ApiScope::Enterprise);
+        let response = client.runner_groups().get_all(true).await.unwrap();
+        assert!(!response.runner_groups[0].inherited);
+        assert_eq!(client.runner_groups().get_runners(3, true).await.unwrap().runners.len(), 1);
+        let orgs = client.runner_groups().get_group_repos(3).await.unwrap().repositories;
+        assert_eq!(orgs[0].name, "acme");
+        client.runner_groups().add_repo_access(3, 9).await.unwrap();
+
+        for mock in [groups, runners, access, add] {
+            mock.assert_async().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn forced_fetch_repopulates_the_cache() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("GET", "/orgs/acme/actions/runner-groups/1/runners")
+            .match_query(Matcher::Any)
+            .with_body(runners_page(0..1, 1))
+            .expect(1)
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        client.runner_groups().get_runners(1, true).await.unwrap();
+        let cached = client.runner_groups().get_runners(1, false).await.unwrap();
+
+        assert_eq!(cached.runners.len(), 1);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn get_is_retried_on_server_errors_but_mutations_are_not() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server.mock("GET", "/orgs/acme/actions/runners/7")
+            .with_status(503)
+            .expect(2)
+            .create_async().await;
+        // mockito answers with the first mock still expecting hits, so the 503s come first.
+        let ok = server.mock("GET", "/orgs/acme/actions/runners/7")
+            .with_body(serde_json::json!({
+                "id": 7, "name": "runner-7", "os": "linux", "status": "online", "busy": false, "labels": [],
+            }).to_string())
+            .expect(1)
+            .create_async().await;
+        let mut client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        client.backoff_base = Duration::ZERO;
+        assert_eq!(client.runners().get_one(7).await.unwrap().id, 7);
+        unavailable.assert_async().await;
+        ok.assert_async().await;
+
+        let delete = server.mock("DELETE", "/orgs/acme/actions/runners/7")
+            .with_status(502)
+            .expect(1)
+            .create_async().await;
+        assert!(client.runners().delete_runner(7).await.is_err());
+        delete.assert_async().await;
+
+        let not_found = server.mock("GET", "/orgs/acme/actions/runners/8")
+            .with_status(404)
+            .expect(1)
+            .create_async().await;
+        assert!(client.runners().get_one(8).await.is_err());
+        not_found.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn rate_limited_request_is_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let path = "/orgs/acme/actions/runners/1/labels";
+        let limited = server.mock("POST", path)
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async().await;
+        let ok = server.mock("POST", path)
+            .with_header("x-ratelimit-limit", "5000")
+            .with_header("x-ratelimit-remaining", "4999")
+            .with_header("x-ratelimit-reset", "1700000000")
+            .with_body("{}")
+            .create_async().await;
+
+        let client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        client.runners().add_label(1, vec![String::from("gpu")]).await.unwrap();
+
+        limited.assert_async().await;
+        ok.assert_async().await;
+        assert_eq!(client.rate_limit(), Some(RateLimit { limit: 5000, remaining: 4999, reset: 1_700_000_000 }));
+    }
+
+    #[test]
+    fn forbidden_is_only_retried_when_rate_limited() {
+        let base = Duration::from_secs(1);
+        assert_eq!(retry_wait(StatusCode::FORBIDDEN, &HeaderMap::new(), 0, base), None);
+        assert_eq!(retry_wait(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), 2, base), Some(Duration::from_secs(4)));
+        assert_eq!(retry_wait(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), 40, base), Some(MAX_RETRY_WAIT));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, "600".parse().unwrap());
+        assert_eq!(retry_wait(StatusCode::FORBIDDEN, &headers, 0, base), Some(MAX_RETRY_WAIT));
+    }
+
+    #[test]
+    fn jittered_backoff_grows_up_to_the_longest_wait() {
+        let base = Duration::from_secs(1);
+        let backoff = jittered_backoff(2, base);
+        assert!(backoff >= Duration::from_secs(4) && backoff <= Duration::from_secs(6), "{:?}", backoff);
+        assert_eq!(jittered_backoff(40, base), MAX_RETRY_WAIT);
+    }
+
+    #[tokio::test]
+    async fn error_responses_map_to_client_errors() {
+        let mut server = mockito::Server::new_async().await;
+        for (id, status) in [(1, 401), (2, 404), (3, 429), (4, 422)] {
+            server.mock("DELETE", format!("/orgs/acme/actions/runners/{}", id).as_str())
+                .with_status(status)
+                .create_async().await;
+        }
+        let mut client = Client::new(&format!("{}/orgs/acme/", server.url()), HeaderMap::new()).unwrap();
+        client.backoff_base = Duration::ZERO;
+
+        assert!(matches!(client.runners().delete_runner(1).await, Err(ClientError::Unauthorized)));
+        assert!(matches!(client.runners().delete_runner(2).await, Err(ClientError::NotFound)));
+        assert!(matches!(client.runners().delete_runner(3).await, Err(ClientError::RateLimited { retry_after: None })));
+        assert!(matches!(client.runners().delete_runner(4).await, Err(ClientError::Http(StatusCode::UNPROCESSABLE_ENTITY))));
+
+        // Nothing listens on the port of a listener that was just closed.
+        let address = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+        let client = Client::new(&format!("http://{}/orgs/acme/", address), HeaderMap::new()).unwrap();
+        assert!(matches!(client.runners().delete_runner(1).await, Err(ClientError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn hung_request_times_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Accepts the connection but never answers.
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let client = Client::new(&format!("http://{}/orgs/acme/", address), HeaderMap::new()).unwrap()
+            .with_timeout(Duration::from_millis(100));
+        let error = client.runners().delete_runner(1).await.unwrap_err();
+
+        assert!(matches!(error, ClientError::Transport(ref e) if e.is_timeout()));
+        server.abort();
+    }
+}
@@ -3,23 +3,28 @@ mod ui;
 mod tabs;
 mod client;
 mod model;
+mod setup;
 mod utils;
 
-use client::api::ApiRepository;
-use crate::backend::{ApiMessage, BackendMessage, Worker};
-use utils::config::read_dot_env;
+use client::api::{ApiRepository, RateLimit};
+use crate::backend::{ApiMessage, BackendMessage, SharedReceiver, Worker};
+use utils::config::{profile_token_origin, read_config, token_origin, Config, ConfigError, Profiles, CONFIG_TOML, DOT_ENV};
+use utils::state::{describe_age, State};
 use tabs::groups_tab::RunnersGroupsTab;
-use model::runners::{Runner, RunnerGroup};
+use model::runners::{GroupOperation, Runner, RunnerGroup, RunnerOperation};
+use tabs::repos_tab::ReposTab;
 use tabs::runners_tab::RunnersTab;
-use crate::ui::Popup;
+use crate::setup::SetupForm;
+use crate::ui::{truncate_to_width, vim_navigation, ConfirmPopup, Popup, SelectableList};
 use cli_log::*;
 use color_eyre::owo_colors::OwoColorize;
 use color_eyre::Result;
 use ratatui::widgets::Tabs;
 use ratatui::{
     buffer::Buffer,
-    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
     style::{
         palette::tailwind::{BLUE, GREEN, SLATE},
         Color, Modifier, Style, Stylize,
@@ -27,16 +32,22 @@ use ratatui::{
 
     ,
     widgets::{
-        ListState, Paragraph,
-        StatefulWidget, Widget,
+        Block, Clear, ListState, Paragraph,
+        StatefulWidget, Widget, Wrap,
     },
     DefaultTerminal,
 };
 use std::fmt::{Display, Write};
 use std::ops::Deref;
+use std::path::Path;
+use std::cell::{Cell, RefCell};
 use std::rc::{Rc, Weak};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures::FutureExt;
+use tokio::task::JoinHandle;
 use tokio::sync::mpsc;
+use unicode_width::UnicodeWidthStr;
 
 const TODO_HEADER_STYLE: Style = Style::new().fg(SLATE.c100).bg(BLUE.c800);
 const NORMAL_ROW_BG: Color = SLATE.c950;
@@ -65,6 +76,11 @@ struct PopupInfo {
     title: String,
     content: Box<dyn Fn() -> String>,
     is_loading: bool,
+    dismissable: bool,
+    /// First visible row. Clamped on render, once the content height is known.
+    scroll: Cell<u16>,
+    max_scroll: Cell<u16>,
+    page_height: Cell<u16>,
 }
 
 impl PopupInfo {
@@ -72,15 +88,24 @@ impl PopupInfo {
         PopupInfo {
             title: String::from("Loading"),
             content: Box::new(||String::from("Loading...")),
-            is_loading: true
+            is_loading: true,
+            dismissable: false,
+            scroll: Cell::new(0),
+            max_scroll: Cell::new(0),
+            page_height: Cell::new(0),
         }
     }
 
+    /// Static message popup, closed by the next key press.
     fn new(title: String, content: String) -> Self {
         PopupInfo {
             title,
             content: Box::new(move || content.clone()),
             is_loading: false,
+            dismissable: true,
+            scroll: Cell::new(0),
+            max_scroll: Cell::new(0),
+            page_height: Cell::new(0),
         }
     }
 
@@ -89,73 +114,278 @@ impl PopupInfo {
             title,
             content: content_fn,
             is_loading: false,
+            dismissable: false,
+            scroll: Cell::new(0),
+            max_scroll: Cell::new(0),
+            page_height: Cell::new(0),
         }
     }
 }
 
+impl PopupInfo {
+    /// Scrolls content that doesn't fit the popup. Returns whether the key was used, so
+    /// callers only fall back to dismissing the popup for other keys.
+    fn scroll(&self, code: KeyCode) -> bool {
+        let max = self.max_scroll.get();
+        if max == 0 {
+            return false;
+        }
+        let current = self.scroll.get();
+        let page = self.page_height.get().max(1);
+        let next = match code {
+            KeyCode::Up => current.saturating_sub(1),
+            KeyCode::Down => current + 1,
+            KeyCode::PageUp => current.saturating_sub(page),
+            KeyCode::PageDown => current + page,
+            _ => return false,
+        };
+        self.scroll.set(next.min(max));
+        true
+    }
+}
+
+/// Share of the screen height a popup may take before its content starts scrolling.
+const SCROLLABLE_POPUP_HEIGHT_PERCENT: u16 = 70;
+
 fn show_popup(popup_content: &Option<PopupInfo>, area: Rect, buf: &mut Buffer) {
     if let Some(popup) = popup_content {
-        let popup_area = Rect {
-            x: area.width / 4,
-            y: area.height / 3,
+        let mut popup_area = Rect {
+            x: area.x + area.width / 4,
+            y: area.y + area.height / 3,
             width: area.width / 2,
             height: 3,
         };
         if !popup.is_loading {
+            let content = (popup.content)();
+            // Count the rows long lines (e.g. label lists) take once wrapped.
+            let inner_width = popup_area.width.saturating_sub(2).max(1);
+            let lines: u16 = content.lines()
+                .map(|line| (line.width() as u16).div_ceil(inner_width).max(1))
+                .sum();
+            if lines + 2 > area.bottom().saturating_sub(popup_area.y) {
+                // Too long for the usual spot, grow towards the top of the screen and scroll.
+                let height = ((area.height as u32 * SCROLLABLE_POPUP_HEIGHT_PERCENT as u32 / 100) as u16)
+                    .max(3)
+                    .min(area.height);
+                popup_area.y = area.y + (area.height - height) / 2;
+                popup_area.height = height;
+            } else {
+                popup_area.height = (lines + 2).max(3);
+            }
+            popup_area = popup_area.intersection(area);
+            let page_height = popup_area.height.saturating_sub(2);
+            let max_scroll = lines.saturating_sub(page_height);
+            popup.page_height.set(page_height);
+            popup.max_scroll.set(max_scroll);
+            popup.scroll.set(popup.scroll.get().min(max_scroll));
             Popup::default()
                 .title(popup.title.as_str())
-                .content((popup.content)())
+                .content(content)
+                .scroll(popup.scroll.get(), lines)
                 .render(popup_area, buf);
         } else {
             Popup::default()
                 .title("Loading")
                 .content(format!("Loading ..."))
-                .render(popup_area, buf);
+                .render(popup_area.intersection(area), buf);
         }
     }
 }
 
+/// How long a success message stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// Below this size the lists and popups can't be laid out, a hint to resize is shown instead.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+/// Wait before starting a stopped worker again, doubled for every restart in a row.
+const WORKER_RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Restarts in a row after which the worker is given up on.
+const MAX_WORKER_RESTARTS: u32 = 5;
+
+enum PickerKind {
+    Profile,
+    Organization,
+}
+
 struct AppState<'a> {
     runners_tab: RunnersTab<'a>,
     runner_groups_tab: RunnersGroupsTab<'a>,
+    repos_tab: ReposTab<'a>,
+    /// Shown tabs, in the order Tab cycles through them, see `Config::tabs`.
+    tabs: Vec<Tab>,
     selected_tab: Tab,
     should_exit: bool,
     tx: &'a mpsc::UnboundedSender<BackendMessage>,
     api_rx: mpsc::UnboundedReceiver<ApiMessage>,
+    local_state: Rc<RefCell<State>>,
+    profiles: Profiles,
+    organizations: Vec<String>,
+    organization: String,
+    /// Changes are only logged, see `Config::dry_run`.
+    dry_run: bool,
+    picker: Option<(PickerKind, SelectableList<String>)>,
+    /// Asked when Esc would quit while the selected tab waits for the backend, or to quit and
+    /// enter a new token when GitHub rejected it. The action tells whether a token is wanted.
+    quit_confirm: Option<ConfirmPopup<bool>>,
+    /// Whether the app quit for the user to enter a new token.
+    reenter_token: bool,
+    /// Where the token comes from when the new token form can't replace it, see `token_origin`.
+    token_origin: Option<String>,
+    /// Last success message and when it arrived, dropped by the event loop after `TOAST_DURATION`.
+    toast: Option<(String, Instant)>,
+    /// Refetch the runners this often when set, see `auto_refresh_due`.
+    refresh_interval: Option<Duration>,
+    last_refresh: Instant,
+    /// An automatic refresh was sent and its lists must be applied in place.
+    auto_refresh_pending: bool,
+    /// Error of the last failed request, until lists arrive again. Flags the data as possibly stale.
+    last_error: Option<String>,
+    /// When a runner or group list last arrived.
+    last_success: Instant,
+    /// Why the worker stopped, shown until it is restarted.
+    fatal_error: Option<String>,
+    /// When the stopped worker is started again, `None` while it runs or once given up on.
+    worker_restart_at: Option<Instant>,
+    /// Restarts since a list last arrived, each one doubling the wait before the next.
+    worker_restarts: u32,
+    /// API quota left, shown at the right of the status bar once a response reported it.
+    rate_limit: Option<RateLimit>,
 }
 
 impl <'a> Widget for &mut AppState<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let [header_area, main_area, footer_area] = Layout::vertical([
+        if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+            Paragraph::new(format!("Terminal too small, resize to at least {}x{}", MIN_WIDTH, MIN_HEIGHT))
+                .wrap(Wrap { trim: true })
+                .render(area, buf);
+            return;
+        }
+        let [header_area, main_area, status_area, footer_area] = Layout::vertical([
             Constraint::Length(1),
             Constraint::Fill(1),
             Constraint::Length(1),
+            Constraint::Length(1),
         ]).areas(area);
 
         self.render_header(header_area, buf);
+        self.render_status(status_area, buf);
         AppState::render_footer(footer_area, buf);
+        if let Some(error) = &self.fatal_error {
+            let next = match self.worker_restart_at {
+                Some(at) => format!("Restarting it in {:.0}s, press q or Esc to quit.", at.saturating_duration_since(Instant::now()).as_secs_f32().ceil()),
+                None => String::from("Press q or Esc to quit."),
+            };
+            Paragraph::new(format!("The backend worker stopped unexpectedly:\n\n{}\n\n{}", error, next))
+                .block(Block::bordered().title("Error").border_style(Style::new().red()))
+                .wrap(Wrap { trim: false })
+                .render(main_area, buf);
+            return;
+        }
         match self.selected_tab {
             Tab::Runners => self.runners_tab.render(main_area, buf),
             Tab::RunnerGroups => self.runner_groups_tab.render(main_area, buf),
+            Tab::Repos => self.repos_tab.render(main_area, buf),
+        }
+        if let Some((kind, picker)) = &mut self.picker {
+            let popup_area = Rect {
+                x: area.width / 4,
+                y: area.height / 4,
+                width: area.width / 2,
+                height: area.height / 2,
+            };
+            Clear.render(popup_area, buf);
+            let title = match kind {
+                PickerKind::Profile => "Switch profile",
+                PickerKind::Organization => "Switch organization",
+            };
+            picker.render(popup_area, buf, title);
+        }
+        if let Some((message, _)) = &self.toast {
+            render_toast(message, main_area, buf);
+        }
+        if let Some(confirm) = &self.quit_confirm {
+            confirm.render(main_area, buf);
         }
     }
 }
 
+/// One line message in the bottom right corner, over whatever the tab shows.
+fn render_toast(message: &str, area: Rect, buf: &mut Buffer) {
+    let width = (message.width() as u16 + 4).min(area.width);
+    let height = 3.min(area.height);
+    let toast_area = Rect {
+        x: area.x + area.width - width,
+        y: area.y + area.height - height,
+        width,
+        height,
+    };
+    Clear.render(toast_area, buf);
+    Paragraph::new(truncate_to_width(message, width.saturating_sub(2) as usize))
+        .block(Block::bordered().border_style(Style::new().fg(COMPLETED_TEXT_FG_COLOR)))
+        .render(toast_area, buf);
+}
+
 impl <'a> AppState<'a> {
-    fn new(runners: Vec<Runner>, runner_groups: Vec<RunnerGroup>, selected_tab: Tab, tx: &'a mpsc::UnboundedSender<BackendMessage>, api_rx: mpsc::UnboundedReceiver<ApiMessage>) -> Self {
+    fn new(selected_tab: Tab, tx: &'a mpsc::UnboundedSender<BackendMessage>, api_rx: mpsc::UnboundedReceiver<ApiMessage>, config: &Config, local_state: Rc<RefCell<State>>) -> Self {
+        let known_operations: Vec<&str> = RunnerOperation::all().iter().map(|op| op.key())
+            .chain(GroupOperation::all().iter().map(|op| op.key()))
+            .collect();
+        for key in config.operations.unknown(&known_operations) {
+            warn!("Unknown operation in config: {}", key);
+        }
+        let tabs = Tab::configured(&config.tabs);
+        let selected_tab = if tabs.contains(&selected_tab) { selected_tab } else { tabs[0] };
         let mut state = AppState {
-            runners_tab: RunnersTab::new(runners, tx),
-            runner_groups_tab: RunnersGroupsTab::new(runner_groups, tx),
+            runners_tab: RunnersTab::new(tx, config, Rc::clone(&local_state)),
+            runner_groups_tab: RunnersGroupsTab::new(tx, config, Rc::clone(&local_state)),
+            repos_tab: ReposTab::new(tx),
+            tabs,
             selected_tab,
             should_exit: false,
             tx,
-            api_rx
+            api_rx,
+            local_state,
+            profiles: config.profiles.clone(),
+            organizations: config.organizations.clone(),
+            organization: config.organization.clone(),
+            dry_run: config.dry_run,
+            picker: None,
+            quit_confirm: None,
+            reenter_token: false,
+            token_origin: None,
+            toast: None,
+            refresh_interval: config.refresh_interval_secs.map(Duration::from_secs),
+            last_refresh: Instant::now(),
+            auto_refresh_pending: false,
+            last_error: None,
+            last_success: Instant::now(),
+            fatal_error: None,
+            worker_restart_at: None,
+            worker_restarts: 0,
+            rate_limit: None,
         };
+        state.runners_tab.set_loading();
+        if state.selected_tab == Tab::Repos {
+            state.repos_tab.load_if_needed();
+        }
         state
     }
 
-    fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
+    /// Runs until the user quits. Returns true if they asked to enter a new token.
+    /// `spawn_worker` starts the backend worker with the given profile and organization, again
+    /// if it stops.
+    fn run(mut self, mut terminal: DefaultTerminal, spawn_worker: impl Fn(Option<String>, String) -> JoinHandle<()>) -> Result<bool> {
+        let mut worker = spawn_worker(None, self.organization.clone());
         while !self.should_exit  {
+            let now = Instant::now();
+            self.expire_toast(now);
+            self.runners_tab.expire_status_changes(now);
+            if self.auto_refresh_due(now) {
+                self.auto_refresh_pending = true;
+                self.tx.send(BackendMessage::FetchRunners { skip_cache: true })
+                    .expect("Could not send refresh command to backend");
+            }
+            self.supervise_worker(&mut worker, &spawn_worker, now);
             terminal.draw(|frame| frame.render_widget(&mut self, frame.area()))?;
             if let Ok(true) = event::poll(Duration::from_millis(100)) {
                 if let Event::Key(key) = event::read()? {
@@ -163,42 +393,239 @@ impl <'a> AppState<'a> {
                 };
             }
             if let Ok(message) = self.api_rx.try_recv() {
-                match message {
-                    ApiMessage::Ok => self.runner_groups_tab.toggle_loading(),
-                    ApiMessage::RunnerList(runners) => self.set_runners(*runners),
-                    ApiMessage::RunnerGroupList(groups) => self.set_runner_groups(*groups),
-                    ApiMessage::GroupRepos(repos) => self.set_group_repos(*repos),
-                }
+                self.handle_api_message(message);
+            }
+        }
+        // Last-seen times are only tracked in memory while running.
+        if let Err(e) = self.local_state.borrow().save() {
+            warn!("Could not save local state: {}", e);
+        }
+        Ok(self.reenter_token)
+    }
+
+    /// Whether the refresh interval elapsed since the last automatic refresh. Waits while the
+    /// previous one is pending or any tab waits on a request, whose lists the refresh would
+    /// otherwise take for its own, and restarts the interval when due.
+    fn auto_refresh_due(&mut self, now: Instant) -> bool {
+        let Some(interval) = self.refresh_interval else {
+            return false;
+        };
+        if now.duration_since(self.last_refresh) < interval || self.auto_refresh_pending || self.any_tab_is_busy() {
+            return false;
+        }
+        self.last_refresh = now;
+        true
+    }
+
+    fn expire_toast(&mut self, now: Instant) {
+        if self.toast.as_ref().is_some_and(|(_, shown)| now.duration_since(*shown) >= TOAST_DURATION) {
+            self.toast = None;
+        }
+    }
+
+    /// Notices the worker stopping and starts a new one once the backoff is over, with the last
+    /// picked profile and the organization shown, which F3 may have changed since.
+    fn supervise_worker(&mut self, worker: &mut JoinHandle<()>, spawn_worker: &impl Fn(Option<String>, String) -> JoinHandle<()>, now: Instant) {
+        if self.fatal_error.is_none() && worker.is_finished() {
+            let reason = AppState::worker_exit_reason(worker);
+            error!("The backend worker stopped: {}", reason);
+            if self.worker_restarts < MAX_WORKER_RESTARTS {
+                self.worker_restart_at = Some(now + WORKER_RESTART_DELAY * 2u32.pow(self.worker_restarts));
             }
+            self.fatal_error = Some(reason);
+        }
+        if self.worker_restart_at.is_none_or(|at| now < at) {
+            return;
+        }
+        let profile = self.local_state.borrow().last_profile.clone();
+        *worker = spawn_worker(profile, self.organization.clone());
+        self.worker_restarts += 1;
+        self.worker_restart_at = None;
+        let reason = self.fatal_error.take().unwrap_or_default();
+        // The request the worker was handling is lost, the tab waiting for it is told so.
+        self.handle_api_message(ApiMessage::Error(format!("The backend worker stopped and was restarted: {}", reason)));
+        self.auto_refresh_pending = true;
+        self.tx.send(BackendMessage::FetchRunners { skip_cache: true })
+            .expect("Could not send refresh command to backend");
+    }
+
+    fn worker_exit_reason(worker: &mut JoinHandle<()>) -> String {
+        match worker.now_or_never() {
+            Some(Err(e)) if e.is_panic() => {
+                let panic = e.into_panic();
+                panic.downcast_ref::<&str>().map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| String::from("unknown panic"))
+            }
+            Some(Err(e)) => e.to_string(),
+            _ => String::from("the worker exited"),
         }
-        Ok(())
     }
 
     fn handle_key(&mut self, key: KeyEvent) {
         if key.kind != KeyEventKind::Press {
             return;
         }
-        if key.code == KeyCode::Tab {
-            self.selected_tab = match self.selected_tab {
-                Tab::Runners => Tab::RunnerGroups,
-                Tab::RunnerGroups => Tab::Runners,
-                a => a
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.should_exit = true;
+            return;
+        }
+        if self.fatal_error.is_some() {
+            self.should_exit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter);
+            return;
+        }
+        if let Some(confirm) = &self.quit_confirm {
+            match confirm.answer(key.code) {
+                Some(true) => {
+                    self.reenter_token = self.quit_confirm.take().unwrap().into_action();
+                    self.should_exit = true;
+                }
+                Some(false) => self.quit_confirm = None,
+                None => {}
             }
+            return;
+        }
+        if self.picker.is_some() {
+            self.handle_picker(key);
+            return;
+        }
+        if key.code == KeyCode::F(2) && !self.profiles.0.is_empty() {
+            self.picker = Some((PickerKind::Profile, SelectableList::new(self.profiles.names(), TODO_HEADER_STYLE).with_first_selected()));
+            return;
+        }
+        if key.code == KeyCode::F(3) && self.organizations.len() > 1 {
+            self.picker = Some((PickerKind::Organization, SelectableList::new(self.organizations.clone(), TODO_HEADER_STYLE).with_first_selected()));
+            return;
+        }
+        if key.code == KeyCode::Char('g') && key.modifiers.contains(KeyModifiers::CONTROL)
+            && self.selected_tab == Tab::Runners && self.runners_tab.is_browsing() {
+            self.jump_to_runner_group();
+            return;
+        }
+        if key.code == KeyCode::Esc && self.selected_tab_is_busy() {
+            self.quit_confirm = Some(ConfirmPopup::new("Quit", "An operation is still in progress, quit anyway?", false));
+            return;
+        }
+        if key.code == KeyCode::Tab {
+            self.select_tab(self.next_tab());
         }
         self.should_exit = match self.selected_tab {
             Tab::Runners => self.runners_tab.handle_input(key),
             Tab::RunnerGroups => self.runner_groups_tab.handle_input(key),
+            Tab::Repos => self.repos_tab.handle_input(key),
+        }
+
+    }
+
+    /// Opens the groups tab on the group of the selected runner.
+    fn jump_to_runner_group(&mut self) {
+        let Some(runner) = self.runners_tab.selected() else {
+            return;
+        };
+        if !self.tabs.contains(&Tab::RunnerGroups) {
+            return;
+        }
+        let group = runner.group.clone();
+        self.selected_tab = Tab::RunnerGroups;
+        self.runner_groups_tab.select_by_name(group.as_deref());
+    }
+
+    /// The shown tab after the selected one, wrapping around.
+    fn next_tab(&self) -> Tab {
+        let position = self.tabs.iter().position(|&tab| tab == self.selected_tab);
+        match position {
+            Some(i) => self.tabs[(i + 1) % self.tabs.len()],
+            None => self.tabs[0],
+        }
+    }
+
+    /// Selects `tab` if it is shown, otherwise stays on the current tab.
+    fn select_tab(&mut self, tab: Tab) {
+        if !self.tabs.contains(&tab) {
+            return;
+        }
+        self.selected_tab = tab;
+        if self.selected_tab == Tab::Repos {
+            self.repos_tab.load_if_needed();
+        }
+    }
+
+    fn selected_tab_is_busy(&self) -> bool {
+        match self.selected_tab {
+            Tab::Runners => self.runners_tab.is_busy(),
+            Tab::RunnerGroups => self.runner_groups_tab.is_busy(),
+            Tab::Repos => self.repos_tab.is_busy(),
+        }
+    }
+
+    fn any_tab_is_busy(&self) -> bool {
+        self.runners_tab.is_busy() || self.runner_groups_tab.is_busy() || self.repos_tab.is_busy()
+    }
+
+    fn handle_picker(&mut self, key: KeyEvent) {
+        let Some((kind, picker)) = &mut self.picker else {
+            return;
+        };
+        match vim_navigation(key).code {
+            KeyCode::Up => picker.select_previous(),
+            KeyCode::Down => picker.select_next(),
+            KeyCode::Esc => self.picker = None,
+            KeyCode::Enter => {
+                match (kind, picker.selected().cloned()) {
+                    (PickerKind::Profile, Some(name)) => self.switch_profile(name),
+                    (PickerKind::Organization, Some(name)) => self.switch_org(name),
+                    _ => {}
+                }
+                self.picker = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn switch_profile(&mut self, name: String) {
+        let mut local_state = self.local_state.borrow_mut();
+        local_state.last_profile = Some(name.clone());
+        if let Err(e) = local_state.save() {
+            error!("Could not persist last used profile: {}", e);
         }
+        drop(local_state);
+        if let Some(profile) = self.profiles.get(&name) {
+            self.organization = profile.organization.clone();
+            self.token_origin = Some(profile_token_origin(&name));
+        }
+        self.reset_for_new_org();
+        self.tx.send(BackendMessage::SwitchProfile(name))
+            .expect("Could not send switch profile command to backend");
+        self.reload_open_repos();
+    }
+
+    fn switch_org(&mut self, organization: String) {
+        self.organization = organization.clone();
+        self.reset_for_new_org();
+        self.tx.send(BackendMessage::SwitchOrg(organization))
+            .expect("Could not send switch organization command to backend");
+        self.reload_open_repos();
+    }
 
+    /// Drops what was loaded for the previous organization and waits for the new runners.
+    fn reset_for_new_org(&mut self) {
+        self.runners_tab.set_loading();
+        self.repos_tab.clear();
+        self.runner_groups_tab.clear_org_repos();
+        self.select_tab(Tab::Runners);
+    }
+
+    /// Lists the repositories of the new organization when the runners tab is hidden and the
+    /// repositories stay open. Only once the switch was sent, so they aren't those of the old one.
+    fn reload_open_repos(&mut self) {
+        if self.selected_tab == Tab::Repos {
+            self.repos_tab.load_if_needed();
+        }
     }
 
     fn render_header(&self, area: Rect, buf: &mut Buffer) {
-        let titles = Tab::all().into_iter().map(|t|t.as_str());
-        let selected_idx = Tab::all()
-            .into_iter()
-            .enumerate()
-            .find(|(i, tab)| self.selected_tab == *tab)
-            .map(|(i, _)| i);
+        let titles = self.tabs.iter().map(|t|t.as_str());
+        let selected_idx = self.tabs.iter().position(|&tab| tab == self.selected_tab);
         Tabs::new(titles)
             .select(selected_idx)
             .padding("", "")
@@ -208,6 +635,62 @@ impl <'a> AppState<'a> {
                 .fg(Color::White))
             .highlight_style(self.selected_tab.style())
             .render(area, buf);
+        let mut scope = String::new();
+        if self.organizations.len() > 1 {
+            scope.push_str(&format!("org: {} (F3) ", self.organization));
+        }
+        if let Some(profile) = &self.local_state.borrow().last_profile {
+            scope.push_str(&format!("profile: {} (F2) ", profile));
+        }
+        let mut line = Line::raw(scope);
+        if self.dry_run {
+            line.spans.insert(0, Span::styled(" DRY RUN ", Style::new().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD)));
+            line.spans.insert(1, Span::raw(" "));
+        }
+        if let Some(indicator) = self.error_indicator(Instant::now()) {
+            line.spans.insert(0, Span::styled(indicator, Style::new().fg(Color::Red).add_modifier(Modifier::BOLD)));
+        }
+        Paragraph::new(line)
+            .right_aligned()
+            .render(area, buf);
+    }
+
+    /// Header warning while the last request failed, telling a lost connection apart from an
+    /// error answer and saying how old the shown data is. `None` when everything is fine.
+    fn error_indicator(&self, now: Instant) -> Option<String> {
+        let error = self.last_error.as_ref()?;
+        let kind = if error.contains("error sending request") || error.contains("timed out") {
+            "⚠ offline"
+        } else {
+            "⚠ API error"
+        };
+        let age = describe_age(0, now.duration_since(self.last_success).as_secs());
+        Some(format!("{}, data from {} ", kind, age))
+    }
+
+    fn render_status(&self, area: Rect, buf: &mut Buffer) {
+        let status = match self.selected_tab {
+            Tab::Runners => self.runners_tab.status_line(),
+            Tab::RunnerGroups => self.runner_groups_tab.status_line(),
+            Tab::Repos => self.repos_tab.status_line(),
+        };
+        let style = Style::default().bg(Color::Black).fg(Color::White);
+        let quota = self.rate_limit.map(|r| {
+            let style = if r.remaining < r.limit / 10 { style.fg(Color::Yellow) } else { style };
+            Span::styled(format!("API quota {}/{} ", r.remaining, r.limit), style)
+        });
+        let [status_area, quota_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Length(quota.as_ref().map_or(0, |q| q.width() as u16)),
+        ]).areas(area);
+        Paragraph::new(status)
+            .style(style)
+            .render(status_area, buf);
+        if let Some(quota) = quota {
+            Paragraph::new(quota)
+                .style(style)
+                .render(quota_area, buf);
+        }
     }
 
     fn render_footer(area: Rect, buf: &mut Buffer) {
@@ -216,12 +699,84 @@ impl <'a> AppState<'a> {
             .render(area, buf);
     }
 
+    fn handle_api_message(&mut self, message: ApiMessage) {
+        match message {
+            ApiMessage::Ok => self.runner_groups_tab.toggle_loading(),
+            ApiMessage::RunnerList(runners) if self.auto_refresh_pending => {
+                self.mark_fresh();
+                self.auto_refresh_pending = false;
+                self.runners_tab.refresh_runners(runners);
+            }
+            ApiMessage::RunnerList(runners) => {
+                self.mark_fresh();
+                self.set_runners(runners);
+            }
+            ApiMessage::RunnerGroupList(groups) if self.auto_refresh_pending => {
+                self.mark_fresh();
+                self.runners_tab.set_runner_groups(&groups);
+                self.runner_groups_tab.refresh_groups(groups);
+            }
+            ApiMessage::RunnerGroupList(groups) => {
+                self.mark_fresh();
+                self.set_runner_groups(groups);
+            }
+            ApiMessage::GroupRepos(repos) => self.set_group_repos(repos),
+            ApiMessage::RepoList(repos) => {
+                self.runner_groups_tab.set_org_repos(&repos);
+                self.repos_tab.set_repos(repos);
+            }
+            ApiMessage::GroupRunners(group, runners) => self.runners_tab.merge_group_runners(&group, runners),
+            ApiMessage::GroupMembers(runners) => self.runner_groups_tab.set_group_runners(runners),
+            ApiMessage::LabelChanged(runner_id, label, added) => self.runners_tab.record_label_change(runner_id, label, added),
+            ApiMessage::RunnerDetails(runner_id, runner) => self.runners_tab.set_runner_details(runner_id, runner.map(|r| *r)),
+            ApiMessage::DuplicateGroups(result) => self.runner_groups_tab.set_duplicate_groups(result),
+            ApiMessage::GroupsMerged(result) => self.runner_groups_tab.show_merge_result(result),
+            ApiMessage::GroupUpdated(group) => self.runner_groups_tab.show_updated_group(&group),
+            ApiMessage::Exported(result) => self.runners_tab.show_export_result(result),
+            ApiMessage::GroupsExported(result) => self.runner_groups_tab.show_export_result(result),
+            ApiMessage::GroupAccess(result) => self.runner_groups_tab.set_group_access(result),
+            ApiMessage::RegistrationToken(result) => self.runners_tab.show_registration_token(result),
+            ApiMessage::RunnerDownloads(result) => self.runners_tab.show_downloads(result),
+            ApiMessage::JitConfig(result) => self.runners_tab.show_jit_config(result.map(|c| *c)),
+            ApiMessage::Success(message) => self.toast = Some((message, Instant::now())),
+            ApiMessage::RateLimit(rate_limit) => self.rate_limit = Some(rate_limit),
+            // The form saves the new token to `.env`, which is no use when the token comes from elsewhere.
+            ApiMessage::InvalidToken(message) if self.token_origin.is_some() => {
+                let origin = self.token_origin.clone().unwrap_or_default();
+                self.handle_api_message(ApiMessage::Error(format!("{}\n\nReplace the token in {}.", message, origin)));
+            }
+            ApiMessage::InvalidToken(message) => {
+                let prompt = format!("{}\n\nQuit and enter a new token?", message);
+                self.handle_api_message(ApiMessage::Error(message));
+                self.quit_confirm = Some(ConfirmPopup::new("Invalid token", prompt, true));
+            }
+            ApiMessage::Error(message) => {
+                // Requests are handled in order, a pending automatic refresh is the one that failed.
+                self.auto_refresh_pending = false;
+                self.last_error = Some(message.clone());
+                match self.selected_tab {
+                    Tab::Runners => self.runners_tab.show_error(message),
+                    Tab::RunnerGroups => self.runner_groups_tab.show_error(message),
+                    Tab::Repos => self.repos_tab.show_error(message),
+                }
+            }
+        }
+    }
+
+    /// A list arrived, so the API is reachable again.
+    fn mark_fresh(&mut self) {
+        self.last_error = None;
+        self.last_success = Instant::now();
+        self.worker_restarts = 0;
+    }
+
     fn set_runners(&mut self, runners: Vec<Runner>) {
         self.runners_tab.set_runners(runners);
-        self.selected_tab = Tab::Runners;
+        self.select_tab(Tab::Runners);
     }
 
     fn set_runner_groups(&mut self, groups: Vec<RunnerGroup>) {
+        self.runners_tab.set_runner_groups(&groups);
         self.runner_groups_tab.set_groups(groups);
     }
 
@@ -234,17 +789,42 @@ impl <'a> AppState<'a> {
 enum Tab {
     Runners,
     RunnerGroups,
+    Repos,
 }
 
 impl Tab {
     fn all() -> Vec<Tab> {
-        vec![Tab::Runners,Tab::RunnerGroups,]
+        vec![Tab::Runners,Tab::RunnerGroups,Tab::Repos,]
+    }
+
+    /// Name of the tab in `Config::tabs`.
+    fn key(&self) -> &'static str {
+        match self {
+            Tab::Runners => "runners",
+            Tab::RunnerGroups => "groups",
+            Tab::Repos => "repos",
+        }
+    }
+
+    /// Tabs named by `keys`, in that order. Unknown and repeated names are skipped,
+    /// and all tabs are shown when none is left.
+    fn configured(keys: &[String]) -> Vec<Tab> {
+        let mut tabs = vec![];
+        for key in keys {
+            match Tab::all().into_iter().find(|tab| tab.key() == key) {
+                Some(tab) if !tabs.contains(&tab) => tabs.push(tab),
+                Some(_) => {}
+                None => warn!("Unknown tab in config: {}", key),
+            }
+        }
+        if tabs.is_empty() { Tab::all() } else { tabs }
     }
 
     fn as_str(&self) -> &'static str {
         match self {
             Tab::Runners => " Runners ",
             Tab::RunnerGroups => " Runner Groups ",
+            Tab::Repos => " Repos ",
         }
     }
 
@@ -252,35 +832,485 @@ impl Tab {
         match self {
             Tab::Runners => TODO_HEADER_STYLE,
             Tab::RunnerGroups => TODO_HEADER_STYLE.bg(Color::Green),
+            Tab::Repos => TODO_HEADER_STYLE.bg(Color::Magenta),
+        }
+    }
+}
+
+/// Restores the terminal before a panic of the UI thread is reported, so the shell isn't left in raw mode.
+/// Panics of the worker task are only logged: the UI keeps running and shows them as a fatal error
+/// (see `AppState::worker_exit_reason`), so they must neither restore the terminal nor print over it.
+/// Has to be installed after `ratatui::init`, whose own hook restores the terminal on any panic,
+/// see `init_terminal`.
+fn install_panic_hook() {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if std::thread::current().name() == Some("main") {
+            ratatui::restore();
+            hook(info);
+        } else {
+            error!("Worker panicked: {}", info);
+        }
+    }));
+}
+
+/// Enters the alternate screen. `ratatui::init` wraps the current panic hook in one that restores
+/// the terminal on any panic, the worker's included, so ours goes on top of it again every time.
+fn init_terminal() -> DefaultTerminal {
+    let terminal = ratatui::init();
+    install_panic_hook();
+    terminal
+}
+
+/// Reads the config, asking for the essentials on the first run instead of failing.
+/// `None` if the user quit the setup form.
+fn load_config() -> Result<Option<Config>> {
+    match read_config() {
+        Err(ConfigError::MissingOrganization | ConfigError::MissingToken) if !Path::new(DOT_ENV).exists() && !Path::new(CONFIG_TOML).exists() => {
+            if !run_setup(SetupForm::first_run())? {
+                return Ok(None);
+            }
+            Ok(Some(read_config()?))
         }
+        config => Ok(Some(config?)),
     }
 }
 
+fn run_setup(form: SetupForm) -> Result<bool> {
+    let mut terminal = init_terminal();
+    let saved = setup::run(&mut terminal, Path::new(DOT_ENV), form);
+    ratatui::restore();
+    saved
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     init_cli_log!();
-    let config = read_dot_env()
-        .expect("Could not read config file");
-    let (tx, rx) = mpsc::unbounded_channel();
-    let (api_tx, api_rx) = mpsc::unbounded_channel();
-    let mut worker = Worker::new(rx, api_tx, config);
     color_eyre::install()?;
-    let terminal = ratatui::init();
+    let dry_run = std::env::args().skip(1).any(|arg| arg == "--dry-run");
+    // Starts over with the new token when the user asks to replace a rejected one.
+    while let Some(mut config) = load_config()? {
+        config.dry_run |= dry_run;
+        let organization = config.organization.clone();
+        if !run_app(config)? || !run_setup(SetupForm::new_token(&organization))? {
+            break;
+        }
+    }
+    Ok(())
+}
 
-    let runners = worker.get_runners(None).await;
-    let app_state = AppState::new(
-        runners,
-        vec!(),
+/// Returns true if the user quit to enter a new token.
+fn run_app(mut config: Config) -> Result<bool> {
+    let local_state = Rc::new(RefCell::new(State::load()));
+    let last_profile = local_state.borrow().last_profile.clone();
+    if let Some(profile) = last_profile && !config.use_profile(&profile) {
+        local_state.borrow_mut().last_profile = None;
+    }
+    let token_origin = token_origin(&config, local_state.borrow().last_profile.as_deref());
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (api_tx, api_rx) = mpsc::unbounded_channel();
+    let mut app_state = AppState::new(
         Tab::Runners,
         &tx,
-        api_rx
+        api_rx,
+        &config,
+        local_state,
     );
+    app_state.token_origin = token_origin;
+    // Held here for the whole run, so commands keep queueing while a stopped worker is replaced.
+    let rx: SharedReceiver = Arc::new(tokio::sync::Mutex::new(rx));
+    let spawn_worker = move |profile: Option<String>, organization: String| {
+        let mut config = config.clone();
+        if let Some(profile) = profile {
+            config.use_profile(&profile);
+        }
+        config.organization = organization;
+        let mut worker = Worker::new(Arc::clone(&rx), api_tx.clone(), config);
+        tokio::spawn(async move {
+            worker.run().await
+        })
+    };
+    let terminal = init_terminal();
 
-    tokio::spawn(async move {
-        worker.run().await
-    });
+    tx.send(BackendMessage::FetchRunners { skip_cache: false })
+        .expect("Could not send initial fetch command to backend");
 
-    let app_result = app_state.run(terminal);
+    let app_result = app_state.run(terminal, spawn_worker);
     ratatui::restore();
     app_result
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::{runner_group_json, ApiRunner};
+
+    #[tokio::test]
+    async fn worker_panic_message_is_kept_for_the_fatal_screen() {
+        let mut worker = tokio::spawn(async {
+            let runners: Vec<usize> = vec![];
+            runners.first().copied().expect("No runners to update");
+        });
+        while !worker.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(AppState::worker_exit_reason(&mut worker), "No runners to update");
+    }
+
+    #[tokio::test]
+    async fn stopped_worker_is_restarted_with_backoff() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let rx: SharedReceiver = Arc::new(tokio::sync::Mutex::new(rx));
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+        // Every worker takes one command and panics on it, dropping its end of the channel.
+        let spawn_worker = |_: Option<String>, _: String| {
+            let rx = Arc::clone(&rx);
+            let seen_tx = seen_tx.clone();
+            tokio::spawn(async move {
+                let kind = match rx.lock().await.recv().await {
+                    Some(BackendMessage::FetchRunners { .. }) => "runners",
+                    Some(BackendMessage::FetchRepos) => "repos",
+                    _ => "other",
+                };
+                seen_tx.send(kind).unwrap();
+                panic!("worker crashed");
+            })
+        };
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        let mut worker = spawn_worker(None, String::new());
+        tx.send(BackendMessage::FetchRunners { skip_cache: false }).unwrap();
+
+        let mut now = Instant::now();
+        for restarts in 0..MAX_WORKER_RESTARTS {
+            while !worker.is_finished() {
+                tokio::task::yield_now().await;
+            }
+            app.supervise_worker(&mut worker, &spawn_worker, now);
+            assert_eq!(app.fatal_error.as_deref(), Some("worker crashed"));
+            let delay = WORKER_RESTART_DELAY * 2u32.pow(restarts);
+            assert_eq!(app.worker_restart_at, Some(now + delay));
+            if restarts == 0 {
+                // Sending still works while no worker runs, the next one gets the command.
+                tx.send(BackendMessage::FetchRepos).unwrap();
+                app.supervise_worker(&mut worker, &spawn_worker, now + delay - Duration::from_millis(1));
+                assert!(app.fatal_error.is_some());
+            }
+            now += delay;
+            app.supervise_worker(&mut worker, &spawn_worker, now);
+            assert!(app.fatal_error.is_none());
+            assert!(!app.runners_tab.is_busy());
+        }
+        while !worker.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        app.supervise_worker(&mut worker, &spawn_worker, now + Duration::from_secs(600));
+        assert!(app.fatal_error.is_some());
+        assert_eq!(app.worker_restart_at, None);
+        assert_eq!(seen_rx.recv().await, Some("runners"));
+        assert_eq!(seen_rx.recv().await, Some("repos"));
+
+        // A list arriving means the worker is healthy again.
+        app.handle_api_message(ApiMessage::RunnerList(vec![]));
+        assert_eq!(app.worker_restarts, 0);
+    }
+
+    #[test]
+    fn switching_profile_shows_its_organization() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let profile = |name: &str, organization: &str| utils::config::Profile {
+            name: name.to_string(), organization: organization.to_string(), token: String::from("t"), api_base: None,
+        };
+        let config = Config {
+            organization: String::from("acme"),
+            profiles: Profiles(vec![profile("work", "acme"), profile("labs", "acme-labs")]),
+            ..Config::default()
+        };
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &config, Rc::new(RefCell::new(State::default())));
+
+        app.switch_profile(String::from("labs"));
+
+        assert_eq!(app.organization, "acme-labs");
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::SwitchProfile(name)) if name == "labs"));
+    }
+
+    #[tokio::test]
+    async fn restarted_worker_keeps_the_picked_organization() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let (seen_tx, mut seen_rx) = mpsc::unbounded_channel();
+        // The receiver is shared with the app, so a worker can't lose it: it only stops early by
+        // returning, as its loop does once the channel closes, or by panicking. Both are restarted.
+        let spawn_worker = |_: Option<String>, organization: String| {
+            seen_tx.send(organization).unwrap();
+            tokio::spawn(async {})
+        };
+        let config = Config {
+            organization: String::from("acme"),
+            organizations: vec![String::from("acme"), String::from("acme-labs")],
+            ..Config::default()
+        };
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &config, Rc::new(RefCell::new(State::default())));
+        let mut worker = spawn_worker(None, app.organization.clone());
+        app.switch_org(String::from("acme-labs"));
+        while !worker.is_finished() {
+            tokio::task::yield_now().await;
+        }
+
+        let now = Instant::now();
+        app.supervise_worker(&mut worker, &spawn_worker, now);
+        assert_eq!(app.fatal_error.as_deref(), Some("the worker exited"));
+        app.supervise_worker(&mut worker, &spawn_worker, now + WORKER_RESTART_DELAY);
+
+        assert!(app.fatal_error.is_none());
+        assert_eq!(seen_rx.recv().await.as_deref(), Some("acme"));
+        assert_eq!(seen_rx.recv().await.as_deref(), Some("acme-labs"));
+    }
+
+    #[test]
+    fn rejected_token_from_elsewhere_says_where_to_replace_it() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        app.token_origin = Some(String::from("the GITHUB_TOKEN environment variable"));
+
+        app.handle_api_message(ApiMessage::InvalidToken(String::from("GitHub rejected the token")));
+
+        assert!(app.quit_confirm.is_none());
+        assert!(app.last_error.as_deref().is_some_and(|e| e.ends_with("Replace the token in the GITHUB_TOKEN environment variable.")));
+    }
+
+    #[test]
+    fn tiny_terminal_renders_without_panicking() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        let area = Rect::new(0, 0, 5, 3);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        assert!(buf.content().iter().any(|cell| cell.symbol() != " "));
+
+        for popup in [PopupInfo::loading(), PopupInfo::new(String::from("Error"), String::from("a\nlong\nmessage\nthat\nwraps"))] {
+            show_popup(&Some(popup), area, &mut buf);
+        }
+        show_popup(&Some(PopupInfo::loading()), Rect::new(0, 0, 0, 0), &mut Buffer::empty(Rect::new(0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn success_toast_is_shown_then_expires() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        app.handle_api_message(ApiMessage::Success(String::from("Added label 'gpu' to runner web-01")));
+        let (_, shown) = app.toast.clone().unwrap();
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("Added label 'gpu' to runner web-01"));
+
+        app.expire_toast(shown + TOAST_DURATION - Duration::from_millis(1));
+        assert!(app.toast.is_some());
+        app.expire_toast(shown + TOAST_DURATION);
+        assert!(app.toast.is_none());
+    }
+
+    #[test]
+    fn rate_limit_is_shown_at_the_end_of_the_status_bar() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        app.handle_api_message(ApiMessage::RateLimit(RateLimit { limit: 5000, remaining: 4999, reset: 1_700_000_000 }));
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let status: String = (0..area.width).map(|x| buf[(x, area.height - 2)].symbol()).collect();
+        assert!(status.ends_with("API quota 4999/5000 "), "{}", status);
+        assert!(status.starts_with(" 0 runners"), "{}", status);
+    }
+
+    #[test]
+    fn auto_refresh_fires_once_per_interval_and_keeps_the_view() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let config = Config { refresh_interval_secs: Some(30), ..Config::default() };
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &config, Rc::new(RefCell::new(State::default())));
+        let runner = |id: usize, name: &str| Runner::from(serde_json::from_value::<ApiRunner>(serde_json::json!({
+            "id": id, "name": name, "os": "linux", "status": "online", "busy": false, "labels": [],
+        })).unwrap());
+        let start = app.last_refresh;
+
+        // Still waiting for the initial load.
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(30)));
+        app.handle_api_message(ApiMessage::RunnerList(vec![runner(1, "db-01"), runner(2, "web-01")]));
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(29)));
+        app.runners_tab.handle_input(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        let selected = app.runners_tab.selected().unwrap().id;
+
+        app.selected_tab = Tab::RunnerGroups;
+        let due = start + Duration::from_secs(30);
+        assert!(app.auto_refresh_due(due));
+        app.auto_refresh_pending = true;
+        assert!(!app.auto_refresh_due(due + Duration::from_secs(30)));
+
+        app.handle_api_message(ApiMessage::RunnerList(vec![runner(3, "api-01"), runner(1, "db-01"), runner(2, "web-01")]));
+        assert!(!app.auto_refresh_pending);
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+        assert_eq!(app.runners_tab.selected().map(|r| r.id), Some(selected));
+        assert!(app.runners_tab.status_line().starts_with(" 3 runners"));
+        assert!(app.auto_refresh_due(due + Duration::from_secs(30)));
+
+        // Another tab waiting on its request holds the refresh back too.
+        app.selected_tab = Tab::Runners;
+        app.repos_tab.load_if_needed();
+        assert!(!app.auto_refresh_due(due + Duration::from_secs(60)));
+        app.handle_api_message(ApiMessage::RepoList(vec![]));
+        assert!(app.auto_refresh_due(due + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn header_flags_stale_data_until_the_next_list_arrives() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        let now = app.last_success + Duration::from_secs(120);
+        assert_eq!(app.error_indicator(now), None);
+
+        app.handle_api_message(ApiMessage::Error(String::from("Could not fetch runners: error sending request for url (https://api.github.com/)")));
+        assert_eq!(app.error_indicator(now).as_deref(), Some("⚠ offline, data from 2m ago "));
+        app.handle_api_message(ApiMessage::Error(String::from("Could not fetch runners: HTTP status server error (502 Bad Gateway)")));
+        assert_eq!(app.error_indicator(now).as_deref(), Some("⚠ API error, data from 2m ago "));
+
+        app.handle_api_message(ApiMessage::RunnerList(vec![]));
+        assert_eq!(app.error_indicator(Instant::now()), None);
+    }
+
+    #[test]
+    fn esc_while_loading_asks_before_quitting() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert!(app.runners_tab.is_busy());
+
+        app.handle_key(esc);
+        assert!(!app.should_exit);
+        assert!(app.quit_confirm.is_some());
+        app.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE));
+        assert!(!app.should_exit);
+        assert!(app.quit_confirm.is_none());
+        assert!(app.runners_tab.is_busy());
+
+        app.handle_key(esc);
+        app.handle_key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
+        assert!(app.should_exit);
+        assert!(!app.reenter_token);
+    }
+
+    #[test]
+    fn rejected_token_offers_to_enter_a_new_one() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        app.handle_api_message(ApiMessage::InvalidToken(String::from("GitHub rejected the token")));
+        assert!(!app.runners_tab.is_busy());
+        assert!(app.quit_confirm.is_some());
+
+        app.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.should_exit);
+        assert!(app.reenter_token);
+    }
+
+    #[test]
+    fn starts_loading_and_fills_in_when_runners_arrive() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        assert!(app.runners_tab.status_line().starts_with(" 0 runners"));
+
+        let runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 1, "name": "web-01", "os": "linux", "status": "online", "busy": false, "labels": [],
+        })).unwrap();
+        app.handle_api_message(ApiMessage::RunnerList(vec![Runner::from(runner)]));
+
+        assert!(app.runners_tab.status_line().starts_with(" 1 runners"));
+        assert!(app.runners_tab.selected().is_some());
+    }
+
+    #[test]
+    fn tab_cycles_through_the_configured_tabs_only() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let tabs = vec![String::from("repos"), String::from("bogus"), String::from("groups"), String::from("repos")];
+        let config = Config { tabs, ..Config::default() };
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &config, Rc::new(RefCell::new(State::default())));
+        assert_eq!(app.tabs, vec![Tab::Repos, Tab::RunnerGroups]);
+        assert_eq!(app.selected_tab, Tab::Repos);
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchRepos)));
+
+        let area = Rect::new(0, 0, 80, 20);
+        let mut buf = Buffer::empty(area);
+        app.render(area, &mut buf);
+        let header: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+        assert!(header.starts_with(" Repos   Runner Groups "));
+        assert!(!header.contains("Runners"));
+
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        app.handle_key(tab);
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+
+        // Runners arriving don't open the hidden runners tab, nor move off the current one.
+        app.handle_api_message(ApiMessage::RunnerList(vec![]));
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+        app.switch_org(String::from("acme-labs"));
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+
+        app.handle_key(tab);
+        assert_eq!(app.selected_tab, Tab::Repos);
+        app.handle_api_message(ApiMessage::RunnerList(vec![]));
+        assert_eq!(app.selected_tab, Tab::Repos);
+        app.handle_api_message(ApiMessage::RepoList(vec![]));
+        while rx.try_recv().is_ok() {}
+        app.switch_org(String::from("acme"));
+        assert_eq!(app.selected_tab, Tab::Repos);
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::SwitchOrg(_))));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchRepos)));
+
+        assert_eq!(Tab::configured(&[]), Tab::all());
+    }
+
+    #[test]
+    fn ctrl_g_jumps_to_the_group_of_the_selected_runner() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_api_tx, api_rx) = mpsc::unbounded_channel();
+        let mut app = AppState::new(Tab::Runners, &tx, api_rx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        let groups = [(1, "Default", true), (2, "gpu", false)].into_iter()
+            .map(|(id, name, default)| RunnerGroup::from(serde_json::from_value::<crate::client::api::ApiRunnerGroup>(runner_group_json(id, name, "all", default)).unwrap()))
+            .collect();
+        app.handle_api_message(ApiMessage::RunnerGroupList(groups));
+        let runner = |group: &str| {
+            let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+                "id": 1, "name": "web-01", "os": "linux", "status": "online", "busy": false, "labels": [],
+            })).unwrap();
+            let mut runner = Runner::from(api_runner);
+            runner.group = Some(group.to_string());
+            runner
+        };
+        let ctrl_g = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL);
+
+        app.handle_api_message(ApiMessage::RunnerList(vec![runner("gpu")]));
+        app.handle_key(ctrl_g);
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+        assert_eq!(app.runner_groups_tab.selected().map(|g| g.name.clone()), Some(String::from("gpu")));
+
+        app.selected_tab = Tab::Runners;
+        app.handle_api_message(ApiMessage::RunnerList(vec![runner("gone")]));
+        app.handle_key(ctrl_g);
+        assert_eq!(app.selected_tab, Tab::RunnerGroups);
+        // The missing group only gets a message, the previous selection stays.
+        assert_eq!(app.runner_groups_tab.selected().map(|g| g.name.clone()), Some(String::from("gpu")));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::client::api::{ApiRunner, ApiRunnerGroup, RunnerGroupVisibility};
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::str::FromStr;
+use serde::Deserialize;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RunnerStatus {
     Online,
     Offline,
@@ -21,6 +23,16 @@ impl FromStr for RunnerStatus {
     }
 }
 
+impl RunnerStatus {
+    pub fn next(&self) -> RunnerStatus {
+        match self {
+            RunnerStatus::Online => RunnerStatus::Offline,
+            RunnerStatus::Offline => RunnerStatus::Busy,
+            RunnerStatus::Busy => RunnerStatus::Online,
+        }
+    }
+}
+
 impl Display for RunnerStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let value = match self {
@@ -37,15 +49,63 @@ pub struct Runner {
     pub id: usize,
     pub status: RunnerStatus,
     pub name: String,
+    pub os: String,
+    /// Custom label names, the only ones that can be added or removed.
     pub labels: Vec<String>,
+    /// Labels GitHub assigns on registration (self-hosted, OS, architecture), which can't be removed.
+    pub default_labels: Vec<String>,
+    pub busy: bool,
     pub group: Option<String>,
+    pub ephemeral: Option<bool>,
+    pub missing_labels: Vec<String>,
+    pub drained: bool,
+    /// Unix time the runner was last known to be active. GitHub doesn't expose when a runner
+    /// finished its last job, so this is the last time we saw it online, if ever.
+    pub last_active: Option<u64>,
+    /// Status before the runner recently went online or offline, to point out flapping runners.
+    pub previous_status: Option<RunnerStatus>,
+}
+
+/// Lifecycle of an ephemeral (just-in-time) runner, which deregisters after a single job.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EphemeralState {
+    /// Registered but the agent hasn't connected yet.
+    Pending,
+    /// Connected and waiting for its job.
+    Idle,
+    /// Running its only job, it will be removed afterwards.
+    Running,
+}
+
+impl Display for EphemeralState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            EphemeralState::Pending => "pending",
+            EphemeralState::Idle => "idle",
+            EphemeralState::Running => "running",
+        };
+        write!(f, "{}", value)
+    }
 }
 
 impl Display for Runner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let group_name = if let Some(group) = &self.group { group } else { &"default".to_string()};
-        let labels = self.labels.join(" | ");
-        let text = format!("{} [{}] ({}) | {}", &self.name, &self.status, &group_name, &labels);
+        let labels = self.sorted_labels().join(" | ");
+        let ephemeral = match self.ephemeral_state() {
+            Some(state) => format!(" ⚡{}", state),
+            None => String::new(),
+        };
+        let drained = if self.drained { "⏸ " } else { "" };
+        let trend = match self.previous_status {
+            Some(RunnerStatus::Offline) => "↑ ",
+            Some(_) => "↓ ",
+            None => "",
+        };
+        let mut text = format!("{}{}{} [{}] ({}) os={}{} | {}", trend, drained, &self.name, &self.status, &group_name, &self.os, &ephemeral, &labels);
+        if !self.missing_labels.is_empty() {
+            text.push_str(&format!(" ⚠ missing: {}", self.missing_labels.join(", ")));
+        }
         write!(f, "{}", text)
     }
 }
@@ -57,26 +117,107 @@ impl From<ApiRunner> for Runner {
         } else {
             RunnerStatus::from_str(&runner.status).unwrap()
         };
-        Runner::new(
+        let mut result = Runner::new(
             runner.id,
             status,
             runner.name,
+            runner.os,
             runner.labels.iter().filter(|label| label.label_type == "custom").map(|x| x.name.to_string()).collect(),
-            None
-        )
+            None,
+            runner.ephemeral,
+        );
+        result.busy = runner.busy;
+        result.default_labels = runner.labels.iter().filter(|label| label.label_type != "custom").map(|x| x.name.to_string()).collect();
+        result
     }
 }
 
 impl Runner {
-    fn new(id: usize, status: RunnerStatus, name: String, labels: Vec<String>, group: Option<String>) -> Self {
+    fn new(id: usize, status: RunnerStatus, name: String, os: String, labels: Vec<String>, group: Option<String>, ephemeral: Option<bool>) -> Self {
         Runner {
             id,
             status,
             name,
+            os,
             labels,
+            default_labels: vec![],
+            busy: false,
             group,
+            ephemeral,
+            missing_labels: vec![],
+            drained: false,
+            last_active: None,
+            previous_status: None,
         }
     }
+
+    /// Labels sorted alphabetically without duplicates, for presentation only.
+    /// Anything sent to the API keeps using `labels` as returned by GitHub.
+    pub fn sorted_labels(&self) -> Vec<&str> {
+        let mut labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        labels
+    }
+
+    pub fn ephemeral_state(&self) -> Option<EphemeralState> {
+        if !self.ephemeral.unwrap_or(false) {
+            return None;
+        }
+        Some(match self.status {
+            RunnerStatus::Offline => EphemeralState::Pending,
+            RunnerStatus::Online => EphemeralState::Idle,
+            RunnerStatus::Busy => EphemeralState::Running,
+        })
+    }
+}
+
+/// Labels every runner is expected to carry, globally and per group name.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RequiredLabels {
+    #[serde(default)]
+    pub all: Vec<String>,
+    #[serde(default)]
+    pub groups: HashMap<String, Vec<String>>,
+}
+
+impl RequiredLabels {
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty() && self.groups.values().all(|labels| labels.is_empty())
+    }
+
+    /// Required labels the runner has neither as a custom nor as a default label. Like GitHub,
+    /// and the label filter, this ignores case.
+    pub fn missing_for(&self, runner: &Runner) -> Vec<String> {
+        let group = runner.group.as_deref().unwrap_or("default");
+        self.all.iter()
+            .chain(self.groups.get(group).into_iter().flatten())
+            .filter(|label| !runner.labels.iter().chain(&runner.default_labels).any(|l| l.eq_ignore_ascii_case(label)))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FleetSummary {
+    pub total: usize,
+    pub online: usize,
+    pub busy: usize,
+    pub offline: usize,
+}
+
+impl FleetSummary {
+    pub fn from_runners<'r>(runners: impl IntoIterator<Item = &'r Runner>) -> Self {
+        runners.into_iter().fold(FleetSummary::default(), |mut summary, runner| {
+            summary.total += 1;
+            match runner.status {
+                RunnerStatus::Online => summary.online += 1,
+                RunnerStatus::Busy => summary.busy += 1,
+                RunnerStatus::Offline => summary.offline += 1,
+            }
+            summary
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -84,36 +225,69 @@ pub struct RunnerGroup {
     pub id: usize,
     pub name: String,
     pub visibility: RunnerGroupVisibility,
+    pub protected: bool,
+    /// The organization's built-in group, which can't be deleted.
+    pub default: bool,
+    pub allows_public_repositories: bool,
+    /// When set, only `selected_workflows` can use the group's runners.
+    pub restricted_to_workflows: bool,
+    pub selected_workflows: Vec<String>,
+    /// Workflow restrictions inherited from the enterprise can't be changed by the org.
+    pub workflow_restrictions_read_only: bool,
+    /// API URL listing the group's runners.
+    pub runners_url: String,
+    /// API URL listing the repositories of a group visible to selected repositories only.
+    pub selected_repositories_url: Option<String>,
 }
 
 impl Display for RunnerGroup {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} ID: {}", self.name.to_string(), self.id)
+        let lock = if self.protected { "🔒 " } else { "" };
+        write!(f, "{}{} ID: {}", lock, self.name, self.id)
     }
 }
 
 impl RunnerGroup {
     fn new(id: usize, name: String, visibility: RunnerGroupVisibility) -> Self {
         RunnerGroup {
-            id, name, visibility
+            id, name, visibility, protected: false, default: false,
+            allows_public_repositories: false,
+            restricted_to_workflows: false,
+            selected_workflows: vec![],
+            workflow_restrictions_read_only: false,
+            runners_url: String::new(),
+            selected_repositories_url: None,
         }
     }
 }
 
 impl From<ApiRunnerGroup> for RunnerGroup {
     fn from(group: ApiRunnerGroup) -> Self {
-        RunnerGroup::new(
+        let mut result = RunnerGroup::new(
             group.id,
             group.name,
             group.visibility
-        )
+        );
+        result.default = group.default;
+        result.allows_public_repositories = group.allows_public_repositories;
+        result.restricted_to_workflows = group.restricted_to_workflows;
+        result.selected_workflows = group.selected_workflows;
+        result.workflow_restrictions_read_only = group.workflow_restrictions_read_only;
+        result.runners_url = group.runners_url;
+        result.selected_repositories_url = group.selected_repositories_url;
+        result
     }
 }
 
 pub enum RunnerOperation {
     AddLabel,
     RemoveLabel,
+    SetLabels,
+    ManageLabels,
     ChangeGroup,
+    RemoveFromGroup,
+    Details,
+    Delete,
 }
 
 impl Display for RunnerOperation {
@@ -121,37 +295,162 @@ impl Display for RunnerOperation {
         let value = match self {
             RunnerOperation::AddLabel => "Add label",
             RunnerOperation::RemoveLabel => "Remove label",
+            RunnerOperation::SetLabels => "Set labels",
+            RunnerOperation::ManageLabels => "Manage labels",
             RunnerOperation::ChangeGroup => "Change group",
+            RunnerOperation::RemoveFromGroup => "Move to default group",
+            RunnerOperation::Details => "Show details",
+            RunnerOperation::Delete => "Delete runner",
         };
         write!(f, "{}", value)
     }
 }
 
 impl RunnerOperation {
+    /// Stable name used to enable or hide the operation from config.
+    pub fn key(&self) -> &'static str {
+        match self {
+            RunnerOperation::AddLabel => "add_label",
+            RunnerOperation::RemoveLabel => "remove_label",
+            RunnerOperation::SetLabels => "set_labels",
+            RunnerOperation::ManageLabels => "manage_labels",
+            RunnerOperation::ChangeGroup => "change_group",
+            RunnerOperation::RemoveFromGroup => "remove_from_group",
+            RunnerOperation::Details => "details",
+            RunnerOperation::Delete => "delete",
+        }
+    }
+
     pub fn all() -> Vec<RunnerOperation> {
-        vec![RunnerOperation::AddLabel, RunnerOperation::RemoveLabel, RunnerOperation::ChangeGroup]
+        vec![RunnerOperation::AddLabel, RunnerOperation::RemoveLabel, RunnerOperation::SetLabels, RunnerOperation::ManageLabels, RunnerOperation::ChangeGroup, RunnerOperation::RemoveFromGroup, RunnerOperation::Details, RunnerOperation::Delete]
     }
 }
 
 pub enum GroupOperation {
     AddRepo,
+    RemoveRepo,
     CreateGroup,
+    CloneGroup,
+    DeleteGroup,
+    Rename,
+    ToggleVisibility,
+    RestrictWorkflows,
     GetRepos,
+    ListRunners,
+    ToggleProtected,
+    FindDuplicates,
 }
 
 impl Display for GroupOperation {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let value = match self {
             GroupOperation::AddRepo => "Add repo",
+            GroupOperation::RemoveRepo => "Remove repo",
             GroupOperation::CreateGroup => "Create group",
+            GroupOperation::CloneGroup => "Clone group with its repo access",
+            GroupOperation::DeleteGroup => "Delete group",
+            GroupOperation::Rename => "Rename group",
+            GroupOperation::ToggleVisibility => "Toggle visibility (selected/all repos)",
+            GroupOperation::RestrictWorkflows => "Restrict workflows",
             GroupOperation::GetRepos => "Get repos accesses",
+            GroupOperation::ListRunners => "List runners",
+            GroupOperation::ToggleProtected => "Toggle do not modify",
+            GroupOperation::FindDuplicates => "Find duplicate groups",
         };
         write!(f, "{}", value)
     }
 }
 
 impl GroupOperation {
+    /// Stable name used to enable or hide the operation from config.
+    pub fn key(&self) -> &'static str {
+        match self {
+            GroupOperation::AddRepo => "add_repo",
+            GroupOperation::RemoveRepo => "remove_repo",
+            GroupOperation::CreateGroup => "create_group",
+            GroupOperation::CloneGroup => "clone_group",
+            GroupOperation::DeleteGroup => "delete_group",
+            GroupOperation::Rename => "rename",
+            GroupOperation::ToggleVisibility => "toggle_visibility",
+            GroupOperation::RestrictWorkflows => "restrict_workflows",
+            GroupOperation::GetRepos => "get_repos",
+            GroupOperation::ListRunners => "list_runners",
+            GroupOperation::ToggleProtected => "toggle_protected",
+            GroupOperation::FindDuplicates => "find_duplicates",
+        }
+    }
+
     pub fn all() -> Vec<GroupOperation> {
-        vec![GroupOperation::CreateGroup, GroupOperation::GetRepos, GroupOperation::AddRepo]
+        vec![GroupOperation::CreateGroup, GroupOperation::CloneGroup, GroupOperation::DeleteGroup, GroupOperation::Rename, GroupOperation::ToggleVisibility, GroupOperation::RestrictWorkflows, GroupOperation::GetRepos, GroupOperation::ListRunners, GroupOperation::AddRepo, GroupOperation::RemoveRepo, GroupOperation::ToggleProtected, GroupOperation::FindDuplicates]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runner(ephemeral: Option<bool>) -> Runner {
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "web-01",
+            "os": "linux",
+            "status": "online",
+            "busy": false,
+            "ephemeral": ephemeral,
+            "labels": [{ "id": 1, "name": "gpu", "type": "custom" }],
+        })).unwrap();
+        Runner::from(api_runner)
+    }
+
+    #[test]
+    fn display_shows_os_and_hides_unknown_ephemeral() {
+        assert_eq!(runner(None).to_string(), "web-01 [online] (default) os=linux | gpu");
+        assert_eq!(runner(Some(false)).to_string(), "web-01 [online] (default) os=linux | gpu");
+    }
+
+    #[test]
+    fn display_marks_ephemeral_runners() {
+        assert_eq!(runner(Some(true)).to_string(), "web-01 [online] (default) os=linux ⚡idle | gpu");
+    }
+
+    #[test]
+    fn default_labels_are_kept_apart_from_custom_ones() {
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "web-01",
+            "os": "linux",
+            "status": "online",
+            "busy": false,
+            "labels": [
+                { "id": 1, "name": "self-hosted", "type": "read-only" },
+                { "id": 2, "name": "Linux", "type": "read-only" },
+                { "id": 3, "name": "gpu", "type": "custom" },
+            ],
+        })).unwrap();
+        let runner = Runner::from(api_runner);
+        assert_eq!(runner.labels, vec!["gpu"]);
+        assert_eq!(runner.default_labels, vec!["self-hosted", "Linux"]);
+    }
+
+    #[test]
+    fn required_default_labels_count_as_present() {
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "web-01",
+            "os": "linux",
+            "status": "online",
+            "busy": false,
+            "labels": [
+                { "id": 1, "name": "self-hosted", "type": "read-only" },
+                { "id": 2, "name": "Linux", "type": "read-only" },
+                { "id": 3, "name": "gpu", "type": "custom" },
+            ],
+        })).unwrap();
+        let runner = Runner::from(api_runner);
+        let required = RequiredLabels {
+            all: vec![String::from("self-hosted"), String::from("linux"), String::from("X64")],
+            groups: HashMap::from([(String::from("default"), vec![String::from("gpu"), String::from("cuda")])]),
+        };
+        assert_eq!(required.missing_for(&runner), vec!["X64", "cuda"]);
+    }
+}
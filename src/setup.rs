@@ -0,0 +1,122 @@
+use std::path::Path;
+use color_eyre::Result;
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::Rect,
+    widgets::Widget,
+    DefaultTerminal,
+};
+use crate::ui::{InputField, Popup};
+use crate::utils::config::{write_dot_env, DOT_ENV};
+
+pub enum SetupOutcome {
+    Submitted { organization: String, token: String },
+    Cancelled,
+}
+
+/// Form asking for the organization and token on the first run, when nothing configures them,
+/// or for a new token after GitHub rejected the configured one.
+pub struct SetupForm {
+    /// Why the form is shown.
+    intro: String,
+    organization: InputField,
+    token: InputField,
+    /// Whether keys go to the token rather than the organization.
+    token_focused: bool,
+    error: Option<String>,
+}
+
+impl SetupForm {
+    pub fn first_run() -> Self {
+        SetupForm {
+            intro: format!("No {} found. Enter the organization to manage and a token allowed to administer its runners.", DOT_ENV),
+            organization: InputField::default(),
+            token: InputField::default(),
+            token_focused: false,
+            error: None,
+        }
+    }
+
+    /// Starts on the token, keeping the configured organization.
+    pub fn new_token(organization: &str) -> Self {
+        let mut form = SetupForm::first_run();
+        form.intro = format!("GitHub rejected the token. Enter a new one, it replaces the token in {}.", DOT_ENV);
+        form.organization.set_text(organization);
+        form.token_focused = true;
+        form
+    }
+
+    /// Tab and the arrows switch fields, Enter moves on to the token and then submits,
+    /// Esc cancels. `None` while the form is still being filled in.
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<SetupOutcome> {
+        match code {
+            KeyCode::Esc => return Some(SetupOutcome::Cancelled),
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => self.token_focused = !self.token_focused,
+            KeyCode::Enter if !self.token_focused => self.token_focused = true,
+            KeyCode::Enter => {
+                let organization = self.organization.text().trim().to_string();
+                let token = self.token.text().trim().to_string();
+                if organization.is_empty() || token.is_empty() {
+                    self.error = Some(String::from("Both the organization and the token are required"));
+                    self.token_focused = !organization.is_empty();
+                    return None;
+                }
+                return Some(SetupOutcome::Submitted { organization, token });
+            }
+            code => {
+                let field = if self.token_focused { &mut self.token } else { &mut self.organization };
+                field.handle_key(code);
+            }
+        }
+        None
+    }
+
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let marker = |focused: bool| if focused { "> " } else { "  " };
+        let mut content = format!(
+            "{}\n\n{}Organization: {}\n{}Token: {}\n",
+            self.intro,
+            marker(!self.token_focused), if self.token_focused { self.organization.text().to_string() } else { self.organization.render() },
+            marker(self.token_focused), if self.token_focused { self.token.render_masked() } else { "*".repeat(self.token.text().chars().count()) },
+        );
+        if let Some(error) = &self.error {
+            content.push_str(&format!("\n{}\n", error));
+        }
+        content.push_str(&format!("\n[Tab] next field  [Enter] save to {}  [Esc] quit", DOT_ENV));
+        let width = (area.width * 2 / 3).max(area.width.min(40));
+        let height = 12.min(area.height);
+        let popup_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        Popup::default()
+            .title("Setup")
+            .content(content)
+            .render(popup_area, buf);
+    }
+}
+
+/// Shows `form` until it is submitted, writing the answers to `path`.
+/// Returns false if the user quit instead.
+pub fn run(terminal: &mut DefaultTerminal, path: &Path, mut form: SetupForm) -> Result<bool> {
+    loop {
+        terminal.draw(|frame| form.render(frame.area(), frame.buffer_mut()))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match form.handle_key(key.code) {
+            Some(SetupOutcome::Submitted { organization, token }) => match write_dot_env(path, &organization, &token) {
+                Ok(()) => return Ok(true),
+                Err(e) => form.error = Some(format!("Could not write {}: {}", path.display(), e)),
+            },
+            Some(SetupOutcome::Cancelled) => return Ok(false),
+            None => {}
+        }
+    }
+}
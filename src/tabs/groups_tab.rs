@@ -1,24 +1,150 @@
 use std::cell::RefCell;
 use std::fmt::Display;
+use std::ops::Deref;
 use std::rc::Rc;
 use cli_log::debug;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::prelude::Color;
 use tokio::sync::mpsc;
-use crate::model::runners::{GroupOperation, RunnerGroup};
+use crate::model::runners::{GroupOperation, Runner, RunnerGroup};
 use crate::{show_popup, PopupInfo, Tab, TODO_HEADER_STYLE};
-use crate::client::api::{ApiRepository, ApiRunnerGroupCreate, RunnerGroupVisibility};
+use crate::client::api::{ApiRepository, ApiRunnerGroupCreate, ApiRunnerGroupUpdate, RunnerGroupVisibility};
 use crate::backend::BackendMessage;
-use crate::ui::{FilterableList, SelectableList};
+use crate::ui::{vim_navigation, ConfirmPopup, FilterableList, InputField, Predicate, SelectableList};
+use crate::utils::clipboard;
+use crate::utils::config::{parse_list, Config};
+use crate::utils::state::State;
 
 enum Stage {
     SelectGroup,
     SelectOperation,
     CreateGroup,
+    /// Naming a new group that gets the repository access of the selected one.
+    CloneGroup,
+    RenameGroup,
+    RestrictWorkflows,
     AddRepo,
     ListRepos,
+    ListRunners,
+    RemoveRepo,
+    MergeCandidates,
+    AccessOverview,
+    AccessRepos,
+}
+
+/// Which groups the list shows by visibility, cycled with Ctrl+V on top of the text filter.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum VisibilityFilter {
+    Any,
+    OnlyAll,
+    OnlySelected,
+}
+
+impl VisibilityFilter {
+    fn next(self) -> Self {
+        match self {
+            VisibilityFilter::Any => VisibilityFilter::OnlyAll,
+            VisibilityFilter::OnlyAll => VisibilityFilter::OnlySelected,
+            VisibilityFilter::OnlySelected => VisibilityFilter::Any,
+        }
+    }
+
+    fn matches(self, group: &RunnerGroup) -> bool {
+        match self {
+            VisibilityFilter::Any => true,
+            VisibilityFilter::OnlyAll => group.visibility == RunnerGroupVisibility::All,
+            VisibilityFilter::OnlySelected => group.visibility == RunnerGroupVisibility::Selected,
+        }
+    }
+}
+
+/// Org repositories offered while typing in the add repo popup.
+#[derive(Default)]
+struct RepoSuggestions {
+    /// `None` until the repository list arrives, typed names are sent as-is meanwhile.
+    names: Option<Vec<String>>,
+    requested: bool,
+    matches: Vec<String>,
+    selected: usize,
+}
+
+const MAX_SUGGESTIONS: usize = 8;
+
+/// GitHub caps repository names at 100 characters.
+const MAX_REPO_NAME_LENGTH: usize = 100;
+
+/// Trims a typed group name, refusing an empty one.
+fn validate_group_name(input: &str) -> Result<String, String> {
+    let name = input.trim();
+    if name.is_empty() {
+        return Err(String::from("The group name can't be empty"));
+    }
+    Ok(name.to_string())
+}
+
+/// Trims a typed repository name and checks it only uses the characters GitHub allows:
+/// ASCII letters, digits, `-`, `_` and `.`.
+fn validate_repo_name(input: &str) -> Result<String, String> {
+    let name = input.trim();
+    if name.is_empty() {
+        return Err(String::from("The repository name can't be empty"));
+    }
+    if name.len() > MAX_REPO_NAME_LENGTH {
+        return Err(format!("Repository names are at most {} characters long", MAX_REPO_NAME_LENGTH));
+    }
+    if name == "." || name == ".." {
+        return Err(format!("{} is not a valid repository name", name));
+    }
+    if let Some(c) = name.chars().find(|c| !c.is_ascii_alphanumeric() && !matches!(c, '-' | '_' | '.')) {
+        return Err(format!("Repository names can't contain '{}', only letters, digits, '-', '_' and '.'", c));
+    }
+    Ok(name.to_string())
+}
+
+impl RepoSuggestions {
+    fn update(&mut self, input: &str) {
+        let input = input.to_lowercase();
+        self.matches = self.names.iter()
+            .flatten()
+            .filter(|name| name.to_lowercase().contains(&input))
+            .cloned()
+            .collect();
+        self.selected = 0;
+    }
+
+    fn select_next(&mut self) {
+        if self.selected + 1 < self.matches.len().min(MAX_SUGGESTIONS) {
+            self.selected += 1;
+        }
+    }
+
+    fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    fn selected(&self) -> Option<&String> {
+        self.matches.get(self.selected)
+    }
+
+    fn render(&self, input: &InputField) -> String {
+        let mut text = input.render();
+        match &self.names {
+            None => text.push_str("\n(loading repositories, Enter sends the name as typed)"),
+            Some(_) if self.matches.is_empty() => text.push_str("\n(no matching repository)"),
+            Some(_) => {
+                for (i, name) in self.matches.iter().take(MAX_SUGGESTIONS).enumerate() {
+                    let marker = if i == self.selected { ">" } else { " " };
+                    text.push_str(&format!("\n{} {}", marker, name));
+                }
+                if self.matches.len() > MAX_SUGGESTIONS {
+                    text.push_str(&format!("\n  ... {} more", self.matches.len() - MAX_SUGGESTIONS));
+                }
+            }
+        }
+        text
+    }
 }
 
 pub struct RunnersGroupsTab<'a> {
@@ -26,35 +152,170 @@ pub struct RunnersGroupsTab<'a> {
     operations: SelectableList<GroupOperation>,
     dynamic_list: SelectableList<Box<dyn Display>>,
     stage: Stage,
-    input_buffer: Rc<RefCell<String>>,
+    input_buffer: Rc<RefCell<InputField>>,
+    /// Why the typed input was rejected, shown under it until the next edit.
+    input_error: Rc<RefCell<Option<String>>>,
     popup_content: Option<PopupInfo>,
+    /// API URLs of the group whose details are shown, the runners one copied if the popup is
+    /// closed with c and the repositories one with r.
+    detail_urls: Option<(String, Option<String>)>,
+    confirm: Option<ConfirmPopup<BackendMessage>>,
+    merge_candidates: Vec<RunnerGroup>,
+    group_repos: Vec<ApiRepository>,
+    /// Every group with the repositories allowed to use it, for the access overview.
+    group_access: Vec<(RunnerGroup, Vec<ApiRepository>)>,
+    /// Row of the overview whose repositories are listed.
+    access_selected: usize,
+    repo_suggestions: Rc<RefCell<RepoSuggestions>>,
+    /// Groups visible to all repositories, for the status bar.
+    open_groups: usize,
+    visibility_filter: VisibilityFilter,
+    /// h/j/k/l move in the lists that don't filter, see `Config::vim_keys`.
+    vim_keys: bool,
+    state: Rc<RefCell<State>>,
     tx: &'a mpsc::UnboundedSender<BackendMessage>,
 }
 
 impl <'a> RunnersGroupsTab<'a> {
-    pub fn new(groups: Vec<RunnerGroup>, tx: &'a mpsc::UnboundedSender<BackendMessage>) -> Self {
+    pub fn new(tx: &'a mpsc::UnboundedSender<BackendMessage>, config: &Config, state: Rc<RefCell<State>>) -> Self {
         let style = TODO_HEADER_STYLE.bg(Color::Green);
         RunnersGroupsTab {
-            groups: FilterableList::new(groups, style).with_first_selected(),
-            operations: SelectableList::new(GroupOperation::all(), style).with_first_selected(),
+            groups: FilterableList::new(vec![], style).with_empty_hint("No runner groups found").with_first_selected(),
+            operations: SelectableList::new(
+                GroupOperation::all().into_iter().filter(|op| config.operations.is_enabled(op.key())).collect(),
+                style
+            ).with_first_selected(),
             stage: Stage::SelectGroup,
             dynamic_list: SelectableList::new(vec![], style),
-            input_buffer: Rc::new(RefCell::new(String::new())),
+            input_buffer: Rc::new(RefCell::new(InputField::default())),
+            input_error: Rc::new(RefCell::new(None)),
             popup_content: None,
+            detail_urls: None,
+            confirm: None,
+            merge_candidates: vec![],
+            group_repos: vec![],
+            group_access: vec![],
+            access_selected: 0,
+            repo_suggestions: Rc::new(RefCell::new(RepoSuggestions::default())),
+            open_groups: 0,
+            visibility_filter: VisibilityFilter::Any,
+            vim_keys: config.vim_keys,
+            state,
             tx
         }
     }
 
+    fn cycle_visibility_filter(&mut self) {
+        let filter = self.visibility_filter.next();
+        self.visibility_filter = filter;
+        let predicate: Option<Predicate<RunnerGroup>> = match filter {
+            VisibilityFilter::Any => None,
+            _ => Some(Box::new(move |g| filter.matches(g))),
+        };
+        self.groups.set_predicate(predicate);
+        self.groups.select_first();
+    }
+
+    /// Visibility, public repository access and workflow restrictions of the selected group.
+    fn show_group_details(&mut self) {
+        let Some(group) = self.selected() else {
+            return;
+        };
+        let visibility = match group.visibility {
+            RunnerGroupVisibility::All => "all repositories",
+            RunnerGroupVisibility::Selected => "selected repositories",
+        };
+        let public = if group.allows_public_repositories { "allowed" } else { "not allowed" };
+        let mut details = format!("ID: {}\nVisibility: {}\nDefault group: {}\nPublic repositories: {}",
+                                  group.id, visibility, if group.default { "yes" } else { "no" }, public);
+        match (group.restricted_to_workflows, group.selected_workflows.is_empty()) {
+            (false, _) => details.push_str("\nWorkflows: any workflow can use this group"),
+            (true, true) => details.push_str("\nWorkflows: restricted, but no workflow is selected"),
+            (true, false) => {
+                details.push_str("\nWorkflows: restricted to");
+                for workflow in &group.selected_workflows {
+                    details.push_str(&format!("\n- {}", workflow));
+                }
+            }
+        }
+        if group.workflow_restrictions_read_only {
+            details.push_str("\nWorkflow restrictions are read-only (set by the enterprise)");
+        }
+        details.push_str(&format!("\n\nRunners URL: {}", group.runners_url));
+        match &group.selected_repositories_url {
+            Some(url) => details.push_str(&format!("\nRepositories URL: {}\n\nPress c to copy the runners URL, r the repositories URL.", url)),
+            None => details.push_str("\nRepositories URL: none, the group isn't limited to selected repositories\n\nPress c to copy the runners URL."),
+        }
+        let title = format!("Group {}", group.name);
+        self.detail_urls = Some((group.runners_url.clone(), group.selected_repositories_url.clone()));
+        self.popup_content = Some(PopupInfo::new(title, details));
+    }
+
+    /// Shows `popup` in place of the current one, whose detail URLs no longer apply.
+    fn replace_popup(&mut self, popup: Option<PopupInfo>) {
+        self.detail_urls = None;
+        self.popup_content = popup;
+    }
+
+    /// Shows the group list with `name` selected, or the default group when `name` is `None`.
+    /// Filters are cleared first so the group can't be hidden by them.
+    pub fn select_by_name(&mut self, name: Option<&str>) {
+        self.stage = Stage::SelectGroup;
+        self.groups.input_buffer.clear();
+        self.visibility_filter = VisibilityFilter::Any;
+        self.groups.set_predicate(None);
+        let found = match name {
+            Some(name) => self.groups.select_where(|g| g.name == name),
+            None => self.groups.select_where(|g| g.default),
+        };
+        if !found {
+            let name = name.unwrap_or("default");
+            let message = format!("Group {} isn't in the loaded list, refresh it with Ctrl+R", name);
+            self.replace_popup(Some(PopupInfo::new(String::from("Group not found"), message)));
+        }
+    }
+
+    pub fn status_line(&self) -> String {
+        let total = self.groups.items.len();
+        format!(" {} groups: {} all repositories, {} selected repositories", total, self.open_groups, total - self.open_groups)
+    }
+
+    /// Whether a request sent to the backend hasn't been answered yet.
+    pub fn is_busy(&self) -> bool {
+        self.popup_content.as_ref().is_some_and(|p| p.is_loading)
+    }
+
     pub fn toggle_loading(&mut self) {
         if let Some(popup) = &self.popup_content {
             if popup.is_loading {
-                self.popup_content = None
+                self.replace_popup(None)
             }
         }
     }
 
+    /// Takes a periodically refreshed group list, unless an operation is under way on the
+    /// current one. Keeps the filters and the selected group.
+    pub fn refresh_groups(&mut self, groups: Vec<RunnerGroup>) {
+        if !matches!(self.stage, Stage::SelectGroup) || self.popup_content.is_some() || self.confirm.is_some() {
+            return;
+        }
+        let selected = self.selected().map(|g| g.id);
+        self.set_groups(groups);
+        if let Some(id) = selected {
+            self.groups.select_where(|g| g.id == id);
+        }
+    }
+
     pub fn set_groups(&mut self, groups: Vec<RunnerGroup>) {
-        self.groups.items = groups.into_iter().map(|g|Rc::new(g)).collect();
+        let state = self.state.borrow();
+        self.groups.items = groups.into_iter()
+            .map(|mut g| {
+                g.protected = state.is_protected(g.id);
+                Rc::new(g)
+            })
+            .collect();
+        drop(state);
+        self.open_groups = self.groups.items.iter().filter(|g| g.visibility == RunnerGroupVisibility::All).count();
         self.groups.filter_items();
         self.toggle_loading();
         self.stage = Stage::SelectGroup;
@@ -62,34 +323,261 @@ impl <'a> RunnersGroupsTab<'a> {
 
     pub fn set_group_repos(&mut self, repos: Vec<ApiRepository>) {
         self.toggle_loading();
-        let display_items = repos.into_iter()
+        let display_items = repos.iter()
+            .cloned()
             .map(|it|Box::new(it) as Box<dyn Display>)
             .collect();
         self.dynamic_list.set_items(display_items);
-        self.stage = Stage::ListRepos;
+        self.group_repos = repos;
+        if matches!(self.stage, Stage::CloneGroup) {
+            return self.ask_clone_name();
+        }
+        if !matches!(self.stage, Stage::RemoveRepo) {
+            self.stage = Stage::ListRepos;
+        }
+    }
+
+    /// Shows the runners of the selected group, or a message when it has none.
+    pub fn set_group_runners(&mut self, runners: Vec<Runner>) {
+        self.toggle_loading();
+        if runners.is_empty() {
+            let name = self.selected().map(|g| g.name.clone()).unwrap_or_default();
+            self.replace_popup(Some(PopupInfo::new(String::from("No runners"), format!("Group {} has no runners", name))));
+            return;
+        }
+        let display_items = runners.into_iter()
+            .map(|r| Box::new(format!("{} [{}]", r.name, r.status)) as Box<dyn Display>)
+            .collect();
+        self.dynamic_list.set_items(display_items);
+        self.dynamic_list.select_first();
+        self.stage = Stage::ListRunners;
+    }
+
+    fn request_group_access(&mut self) {
+        self.replace_popup(Some(PopupInfo::loading()));
+        self.tx.send(BackendMessage::FetchGroupAccess)
+            .expect("Could not send group access command to backend");
+    }
+
+    pub fn set_group_access(&mut self, result: Result<Vec<(RunnerGroup, Vec<ApiRepository>)>, String>) {
+        self.toggle_loading();
+        match result {
+            Ok(groups) => {
+                self.group_access = groups;
+                self.access_selected = 0;
+                self.show_access_overview();
+            }
+            Err(e) => self.replace_popup(Some(PopupInfo::new(String::from("Error"), e))),
+        }
+    }
+
+    /// One row per group with how many repositories can use it.
+    fn show_access_overview(&mut self) {
+        let display_items = self.group_access.iter()
+            .map(|(group, repos)| {
+                let access = match group.visibility {
+                    RunnerGroupVisibility::All => String::from("all repositories"),
+                    RunnerGroupVisibility::Selected => format!("{} repos", repos.len()),
+                };
+                Box::new(format!("{}: {}", group.name, access)) as Box<dyn Display>
+            })
+            .collect();
+        self.dynamic_list.set_items(display_items);
+        self.dynamic_list.state.select(Some(self.access_selected));
+        self.stage = Stage::AccessOverview;
+    }
+
+    /// Lists the repositories of the overview row under the cursor. Groups open to all
+    /// repositories have no list of their own, they only get a message.
+    fn show_access_repos(&mut self) {
+        let Some(idx) = self.dynamic_list.state.selected().filter(|idx| *idx < self.group_access.len()) else {
+            return;
+        };
+        let (group, repos) = &self.group_access[idx];
+        let message = match group.visibility {
+            RunnerGroupVisibility::All => Some(format!("Group {} can be used by every repository of the organization", group.name)),
+            RunnerGroupVisibility::Selected if repos.is_empty() => Some(format!("No repository has access to group {}", group.name)),
+            RunnerGroupVisibility::Selected => None,
+        };
+        if let Some(message) = message {
+            self.replace_popup(Some(PopupInfo::new(String::from("Repository access"), message)));
+            return;
+        }
+        let display_items = repos.iter()
+            .cloned()
+            .map(|repo| Box::new(repo) as Box<dyn Display>)
+            .collect();
+        self.access_selected = idx;
+        self.dynamic_list.set_items(display_items);
+        self.dynamic_list.select_first();
+        self.stage = Stage::AccessRepos;
+    }
+
+    /// Org repositories used to autocomplete the add repo input.
+    pub fn set_org_repos(&mut self, repos: &[ApiRepository]) {
+        let mut suggestions = self.repo_suggestions.borrow_mut();
+        suggestions.names = Some(repos.iter().map(|repo| repo.name.clone()).collect());
+        suggestions.update(self.input_buffer.borrow().text());
+    }
+
+    /// Forgets the org repositories, e.g. after switching to another organization.
+    pub fn clear_org_repos(&mut self) {
+        *self.repo_suggestions.borrow_mut() = RepoSuggestions::default();
+    }
+
+    pub fn set_duplicate_groups(&mut self, result: Result<Vec<RunnerGroup>, String>) {
+        self.toggle_loading();
+        match result {
+            Ok(groups) if groups.is_empty() => {
+                self.replace_popup(Some(PopupInfo::new(String::from("Duplicates"), String::from("No group has the same repository access"))));
+            }
+            Ok(groups) => {
+                let display_items = groups.iter()
+                    .cloned()
+                    .map(|g| Box::new(g) as Box<dyn Display>)
+                    .collect();
+                self.dynamic_list.set_items(display_items);
+                self.dynamic_list.select_first();
+                self.merge_candidates = groups;
+                self.stage = Stage::MergeCandidates;
+            }
+            Err(e) => {
+                self.replace_popup(Some(PopupInfo::new(String::from("Error"), e)));
+            }
+        }
+    }
+
+    pub fn show_merge_result(&mut self, result: Result<String, String>) {
+        self.replace_popup(Some(match result {
+            Ok(message) => PopupInfo::new(String::from("Merge"), message),
+            Err(e) => PopupInfo::new(String::from("Merge failed"), e),
+        }));
+    }
+
+    pub fn show_updated_group(&mut self, group: &RunnerGroup) {
+        let visibility = match group.visibility {
+            RunnerGroupVisibility::All => "all repositories",
+            RunnerGroupVisibility::Selected => "selected repositories",
+        };
+        let mut message = format!("{} is available to {}", group.name, visibility);
+        if group.restricted_to_workflows {
+            message.push_str(&format!(", restricted to {} workflow(s)", group.selected_workflows.len()));
+        }
+        self.replace_popup(Some(PopupInfo::new(String::from("Group updated"), message)));
+        // The runners tab lists runners under their group's name, which may have just changed.
+        self.tx.send(BackendMessage::RefreshGroup(group.id))
+            .expect("Could not send refresh group command to backend");
+    }
+
+    pub fn show_export_result(&mut self, result: Result<String, String>) {
+        self.replace_popup(Some(match result {
+            Ok(path) => PopupInfo::new(String::from("Export"), format!("Group access written to {}", path)),
+            Err(e) => PopupInfo::new(String::from("Export failed"), e),
+        }));
+    }
+
+    /// Replaces whatever popup is shown (usually the loading one) with the backend error.
+    pub fn show_error(&mut self, message: String) {
+        self.replace_popup(Some(PopupInfo::new(String::from("Error"), message)));
+    }
+
+    fn selected_candidate(&self) -> Option<&RunnerGroup> {
+        self.dynamic_list.state.selected().and_then(|idx| self.merge_candidates.get(idx))
+    }
+
+    fn confirm_merge(&mut self) {
+        let Some(candidate) = self.selected_candidate() else {
+            return;
+        };
+        let (candidate_id, candidate_name) = (candidate.id, candidate.name.clone());
+        if self.reject_if_protected(candidate_id, &candidate_name) {
+            return;
+        }
+        let group = self.selected().unwrap();
+        let prompt = format!("Move all runners of {} into {} and delete {}?", candidate_name, group.name, candidate_name);
+        self.confirm = Some(ConfirmPopup::new("Merge groups", prompt, BackendMessage::MergeGroups(candidate_id, group.id)));
     }
 
     pub fn selected(&self) -> Option<&RunnerGroup> {
         self.groups.selected()
     }
 
-    fn add_to_input(&mut self, c: char) {
-        self.input_buffer.borrow_mut().push(c);
+    fn edit_input(&mut self, code: KeyCode) {
+        if self.input_buffer.borrow_mut().handle_key(code) {
+            *self.input_error.borrow_mut() = None;
+        }
     }
 
-    fn remove_last_input(&mut self) {
-        self.input_buffer.borrow_mut().pop();
+    fn drain_input(&mut self) -> String {
+        *self.input_error.borrow_mut() = None;
+        self.input_buffer.borrow_mut().take()
     }
 
-    fn drain_input(&mut self) -> String {
-        std::mem::replace(&mut *self.input_buffer.borrow_mut(), String::new())
+    /// Popup editing the shared input, with the validation error below it if there is one.
+    fn input_popup(&self, title: &str) -> PopupInfo {
+        let input_clone = Rc::clone(&self.input_buffer);
+        let error_clone = Rc::clone(&self.input_error);
+        PopupInfo::new_dynamic(String::from(title), Box::new(move || {
+            let mut text = input_clone.borrow().render();
+            if let Some(error) = error_clone.borrow().as_ref() {
+                text.push_str(&format!("\n{}", error));
+            }
+            text
+        }))
+    }
+
+    /// Keeps the input popup open with `error` shown under the typed text.
+    fn reject_input(&mut self, error: String) {
+        *self.input_error.borrow_mut() = Some(error);
+    }
+
+    fn start_add_repo(&mut self) {
+        let mut suggestions = self.repo_suggestions.borrow_mut();
+        if !suggestions.requested {
+            suggestions.requested = true;
+            self.tx.send(BackendMessage::FetchRepos)
+                .expect("Could not send fetch repos command to backend");
+        }
+        suggestions.update(self.input_buffer.borrow().text());
+        drop(suggestions);
+        let input_clone = Rc::clone(&self.input_buffer);
+        let error_clone = Rc::clone(&self.input_error);
+        let suggestions_clone = Rc::clone(&self.repo_suggestions);
+        self.replace_popup(Some(
+            PopupInfo::new_dynamic(String::from("Input repo name:"), Box::new(move || {
+                let mut text = suggestions_clone.borrow().render(&input_clone.borrow());
+                if let Some(error) = error_clone.borrow().as_ref() {
+                    text.push_str(&format!("\n{}", error));
+                }
+                text
+            }))));
+        self.stage = Stage::AddRepo;
+    }
+
+    fn update_repo_input(&mut self, code: KeyCode) {
+        self.edit_input(code);
+        self.repo_suggestions.borrow_mut().update(self.input_buffer.borrow().text());
     }
 
     fn add_repo(&mut self) {
-        self.popup_content = Some(PopupInfo::loading());
-        let input = self.drain_input();
+        let suggestions = self.repo_suggestions.borrow();
+        // Once the list is known only an existing repository can be picked.
+        let validated = match &suggestions.names {
+            Some(_) => match suggestions.selected() {
+                Some(name) => Ok(name.clone()),
+                None => return,
+            },
+            None => validate_repo_name(self.input_buffer.borrow().text()),
+        };
+        drop(suggestions);
+        let repo = match validated {
+            Ok(name) => name,
+            Err(e) => return self.reject_input(e),
+        };
+        self.drain_input();
+        self.replace_popup(Some(PopupInfo::loading()));
         let group = self.selected().unwrap();
-        self.tx.send(BackendMessage::AddRepoToGroup(input, group.id))
+        self.tx.send(BackendMessage::AddRepoToGroup(repo, group.id))
             .expect("Could not send add repo command to backend");
         self.stage = Stage::SelectGroup;
     }
@@ -100,9 +588,201 @@ impl <'a> RunnersGroupsTab<'a> {
             .expect("Could not send get group repos command to backend");
     }
 
+    /// Fetches the repositories of the selected group to give them to a new one.
+    fn start_clone_group(&mut self) {
+        let group = self.selected().unwrap();
+        if group.visibility == RunnerGroupVisibility::All {
+            let message = format!("{} is open to all repositories, there is no access list to copy", group.name);
+            self.replace_popup(Some(PopupInfo::new(String::from("Clone group"), message)));
+            return;
+        }
+        let group_id = group.id;
+        self.stage = Stage::CloneGroup;
+        self.replace_popup(Some(PopupInfo::loading()));
+        self.tx.send(BackendMessage::GetGroupRepos(group_id))
+            .expect("Could not send get group repos command to backend");
+    }
+
+    fn ask_clone_name(&mut self) {
+        let name = format!("{}-copy", self.selected().unwrap().name);
+        self.input_buffer.borrow_mut().set_text(name);
+        let title = format!("Name of the new group ({} repos copied):", self.group_repos.len());
+        self.replace_popup(Some(self.input_popup(&title)));
+    }
+
+    fn clone_runner_group(&mut self) {
+        let validated = validate_group_name(self.input_buffer.borrow().text());
+        let name = match validated {
+            Ok(name) => name,
+            Err(e) => return self.reject_input(e),
+        };
+        self.drain_input();
+        self.replace_popup(None);
+        self.stage = Stage::SelectGroup;
+        let source = self.selected().unwrap().name.clone();
+        let prompt = format!("Create runner group {} with access to the {} repositories of {}?", name, self.group_repos.len(), source);
+        let group = ApiRunnerGroupCreate {
+            name,
+            visibility: RunnerGroupVisibility::Selected,
+            runners: vec![],
+            selected_repository_ids: self.group_repos.iter().map(|repo| repo.id).collect(),
+        };
+        self.confirm = Some(ConfirmPopup::new("Clone group", prompt, BackendMessage::CreateRunnerGroup(Box::new(group))));
+    }
+
+    fn list_runners(&mut self) {
+        let group_id = self.selected().unwrap().id;
+        self.replace_popup(Some(PopupInfo::loading()));
+        self.tx.send(BackendMessage::GetGroupRunners(group_id))
+            .expect("Could not send get group runners command to backend");
+    }
+
+    fn list_repos_to_remove(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name) = (group.id, group.name.clone());
+        if self.reject_if_protected(group_id, &group_name) {
+            return;
+        }
+        self.dynamic_list.set_items(vec![]);
+        self.group_repos = vec![];
+        self.replace_popup(Some(PopupInfo::loading()));
+        self.stage = Stage::RemoveRepo;
+        self.tx.send(BackendMessage::GetGroupRepos(group_id))
+            .expect("Could not send get group repos command to backend");
+    }
+
+    fn remove_repo(&mut self) {
+        let Some(repo) = self.dynamic_list.state.selected().and_then(|idx| self.group_repos.get(idx)) else {
+            return;
+        };
+        let prompt = format!("Revoke access of {} to runner group {}?", repo.name, self.selected().unwrap().name);
+        let action = BackendMessage::RemoveRepoFromGroup(self.selected().unwrap().id, repo.id);
+        self.confirm = Some(ConfirmPopup::new("Remove repo", prompt, action));
+    }
+
+    fn confirm_delete_group(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name) = (group.id, group.name.clone());
+        if group.default {
+            let message = format!("{} is the default runner group and can't be deleted", group_name);
+            self.replace_popup(Some(PopupInfo::new(String::from("Delete group"), message)));
+            return;
+        }
+        if self.reject_if_protected(group_id, &group_name) {
+            return;
+        }
+        let prompt = format!("Delete runner group {}? Its runners will move to the default group.", group_name);
+        self.confirm = Some(ConfirmPopup::new("Delete group", prompt, BackendMessage::DeleteRunnerGroup(group_id)));
+    }
+
+    fn start_rename(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name) = (group.id, group.name.clone());
+        if self.reject_if_protected(group_id, &group_name) {
+            return;
+        }
+        self.input_buffer.borrow_mut().set_text(group_name);
+        self.replace_popup(Some(self.input_popup("Input new group name:")));
+        self.stage = Stage::RenameGroup;
+    }
+
+    fn rename_group(&mut self) {
+        let validated = validate_group_name(self.input_buffer.borrow().text());
+        let name = match validated {
+            Ok(name) => name,
+            Err(e) => return self.reject_input(e),
+        };
+        self.drain_input();
+        let group_id = self.selected().unwrap().id;
+        self.update_group(group_id, ApiRunnerGroupUpdate { name: Some(name), ..Default::default() });
+    }
+
+    fn toggle_visibility(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name) = (group.id, group.name.clone());
+        let visibility = match group.visibility {
+            RunnerGroupVisibility::Selected => RunnerGroupVisibility::All,
+            RunnerGroupVisibility::All => RunnerGroupVisibility::Selected,
+        };
+        if self.reject_if_protected(group_id, &group_name) {
+            return;
+        }
+        self.update_group(group_id, ApiRunnerGroupUpdate { visibility: Some(visibility), ..Default::default() });
+    }
+
+    fn start_restrict_workflows(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name, workflows) = (group.id, group.name.clone(), group.selected_workflows.join(", "));
+        if group.workflow_restrictions_read_only {
+            let message = format!("The workflow restrictions of {} are managed by the enterprise and can't be changed here", group_name);
+            self.replace_popup(Some(PopupInfo::new(String::from("Restrict workflows"), message)));
+            return;
+        }
+        if self.reject_if_protected(group_id, &group_name) {
+            return;
+        }
+        self.input_buffer.borrow_mut().set_text(workflows);
+        self.replace_popup(Some(self.input_popup("Workflows allowed to use the group, comma separated (empty lifts the restriction):")));
+        self.stage = Stage::RestrictWorkflows;
+    }
+
+    /// Sends the typed workflow refs, e.g. `octo-org/app/.github/workflows/ci.yml@refs/heads/main`.
+    fn restrict_workflows(&mut self) {
+        let workflows = parse_list(&self.drain_input().replace('\n', ","));
+        let group_id = self.selected().unwrap().id;
+        self.update_group(group_id, ApiRunnerGroupUpdate {
+            restricted_to_workflows: Some(!workflows.is_empty()),
+            selected_workflows: Some(workflows),
+            ..Default::default()
+        });
+    }
+
+    fn update_group(&mut self, group_id: usize, patch: ApiRunnerGroupUpdate) {
+        self.replace_popup(Some(PopupInfo::loading()));
+        self.tx.send(BackendMessage::UpdateRunnerGroup(group_id, Box::new(patch)))
+            .expect("Could not send update group command to backend");
+        self.stage = Stage::SelectGroup;
+    }
+
+    fn toggle_protected(&mut self) {
+        let group = self.selected().unwrap();
+        let (group_id, group_name) = (group.id, group.name.clone());
+        let protected = self.state.borrow_mut().toggle_protected(group_id);
+        let saved = self.state.borrow().save();
+        if let Err(e) = saved {
+            self.replace_popup(Some(PopupInfo::new(String::from("Error"), format!("Could not save state file: {}", e))));
+            return;
+        }
+        let groups = self.groups.items.iter().map(|g| g.deref().clone()).collect();
+        self.set_groups(groups);
+        let message = if protected {
+            format!("Group {} is now protected from destructive operations", group_name)
+        } else {
+            format!("Group {} is no longer protected", group_name)
+        };
+        self.replace_popup(Some(PopupInfo::new(String::from("Do not modify"), message)));
+    }
+
+    /// Shows a message and returns true when the group is tagged as "do not modify".
+    /// Destructive operations must call this before dispatching anything to the backend.
+    fn reject_if_protected(&mut self, group_id: usize, group_name: &str) -> bool {
+        if !self.state.borrow().is_protected(group_id) {
+            return false;
+        }
+        let message = format!("Group {} is tagged as \"do not modify\". Remove the tag to change it.", group_name);
+        self.replace_popup(Some(PopupInfo::new(String::from("Protected group"), message)));
+        true
+    }
+
     fn create_runner_group(&mut self) {
+        let validated = validate_group_name(self.input_buffer.borrow().text());
+        let name = match validated {
+            Ok(name) => name,
+            Err(e) => return self.reject_input(e),
+        };
+        self.drain_input();
         let group = ApiRunnerGroupCreate {
-            name: self.drain_input(),
+            name,
             visibility: RunnerGroupVisibility::Selected,
             runners: vec![],
             selected_repository_ids: vec![],
@@ -115,10 +795,17 @@ impl <'a> RunnersGroupsTab<'a> {
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
         match self.stage {
             Stage::SelectGroup => {
-                let list_title = String::from("Runner Groups");
+                let mut list_title = match self.visibility_filter {
+                    VisibilityFilter::Any => String::from("Runner Groups"),
+                    VisibilityFilter::OnlyAll => String::from("Runner Groups [all repositories]"),
+                    VisibilityFilter::OnlySelected => String::from("Runner Groups [selected repositories]"),
+                };
+                if self.groups.is_fuzzy() {
+                    list_title.push_str(" [fuzzy]");
+                }
                 self.groups.render(area, buf, &list_title);
             }
-            Stage::SelectOperation | Stage::AddRepo => {
+            Stage::SelectOperation | Stage::AddRepo | Stage::RenameGroup | Stage::RestrictWorkflows | Stage::CloneGroup => {
                 let group = self.selected().unwrap();
                 let list_title = format!("Select operation - {}", group.name);
                 self.operations.render(area, buf, &list_title);
@@ -129,14 +816,81 @@ impl <'a> RunnersGroupsTab<'a> {
                 let list_title = format!("Repos with access to group - {}", group.name);
                 self.dynamic_list.render(area, buf, &list_title);
             }
+            Stage::ListRunners => {
+                let group = self.selected().unwrap();
+                let list_title = format!("Runners in group - {} ({})", group.name, self.dynamic_list.items.len());
+                self.dynamic_list.render(area, buf, &list_title);
+            }
+            Stage::RemoveRepo => {
+                let group = self.selected().unwrap();
+                let list_title = format!("Remove repo access - {} - Enter to remove", group.name);
+                self.dynamic_list.render(area, buf, &list_title);
+            }
+            Stage::MergeCandidates => {
+                let group = self.selected().unwrap();
+                let list_title = format!("Groups with the same repo access as {} - Enter to merge", group.name);
+                self.dynamic_list.render(area, buf, &list_title);
+            }
+            Stage::AccessOverview => {
+                let list_title = format!("Repository access by group ({}) - Enter to see the repos", self.group_access.len());
+                self.dynamic_list.render(area, buf, &list_title);
+            }
+            Stage::AccessRepos => {
+                let name = self.group_access.get(self.access_selected).map(|(g, _)| g.name.as_str()).unwrap_or_default();
+                let list_title = format!("Repos with access to group - {} ({})", name, self.dynamic_list.items.len());
+                self.dynamic_list.render(area, buf, &list_title);
+            }
         }
         show_popup(&self.popup_content, area, buf);
+        if let Some(confirm) = &self.confirm {
+            confirm.render(area, buf);
+        }
     }
 
     pub fn handle_input(&mut self, event: KeyEvent) -> bool {
+        if let Some(confirm) = &self.confirm {
+            match confirm.answer(event.code) {
+                Some(true) => {
+                    let action = self.confirm.take().unwrap().into_action();
+                    self.replace_popup(Some(PopupInfo::loading()));
+                    self.tx.send(action)
+                        .expect("Could not send confirmed command to backend");
+                }
+                Some(false) => self.confirm = None,
+                None => {}
+            }
+            return false;
+        }
         if event.code == KeyCode::Esc && self.popup_content.is_none() {
             return true;
         }
+        // Quitting while the backend works is up to the app, which asks first.
+        if event.code == KeyCode::Esc && self.is_busy() {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable && p.scroll(event.code)) {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable) {
+            self.popup_content = None;
+            if let Some((runners_url, repositories_url)) = self.detail_urls.take() {
+                self.popup_content = match (event.code, repositories_url) {
+                    (KeyCode::Char('c'), _) => Some(clipboard::copy_with_popup(&runners_url, "Runners URL")),
+                    (KeyCode::Char('r'), Some(url)) => Some(clipboard::copy_with_popup(&url, "Repositories URL")),
+                    _ => None,
+                };
+            }
+            return false;
+        }
+        // Lists that don't filter jump to the item starting with a typed letter, unless h/j/k/l
+        // are set to move instead.
+        let event = match self.stage {
+            _ if !self.vim_keys => event,
+            Stage::SelectOperation if self.popup_content.is_none() => vim_navigation(event),
+            Stage::ListRepos | Stage::ListRunners | Stage::RemoveRepo | Stage::MergeCandidates
+            | Stage::AccessOverview | Stage::AccessRepos => vim_navigation(event),
+            _ => event,
+        };
         match self.stage {
             Stage::SelectGroup => {
                 match event.code {
@@ -145,8 +899,26 @@ impl <'a> RunnersGroupsTab<'a> {
                     KeyCode::Up => self.groups.select_previous(),
                     KeyCode::Home => self.groups.select_first(),
                     KeyCode::End => self.groups.select_last(),
+                    KeyCode::PageDown => self.groups.select_page(true, false),
+                    KeyCode::PageUp => self.groups.select_page(false, false),
+                    KeyCode::Char('d') if event.modifiers.contains(KeyModifiers::CONTROL) => self.groups.select_page(true, true),
+                    KeyCode::Char('u') if event.modifiers.contains(KeyModifiers::CONTROL) => self.groups.select_page(false, true),
                     KeyCode::Right | KeyCode::Enter => self.stage = Stage::SelectOperation,
+                    KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.replace_popup(Some(PopupInfo::loading()));
+                        self.tx.send(BackendMessage::FetchGroups { skip_cache: true })
+                            .expect("Could not send refresh command to backend");
+                    }
+                    KeyCode::Char('v') if event.modifiers.contains(KeyModifiers::CONTROL) => self.cycle_visibility_filter(),
+                    KeyCode::Char('s') if event.modifiers.contains(KeyModifiers::CONTROL) => self.show_group_details(),
+                    KeyCode::Char('a') if event.modifiers.contains(KeyModifiers::CONTROL) => self.request_group_access(),
+                    KeyCode::Char('e') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.replace_popup(Some(PopupInfo::loading()));
+                        self.tx.send(BackendMessage::ExportGroupsCsv)
+                            .expect("Could not send export command to backend");
+                    }
                     KeyCode::Backspace => self.groups.remove_last_input(),
+                    KeyCode::Char('o') if event.modifiers.contains(KeyModifiers::CONTROL) => self.groups.toggle_fuzzy(),
                     KeyCode::Char(c) => self.groups.update_filter(c),
                     _ => {}
                 }
@@ -156,34 +928,33 @@ impl <'a> RunnersGroupsTab<'a> {
                     KeyCode::Up => self.operations.select_previous(),
                     KeyCode::Down => self.operations.select_next(),
                     KeyCode::Left => self.stage = Stage::SelectGroup,
-                    KeyCode::Char(c) => match self.popup_content {
-                        Some(_) => self.add_to_input(c),
-                        _ => {}
-                    }
-                    KeyCode::Backspace => self.remove_last_input(),
                     KeyCode::Right | KeyCode::Enter => match self.operations.selected() {
-                        Some(GroupOperation::AddRepo) => {
-                            let input_clone = Rc::clone(&self.input_buffer);
-                            self.popup_content = Some(
-                                PopupInfo::new_dynamic(String::from("Input repo name:"),
-                                                       Box::new(move ||format!("{}_", input_clone.borrow()))
-                                ));
-                            self.stage = Stage::AddRepo;
-                        },
+                        Some(GroupOperation::AddRepo) => self.start_add_repo(),
                         Some(GroupOperation::CreateGroup) => {
                             debug!("This should be anywhere else");
-                            let input_clone = Rc::clone(&self.input_buffer);
-                            self.popup_content = Some(
-                                PopupInfo::new_dynamic(String::from("Input group name:"),
-                                                       Box::new(move ||format!("{}_", input_clone.borrow()))
-                                ));
+                            self.replace_popup(Some(self.input_popup("Input group name:")));
                             self.stage = Stage::CreateGroup;
                         },
+                        Some(GroupOperation::CloneGroup) => self.start_clone_group(),
                         Some(GroupOperation::GetRepos) => {
                             self.get_repos();
                         }
+                        Some(GroupOperation::ListRunners) => self.list_runners(),
+                        Some(GroupOperation::RemoveRepo) => self.list_repos_to_remove(),
+                        Some(GroupOperation::DeleteGroup) => self.confirm_delete_group(),
+                        Some(GroupOperation::Rename) => self.start_rename(),
+                        Some(GroupOperation::ToggleVisibility) => self.toggle_visibility(),
+                        Some(GroupOperation::RestrictWorkflows) => self.start_restrict_workflows(),
+                        Some(GroupOperation::ToggleProtected) => self.toggle_protected(),
+                        Some(GroupOperation::FindDuplicates) => {
+                            self.replace_popup(Some(PopupInfo::loading()));
+                            let group = self.selected().unwrap();
+                            self.tx.send(BackendMessage::FindDuplicateGroups(group.id))
+                                .expect("Could not send find duplicates command to backend");
+                        }
                         _ => {}
                     },
+                    KeyCode::Char(c) => self.operations.select_by_letter(c),
                     _ => {}
                 }
             }
@@ -191,12 +962,13 @@ impl <'a> RunnersGroupsTab<'a> {
                 match event.code {
                     KeyCode::Enter => self.add_repo(),
                     KeyCode::Esc => {
-                        self.popup_content = None;
+                        self.drain_input();
+                        self.replace_popup(None);
                         self.stage = Stage::SelectOperation;
                     }
-                    KeyCode::Char(c) => self.add_to_input(c),
-                    KeyCode::Backspace => self.remove_last_input(),
-                    _ => {}
+                    KeyCode::Up => self.repo_suggestions.borrow_mut().select_previous(),
+                    KeyCode::Down => self.repo_suggestions.borrow_mut().select_next(),
+                    code => self.update_repo_input(code),
                 }
             }
             Stage::ListRepos => {
@@ -205,20 +977,669 @@ impl <'a> RunnersGroupsTab<'a> {
                     _ => {}
                 }
             }
+            Stage::ListRunners => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectOperation,
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::RemoveRepo => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectOperation,
+                    KeyCode::Enter => self.remove_repo(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::MergeCandidates => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectOperation,
+                    KeyCode::Enter => self.confirm_merge(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::AccessOverview => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectGroup,
+                    KeyCode::Right | KeyCode::Enter => self.show_access_repos(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::AccessRepos => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.show_access_overview(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::CloneGroup => {
+                match event.code {
+                    KeyCode::Enter => self.clone_runner_group(),
+                    KeyCode::Esc => {
+                        self.drain_input();
+                        self.replace_popup(None);
+                        self.stage = Stage::SelectOperation;
+                    }
+                    code => self.edit_input(code),
+                }
+            }
+            Stage::RenameGroup => {
+                match event.code {
+                    KeyCode::Enter => self.rename_group(),
+                    KeyCode::Esc => {
+                        self.drain_input();
+                        self.replace_popup(None);
+                        self.stage = Stage::SelectOperation;
+                    }
+                    code => self.edit_input(code),
+                }
+            }
+            Stage::RestrictWorkflows => {
+                match event.code {
+                    KeyCode::Enter => self.restrict_workflows(),
+                    KeyCode::Esc => {
+                        self.drain_input();
+                        self.replace_popup(None);
+                        self.stage = Stage::SelectOperation;
+                    }
+                    code => self.edit_input(code),
+                }
+            }
             Stage::CreateGroup => {
                 match event.code {
                     KeyCode::Enter => self.create_runner_group(),
                     KeyCode::Esc => {
-                        self.popup_content = None;
+                        self.drain_input();
+                        self.replace_popup(None);
                         self.stage = Stage::SelectOperation;
                     }
-                    KeyCode::Char(c) => self.add_to_input(c),
-                    KeyCode::Backspace => self.remove_last_input(),
-                    _ => {}
+                    code => self.edit_input(code),
                 }
             }
         }
 
         false
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::{runner_group_json, ApiRunner, ApiRunnerGroup};
+    use crossterm::event::KeyModifiers;
+
+    fn group(id: usize, name: &str) -> RunnerGroup {
+        let api_group: ApiRunnerGroup = serde_json::from_value(runner_group_json(id, name, "selected", false)).unwrap();
+        RunnerGroup::from(api_group)
+    }
+
+    fn press(tab: &mut RunnersGroupsTab, code: KeyCode) -> bool {
+        tab.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(tab: &mut RunnersGroupsTab, text: &str) {
+        text.chars().for_each(|c| { press(tab, KeyCode::Char(c)); });
+    }
+
+    fn setup(tx: &mpsc::UnboundedSender<BackendMessage>) -> RunnersGroupsTab<'_> {
+        let mut tab = RunnersGroupsTab::new(tx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        tab.set_groups(vec![group(1, "linux"), group(2, "windows")]);
+        tab
+    }
+
+    fn open_operation(tab: &mut RunnersGroupsTab, operation: &str) {
+        press(tab, KeyCode::Enter);
+        while tab.operations.selected().map(|op| op.to_string()) != Some(operation.to_string()) {
+            press(tab, KeyCode::Down);
+        }
+        press(tab, KeyCode::Enter);
+    }
+
+    #[test]
+    fn add_repo_sends_typed_name_for_selected_group() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Add repo");
+        assert!(matches!(tab.stage, Stage::AddRepo));
+        assert!(tab.popup_content.is_some());
+
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchRepos)));
+
+        // The repository list hasn't arrived, so the typed name is sent as-is.
+        type_text(&mut tab, "api");
+        press(&mut tab, KeyCode::Enter);
+
+        assert!(matches!(tab.stage, Stage::SelectGroup));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        match rx.try_recv() {
+            Ok(BackendMessage::AddRepoToGroup(repo, group_id)) => {
+                assert_eq!(repo, "api");
+                assert_eq!(group_id, 1);
+            }
+            _ => panic!("expected an AddRepoToGroup message"),
+        }
+    }
+
+    #[test]
+    fn clone_group_copies_the_repository_ids_after_confirming() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        open_operation(&mut tab, "Clone group with its repo access");
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::GetGroupRepos(_))));
+
+        tab.set_group_repos(vec![
+            ApiRepository { id: 10, name: String::from("api") },
+            ApiRepository { id: 11, name: String::from("web") },
+        ]);
+        assert!(matches!(tab.stage, Stage::CloneGroup));
+        assert!(tab.popup_content.as_ref().unwrap().title.contains("2 repos"));
+        for _ in 0.."-copy".len() {
+            press(&mut tab, KeyCode::Backspace);
+        }
+        type_text(&mut tab, "-arm");
+        press(&mut tab, KeyCode::Enter);
+
+        assert!(rx.try_recv().is_err());
+        press(&mut tab, KeyCode::Char('y'));
+        match rx.try_recv() {
+            Ok(BackendMessage::CreateRunnerGroup(group)) => {
+                assert!(group.name.ends_with("-arm"));
+                assert_eq!(group.selected_repository_ids, vec![10, 11]);
+            }
+            _ => panic!("expected a CreateRunnerGroup message"),
+        }
+    }
+
+    #[test]
+    fn access_overview_counts_repos_and_drills_into_a_group() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        tab.handle_input(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchGroupAccess)));
+
+        let mut open = group(3, "everyone");
+        open.visibility = RunnerGroupVisibility::All;
+        let repos = vec![
+            ApiRepository { id: 10, name: String::from("api") },
+            ApiRepository { id: 11, name: String::from("web") },
+        ];
+        tab.set_group_access(Ok(vec![(open, vec![]), (group(1, "linux"), repos)]));
+        assert!(matches!(tab.stage, Stage::AccessOverview));
+        let rows: Vec<String> = tab.dynamic_list.items.iter().map(|it| it.to_string()).collect();
+        assert_eq!(rows, vec!["everyone: all repositories", "linux: 2 repos"]);
+
+        // Groups open to every repository have no list to drill into.
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::AccessOverview));
+        assert!((tab.popup_content.as_ref().unwrap().content)().contains("every repository"));
+        press(&mut tab, KeyCode::Enter);
+
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::AccessRepos));
+        let rows: Vec<String> = tab.dynamic_list.items.iter().map(|it| it.to_string()).collect();
+        assert_eq!(rows, vec!["api", "web"]);
+
+        press(&mut tab, KeyCode::Left);
+        assert!(matches!(tab.stage, Stage::AccessOverview));
+        assert_eq!(tab.dynamic_list.state.selected(), Some(1));
+    }
+
+    #[test]
+    fn esc_leaves_input_stage_without_sending() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Create group");
+        assert!(matches!(tab.stage, Stage::CreateGroup));
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(matches!(tab.stage, Stage::SelectOperation));
+        assert!(tab.popup_content.is_none());
+        assert!(rx.try_recv().is_err());
+
+        assert!(press(&mut tab, KeyCode::Esc));
+    }
+
+    #[test]
+    fn loading_popup_is_cleared_by_new_groups() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        open_operation(&mut tab, "Add repo");
+        type_text(&mut tab, "api");
+        press(&mut tab, KeyCode::Enter);
+
+        tab.set_groups(vec![group(1, "linux")]);
+
+        assert!(tab.popup_content.is_none());
+        assert!(matches!(tab.stage, Stage::SelectGroup));
+    }
+
+    #[test]
+    fn typing_filters_the_group_list() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        type_text(&mut tab, "win");
+
+        assert_eq!(tab.groups.filtered_items().len(), 1);
+        assert_eq!(tab.selected().unwrap().name, "windows");
+    }
+
+    #[test]
+    fn merge_confirmation_can_be_declined() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        press(&mut tab, KeyCode::Enter);
+        tab.set_duplicate_groups(Ok(vec![group(2, "windows")]));
+        assert!(matches!(tab.stage, Stage::MergeCandidates));
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.confirm.is_some());
+
+        press(&mut tab, KeyCode::Char('n'));
+        assert!(tab.confirm.is_none());
+        assert!(matches!(tab.stage, Stage::MergeCandidates));
+        assert!(tab.popup_content.is_none());
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn remove_repo_sends_highlighted_repo_and_stays_in_list() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Remove repo");
+        assert!(matches!(tab.stage, Stage::RemoveRepo));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::GetGroupRepos(1))));
+
+        let repos = serde_json::from_value(serde_json::json!([
+            { "id": 10, "name": "api" },
+            { "id": 11, "name": "web" },
+        ])).unwrap();
+        tab.set_group_repos(repos);
+        assert!(tab.popup_content.is_none());
+        assert!(matches!(tab.stage, Stage::RemoveRepo));
+
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+        press(&mut tab, KeyCode::Char('y'));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::RemoveRepoFromGroup(1, 11))));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+    }
+
+    #[test]
+    fn delete_group_refuses_the_default_group_and_confirms_others() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let mut default = group(1, "Default");
+        default.default = true;
+        tab.set_groups(vec![default, group(2, "linux")]);
+
+        open_operation(&mut tab, "Delete group");
+        assert!(tab.confirm.is_none());
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.dismissable));
+        press(&mut tab, KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+
+        press(&mut tab, KeyCode::Left);
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.confirm.is_some());
+        press(&mut tab, KeyCode::Char('y'));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::DeleteRunnerGroup(2))));
+    }
+
+    #[test]
+    fn rename_is_prefilled_and_sends_only_the_name() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Rename group");
+        assert!(matches!(tab.stage, Stage::RenameGroup));
+        let current = tab.selected().unwrap().name.clone();
+        assert_eq!(tab.input_buffer.borrow().text(), current);
+
+        type_text(&mut tab, "-old");
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::UpdateRunnerGroup(id, patch)) => {
+                assert_eq!(id, tab.selected().unwrap().id);
+                assert_eq!(patch.name, Some(format!("{}-old", current)));
+                assert!(patch.visibility.is_none());
+            }
+            _ => panic!("expected an UpdateRunnerGroup message"),
+        }
+
+        tab.show_updated_group(&group(1, "linux-old"));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::RefreshGroup(1))));
+    }
+
+    #[test]
+    fn toggle_visibility_flips_selected_to_all() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Toggle visibility (selected/all repos)");
+        match rx.try_recv() {
+            Ok(BackendMessage::UpdateRunnerGroup(_, patch)) => {
+                assert_eq!(patch.visibility, Some(RunnerGroupVisibility::All));
+                assert!(patch.name.is_none());
+            }
+            _ => panic!("expected an UpdateRunnerGroup message"),
+        }
+    }
+
+    #[test]
+    fn vim_keys_move_through_the_operations_when_enabled() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let config = Config { vim_keys: true, ..Config::default() };
+        let mut tab = RunnersGroupsTab::new(&tx, &config, Rc::new(RefCell::new(State::default())));
+        tab.set_groups(vec![group(1, "linux"), group(2, "windows")]);
+        press(&mut tab, KeyCode::Enter);
+        let first = tab.operations.selected().map(|op| op.to_string());
+
+        press(&mut tab, KeyCode::Char('j'));
+        assert_ne!(tab.operations.selected().map(|op| op.to_string()), first);
+        press(&mut tab, KeyCode::Char('k'));
+        assert_eq!(tab.operations.selected().map(|op| op.to_string()), first);
+        press(&mut tab, KeyCode::Char('h'));
+        assert!(matches!(tab.stage, Stage::SelectGroup));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn typing_l_in_the_operations_jumps_instead_of_running_one() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        press(&mut tab, KeyCode::Enter);
+        while tab.operations.selected().map(|op| op.to_string()) != Some(String::from("Toggle visibility (selected/all repos)")) {
+            press(&mut tab, KeyCode::Down);
+        }
+
+        press(&mut tab, KeyCode::Char('l'));
+
+        assert!(matches!(tab.stage, Stage::SelectOperation));
+        assert!(tab.operations.selected().is_some_and(|op| op.to_string().starts_with('L')));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn add_repo_only_sends_a_picked_suggestion() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let repos: Vec<ApiRepository> = ["web-api", "web-app", "docs"].iter().enumerate()
+            .map(|(id, name)| serde_json::from_value(serde_json::json!({ "id": id, "name": name })).unwrap())
+            .collect();
+
+        open_operation(&mut tab, "Add repo");
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchRepos)));
+        tab.set_org_repos(&repos);
+
+        type_text(&mut tab, "wbe");
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::AddRepo));
+        assert!(rx.try_recv().is_err());
+
+        press(&mut tab, KeyCode::Backspace);
+        press(&mut tab, KeyCode::Backspace);
+        type_text(&mut tab, "eb");
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::AddRepoToGroup(repo, _)) => assert_eq!(repo, "web-app"),
+            _ => panic!("expected an AddRepoToGroup message"),
+        }
+
+        open_operation(&mut tab, "Add repo");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn visibility_filter_cycles_on_top_of_the_text_filter() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let mut open = group(3, "linux-open");
+        open.visibility = RunnerGroupVisibility::All;
+        tab.set_groups(vec![group(1, "linux"), group(2, "windows"), open]);
+        let ctrl_v = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL);
+        let names = |tab: &RunnersGroupsTab| tab.groups.filtered_items().iter().map(|g| g.name.clone()).collect::<Vec<_>>();
+
+        type_text(&mut tab, "linux");
+        tab.handle_input(ctrl_v);
+        assert_eq!(names(&tab), vec!["linux-open"]);
+        tab.handle_input(ctrl_v);
+        assert_eq!(names(&tab), vec!["linux"]);
+        tab.handle_input(ctrl_v);
+        assert_eq!(names(&tab), vec!["linux", "linux-open"]);
+    }
+
+    #[test]
+    fn list_runners_shows_group_members_or_an_empty_message() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "List runners");
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::GetGroupRunners(1))));
+
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "name": "build-01",
+            "os": "linux",
+            "status": "offline",
+            "busy": false,
+            "labels": [],
+        })).unwrap();
+        tab.set_group_runners(vec![Runner::from(api_runner)]);
+        assert!(matches!(tab.stage, Stage::ListRunners));
+        assert!(tab.popup_content.is_none());
+        assert_eq!(tab.dynamic_list.selected().map(|r| r.to_string()), Some(String::from("build-01 [offline]")));
+
+        press(&mut tab, KeyCode::Left);
+        press(&mut tab, KeyCode::Enter);
+        tab.set_group_runners(vec![]);
+        assert!(matches!(tab.stage, Stage::SelectOperation));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.dismissable));
+    }
+
+    #[test]
+    fn group_details_list_security_settings() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let mut restricted = group(3, "deploy");
+        restricted.restricted_to_workflows = true;
+        restricted.selected_workflows = vec![String::from("octo-org/app/.github/workflows/cd.yml@refs/heads/main")];
+        let mut empty = group(4, "locked");
+        empty.restricted_to_workflows = true;
+        tab.set_groups(vec![restricted, empty]);
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let details = |tab: &RunnersGroupsTab| tab.popup_content.as_ref().map(|p| (p.content)()).unwrap_or_default();
+
+        tab.handle_input(ctrl_s);
+        let text = details(&tab);
+        assert!(text.contains("Public repositories: not allowed"));
+        assert!(text.contains("- octo-org/app/.github/workflows/cd.yml@refs/heads/main"));
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Down);
+        tab.handle_input(ctrl_s);
+        assert!(details(&tab).contains("restricted, but no workflow is selected"));
+    }
+
+    #[test]
+    fn group_details_show_the_api_urls() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let mut selected = group(3, "deploy");
+        selected.runners_url = String::from("https://api.github.com/orgs/acme/actions/runner-groups/3/runners");
+        selected.selected_repositories_url = Some(String::from("https://api.github.com/orgs/acme/actions/runner-groups/3/repositories"));
+        let mut all = group(4, "linux");
+        all.runners_url = String::from("https://api.github.com/orgs/acme/actions/runner-groups/4/runners");
+        tab.set_groups(vec![selected, all]);
+        let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let details = |tab: &RunnersGroupsTab| tab.popup_content.as_ref().map(|p| (p.content)()).unwrap_or_default();
+
+        tab.handle_input(ctrl_s);
+        let text = details(&tab);
+        assert!(text.contains("Runners URL: https://api.github.com/orgs/acme/actions/runner-groups/3/runners"));
+        assert!(text.contains("Repositories URL: https://api.github.com/orgs/acme/actions/runner-groups/3/repositories"));
+        assert!(text.contains("r the repositories URL"));
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.detail_urls.is_none() && tab.popup_content.is_none());
+        press(&mut tab, KeyCode::Down);
+        tab.handle_input(ctrl_s);
+        let text = details(&tab);
+        assert!(text.contains("Runners URL: https://api.github.com/orgs/acme/actions/runner-groups/4/runners"));
+        assert!(text.contains("Repositories URL: none"));
+        assert_eq!(tab.detail_urls, Some((String::from("https://api.github.com/orgs/acme/actions/runner-groups/4/runners"), None)));
+
+        // Without a repositories URL, r only closes the popup.
+        press(&mut tab, KeyCode::Char('r'));
+        assert!(tab.popup_content.is_none());
+
+        // A popup arriving over the details takes their URLs with them, c only closes it.
+        tab.handle_input(ctrl_s);
+        tab.show_error(String::from("Could not fetch runner groups"));
+        assert!(tab.detail_urls.is_none());
+        press(&mut tab, KeyCode::Char('c'));
+        assert!(tab.popup_content.is_none());
+    }
+
+    #[test]
+    fn restrict_workflows_sends_the_typed_list_unless_read_only() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+        let mut inherited = group(3, "enterprise");
+        inherited.workflow_restrictions_read_only = true;
+        tab.set_groups(vec![group(1, "linux"), inherited]);
+
+        open_operation(&mut tab, "Restrict workflows");
+        assert!(matches!(tab.stage, Stage::RestrictWorkflows));
+        type_text(&mut tab, "octo/app/.github/workflows/ci.yml@refs/heads/main, octo/app/.github/workflows/cd.yml@main");
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::UpdateRunnerGroup(1, patch)) => {
+                assert_eq!(patch.restricted_to_workflows, Some(true));
+                assert_eq!(patch.selected_workflows.unwrap().len(), 2);
+            }
+            _ => panic!("expected an UpdateRunnerGroup message"),
+        }
+
+        tab.toggle_loading();
+        press(&mut tab, KeyCode::Down);
+        open_operation(&mut tab, "Restrict workflows");
+        assert!(matches!(tab.stage, Stage::SelectOperation));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.dismissable));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn repo_names_are_trimmed_and_checked_against_allowed_characters() {
+        assert_eq!(validate_repo_name("  web.app_2-x "), Ok(String::from("web.app_2-x")));
+        assert!(validate_repo_name("   ").is_err());
+        assert!(validate_repo_name("my repo").is_err());
+        assert!(validate_repo_name("octo/app").is_err());
+        assert!(validate_repo_name("..").is_err());
+        assert!(validate_repo_name(&"a".repeat(MAX_REPO_NAME_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn group_names_are_trimmed_and_must_not_be_empty() {
+        assert_eq!(validate_group_name(" gpu runners "), Ok(String::from("gpu runners")));
+        assert!(validate_group_name(" ").is_err());
+    }
+
+    #[test]
+    fn invalid_input_keeps_the_popup_open_with_an_inline_error() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut tab = setup(&tx);
+
+        open_operation(&mut tab, "Create group");
+        type_text(&mut tab, "  ");
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::CreateGroup));
+        assert!(rx.try_recv().is_err());
+        let content = (tab.popup_content.as_ref().unwrap().content)();
+        assert!(content.contains("can't be empty"));
+
+        type_text(&mut tab, "gpu");
+        assert!(tab.input_error.borrow().is_none());
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::CreateRunnerGroup(group)) => assert_eq!(group.name, "gpu"),
+            _ => panic!("expected a CreateRunnerGroup message"),
+        }
+    }
+
+    #[test]
+    fn toggle_do_not_modify_saves_the_state_file() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let state = Rc::new(RefCell::new(State::with_path(path.clone())));
+        let mut tab = RunnersGroupsTab::new(&tx, &Config::default(), Rc::clone(&state));
+        tab.set_groups(vec![group(1, "linux"), group(2, "windows")]);
+
+        open_operation(&mut tab, "Toggle do not modify");
+        assert!(state.borrow().is_protected(1));
+        assert!(State::load_from(path.clone()).is_protected(1));
+        assert!(rx.try_recv().is_err());
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::SelectGroup));
+        open_operation(&mut tab, "Toggle do not modify");
+        assert!(!State::load_from(path).is_protected(1));
+    }
+
+    #[test]
+    fn protected_groups_refuse_destructive_operations() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = State::with_path(dir.path().join("state.json"));
+        state.toggle_protected(1);
+        state.toggle_protected(2);
+        let state = Rc::new(RefCell::new(state));
+
+        let operations = ["Delete group", "Toggle visibility (selected/all repos)", "Remove repo", "Rename group", "Restrict workflows"];
+        for operation in operations {
+            let mut tab = RunnersGroupsTab::new(&tx, &Config::default(), Rc::clone(&state));
+            tab.set_groups(vec![group(1, "linux"), group(2, "windows")]);
+
+            open_operation(&mut tab, operation);
+            assert!(tab.confirm.is_none(), "{} asked for confirmation", operation);
+            assert_eq!(tab.popup_content.as_ref().unwrap().title, "Protected group", "{} was not refused", operation);
+            assert!(matches!(tab.stage, Stage::SelectOperation), "{} left the operation list", operation);
+            assert!(rx.try_recv().is_err(), "{} sent a message", operation);
+        }
+
+        // The merge deletes the candidate, so that is the group that must not be protected.
+        let mut tab = RunnersGroupsTab::new(&tx, &Config::default(), Rc::clone(&state));
+        tab.set_groups(vec![group(1, "linux"), group(2, "windows")]);
+        press(&mut tab, KeyCode::Enter);
+        tab.set_duplicate_groups(Ok(vec![group(2, "windows")]));
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.confirm.is_none());
+        assert_eq!(tab.popup_content.as_ref().unwrap().title, "Protected group");
+        press(&mut tab, KeyCode::Char('y'));
+        assert!(rx.try_recv().is_err());
+    }
+}
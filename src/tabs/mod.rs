@@ -1,2 +1,3 @@
 pub mod groups_tab;
+pub mod repos_tab;
 pub mod runners_tab;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::Color;
+use tokio::sync::mpsc;
+use crate::backend::BackendMessage;
+use crate::client::api::ApiRepository;
+use crate::ui::FilterableList;
+use crate::{show_popup, PopupInfo, TODO_HEADER_STYLE};
+
+pub struct ReposTab<'a> {
+    repos: FilterableList<ApiRepository>,
+    popup_content: Option<PopupInfo>,
+    loaded: bool,
+    tx: &'a mpsc::UnboundedSender<BackendMessage>,
+}
+
+impl <'a> ReposTab<'a> {
+    pub fn new(tx: &'a mpsc::UnboundedSender<BackendMessage>) -> Self {
+        ReposTab {
+            repos: FilterableList::new(vec![], TODO_HEADER_STYLE.bg(Color::Magenta))
+                .with_empty_hint("No repositories found")
+                .with_first_selected(),
+            popup_content: None,
+            loaded: false,
+            tx,
+        }
+    }
+
+    /// Fetches the org repositories the first time the tab is opened.
+    pub fn load_if_needed(&mut self) {
+        if self.loaded {
+            return;
+        }
+        self.loaded = true;
+        self.popup_content = Some(PopupInfo::loading());
+        self.tx.send(BackendMessage::FetchRepos)
+            .expect("Could not send fetch repos command to backend");
+    }
+
+    /// Forgets the fetched repositories, e.g. after switching to another organization.
+    pub fn clear(&mut self) {
+        self.loaded = false;
+        self.repos.items = vec![];
+        self.repos.filter_items();
+    }
+
+    pub fn set_repos(&mut self, repos: Vec<ApiRepository>) {
+        self.loaded = true;
+        self.repos.items = repos.into_iter().map(std::rc::Rc::new).collect();
+        self.repos.filter_items();
+        self.repos.select_first();
+        if self.popup_content.as_ref().is_some_and(|p| p.is_loading) {
+            self.popup_content = None;
+        }
+    }
+
+    pub fn show_error(&mut self, message: String) {
+        // Try again next time the tab is opened if the first fetch failed.
+        if self.repos.items.is_empty() {
+            self.loaded = false;
+        }
+        self.popup_content = Some(PopupInfo::new(String::from("Error"), message));
+    }
+
+    /// Whether the repositories are still being fetched.
+    pub fn is_busy(&self) -> bool {
+        self.popup_content.as_ref().is_some_and(|p| p.is_loading)
+    }
+
+    pub fn status_line(&self) -> String {
+        format!(" {} repositories", self.repos.items.len())
+    }
+
+    pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
+        let list_title = format!("Repositories ({}) - {}", self.repos.filtered_items().len(), self.repos.input_buffer);
+        self.repos.render(area, buf, &list_title);
+        show_popup(&self.popup_content, area, buf);
+    }
+
+    pub fn handle_input(&mut self, event: KeyEvent) -> bool {
+        if event.code == KeyCode::Esc && self.popup_content.is_none() {
+            return true;
+        }
+        // Quitting while the backend works is up to the app, which asks first.
+        if event.code == KeyCode::Esc && self.is_busy() {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable && p.scroll(event.code)) {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable) {
+            self.popup_content = None;
+            return false;
+        }
+        match event.code {
+            KeyCode::Left => self.repos.select_none(),
+            KeyCode::Down => self.repos.select_next(),
+            KeyCode::Up => self.repos.select_previous(),
+            KeyCode::Home => self.repos.select_first(),
+            KeyCode::End => self.repos.select_last(),
+            KeyCode::PageDown => self.repos.select_page(true, false),
+            KeyCode::PageUp => self.repos.select_page(false, false),
+            KeyCode::Char('d') if event.modifiers.contains(KeyModifiers::CONTROL) => self.repos.select_page(true, true),
+            KeyCode::Char('u') if event.modifiers.contains(KeyModifiers::CONTROL) => self.repos.select_page(false, true),
+            KeyCode::Backspace => self.repos.remove_last_input(),
+            KeyCode::Char(c) => self.repos.update_filter(c),
+            _ => {}
+        }
+        false
+    }
+}
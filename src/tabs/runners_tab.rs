@@ -1,18 +1,73 @@
 use crate::backend::BackendMessage;
-use crate::model::runners::{Runner, RunnerOperation};
-use crate::ui::{FilterableList, SelectableList};
+use crate::model::runners::{EphemeralState, FleetSummary, RequiredLabels, Runner, RunnerGroup, RunnerOperation, RunnerStatus};
+use crate::ui::{vim_navigation, ConfirmPopup, FilterableList, InputField, Order, Predicate, SelectableList};
+use crate::client::api::{JitConfig, RegistrationToken, RunnerApplication};
+use crate::utils::clipboard;
+use crate::utils::config::{parse_list, Config};
+use crate::utils::export::write_json_export;
+use crate::utils::state::{describe_age, now_secs, State};
 use crate::{show_popup, PopupInfo, TODO_HEADER_STYLE};
-use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::prelude::{Buffer, Rect};
+use cli_log::debug;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::prelude::{Buffer, Color, Rect, Style};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::Display;
+use std::ops::Deref;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// How long a runner that went online or offline stays marked in the list.
+const STATUS_CHANGE_DURATION: Duration = Duration::from_secs(10);
+
 enum Stage {
     SelectRunner,
     SelectOp,
+    /// Typing a label or picking one of the configured presets.
+    AddLabel,
     RemoveLabels,
+    /// Editing a draft of the custom labels, sent in one request once confirmed.
+    ManageLabels,
+    ChangeGroup,
+    /// Picking the group whose runners are listed.
+    FilterGroup,
+    /// Picking the runner package whose download URL is copied.
+    SelectDownload,
+    /// Picking the group of a new just-in-time runner, see `JitDraft`.
+    JitGroup,
+}
+
+/// Inputs of a just-in-time runner asked so far: the name, then the group, then the labels.
+#[derive(Default)]
+struct JitDraft {
+    name: String,
+    group_id: Option<usize>,
+}
+
+/// GitHub's names for the platform this runs on, e.g. `("linux", "x64")` or `("osx", "arm64")`.
+fn local_platform() -> (&'static str, &'static str) {
+    let os = match std::env::consts::OS {
+        "macos" => "osx",
+        "windows" => "win",
+        os => os,
+    };
+    let architecture = match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "aarch64" => "arm64",
+        architecture => architecture,
+    };
+    (os, architecture)
+}
+
+fn status_style(status: RunnerStatus) -> Style {
+    match status {
+        RunnerStatus::Online => Style::new().fg(Color::Green),
+        RunnerStatus::Busy => Style::new().fg(Color::Yellow),
+        RunnerStatus::Offline => Style::new().fg(Color::DarkGray),
+    }
 }
 
 pub struct RunnersTab<'a> {
@@ -20,24 +75,124 @@ pub struct RunnersTab<'a> {
     operations: SelectableList<RunnerOperation>,
     dynamic_list: SelectableList<Box<dyn Display>>,
     stage: Stage,
-    input_buffer: Rc<RefCell<String>>,
+    input_buffer: Rc<RefCell<InputField>>,
     popup_content: Option<PopupInfo>,
+    confirm: Option<ConfirmPopup<BackendMessage>>,
+    required_labels: RequiredLabels,
+    drain_label: String,
+    label_presets: Vec<String>,
+    non_compliant_only: bool,
+    ephemeral_only: bool,
+    busy_only: bool,
+    /// Labels a runner must all carry to be listed, compared ignoring case.
+    label_filter: Vec<String>,
+    /// Whether the popup editing `label_filter` is open.
+    editing_label_filter: bool,
+    /// Group whose runners are listed. Picking the default group also lists runners without one.
+    group_filter: Option<String>,
+    stale_first: bool,
+    jump_status: RunnerStatus,
+    group_names: Vec<String>,
+    /// Ids of `group_names`, in the same order.
+    group_ids: Vec<usize>,
+    default_group: Option<String>,
+    /// Secret shown in the popup and what it is, copied if the popup is closed with c.
+    copyable: Option<(String, &'static str)>,
+    /// Set while the inputs of a just-in-time runner are asked for.
+    jit_draft: Option<JitDraft>,
+    /// Runner packages listed by `Stage::SelectDownload`, in the same order.
+    downloads: Vec<RunnerApplication>,
+    /// Custom labels being edited in the manage labels screen.
+    label_draft: Vec<String>,
+    /// Whether keys go to the new label input rather than the draft list.
+    label_input_focused: bool,
+    label_error: Option<String>,
+    /// Set while a bulk operation runs so the marks are cleared once it's done.
+    bulk_pending: bool,
+    /// Last label added (true) or removed on a runner, by runner id, which ^Z reverts.
+    last_label_change: Option<(usize, String, bool)>,
+    /// Set while the revert runs, so it can't be reverted in turn.
+    undoing: bool,
+    /// Counts for the status bar, updated with the runner list.
+    summary: FleetSummary,
+    /// Status of every listed runner at the last refresh, by id.
+    statuses: HashMap<usize, RunnerStatus>,
+    /// Runners that recently went online or offline: the status they had before and when it changed.
+    status_changes: HashMap<usize, (RunnerStatus, Instant)>,
+    /// h/j/k/l move in the lists that don't filter, see `Config::vim_keys`.
+    vim_keys: bool,
+    state: Rc<RefCell<State>>,
     tx: &'a mpsc::UnboundedSender<BackendMessage>,
 }
 
 impl <'a> RunnersTab<'a> {
-    pub fn new(runners: Vec<Runner>, tx: &mpsc::UnboundedSender<BackendMessage>) -> RunnersTab {
+    /// Starts empty, runners arrive through `set_runners` once the backend fetched them.
+    pub fn new(tx: &'a mpsc::UnboundedSender<BackendMessage>, config: &Config, state: Rc<RefCell<State>>) -> Self {
+        let mut runners = FilterableList::new(vec![], TODO_HEADER_STYLE)
+            .with_empty_hint("No runners found")
+            .with_first_selected()
+            .with_marks(|r: &Runner| r.id);
+        runners.set_item_style(Some(Box::new(|r: &Runner| Some(status_style(r.status)))));
+        runners.set_item_suffix(Some(Box::new(|r: &Runner| {
+            (!r.default_labels.is_empty()).then(|| format!(" · {}", r.default_labels.join(" | ")))
+        })));
         RunnersTab {
-            runners: FilterableList::new(runners, TODO_HEADER_STYLE).with_first_selected(),
-            operations: SelectableList::new(RunnerOperation::all(), TODO_HEADER_STYLE).with_first_selected(),
+            runners,
+            operations: SelectableList::new(
+                RunnerOperation::all().into_iter().filter(|op| config.operations.is_enabled(op.key())).collect(),
+                TODO_HEADER_STYLE
+            ).with_first_selected(),
             stage: Stage::SelectRunner,
             dynamic_list: SelectableList::new(vec![], TODO_HEADER_STYLE),
-            input_buffer: Rc::new(RefCell::new(String::new())),
+            input_buffer: Rc::new(RefCell::new(InputField::default())),
             popup_content: None,
+            confirm: None,
+            required_labels: config.required_labels.clone(),
+            drain_label: config.drain_label.clone(),
+            label_presets: config.label_presets.clone(),
+            non_compliant_only: false,
+            ephemeral_only: false,
+            busy_only: false,
+            label_filter: vec![],
+            editing_label_filter: false,
+            group_filter: None,
+            stale_first: false,
+            jump_status: RunnerStatus::Offline,
+            group_names: vec![],
+            group_ids: vec![],
+            default_group: None,
+            copyable: None,
+            jit_draft: None,
+            downloads: vec![],
+            label_draft: vec![],
+            label_input_focused: false,
+            label_error: None,
+            bulk_pending: false,
+            last_label_change: None,
+            undoing: false,
+            summary: FleetSummary::default(),
+            statuses: HashMap::new(),
+            status_changes: HashMap::new(),
+            vim_keys: config.vim_keys,
+            state,
             tx
         }
     }
 
+    pub fn status_line(&self) -> String {
+        format!(" {} runners: {} online, {} busy, {} offline",
+                self.summary.total, self.summary.online, self.summary.busy, self.summary.offline)
+    }
+
+    /// Whether a request sent to the backend hasn't been answered yet.
+    pub fn is_busy(&self) -> bool {
+        self.popup_content.as_ref().is_some_and(|p| p.is_loading)
+    }
+
+    pub fn set_loading(&mut self) {
+        self.popup_content = Some(PopupInfo::loading());
+    }
+
     fn toggle_loading(&mut self) {
         if let Some(popup) = &self.popup_content {
             if popup.is_loading {
@@ -47,28 +202,559 @@ impl <'a> RunnersTab<'a> {
     }
 
     pub fn set_runners(&mut self, runners: Vec<Runner>) {
-        self.runners.items = runners.into_iter().map(|r| Rc::new(r)).collect();
-        self.runners.filter_items();
+        self.replace_runners(runners);
+        if std::mem::take(&mut self.bulk_pending) {
+            self.runners.clear_marks();
+        }
+        self.runners.retain_marks();
         self.toggle_loading();
         self.stage = Stage::SelectRunner;
     }
 
-    pub fn selected(&self) -> Option<&Runner> {
-        self.runners.selected()
+    /// Swaps in a periodically refreshed list without touching the stage, popups or filters,
+    /// and keeps the same runner selected while it's still listed. An operation open on a
+    /// runner that is no longer listed is closed instead.
+    pub fn refresh_runners(&mut self, runners: Vec<Runner>) {
+        let selected = self.selected().map(|r| r.id);
+        self.replace_runners(runners);
+        self.runners.retain_marks();
+        let gone = selected.is_some_and(|id| !self.runners.select_where(|r| r.id == id));
+        if !gone {
+            return;
+        }
+        self.runners.clamp_selection();
+        if !matches!(self.stage, Stage::SelectRunner | Stage::FilterGroup | Stage::SelectDownload | Stage::JitGroup) {
+            self.stage = Stage::SelectRunner;
+            // E.g. the label being typed for it.
+            if self.popup_content.as_ref().is_some_and(|p| !p.dismissable && !p.is_loading) {
+                self.popup_content = None;
+            }
+        }
+    }
+
+    fn replace_runners(&mut self, runners: Vec<Runner>) {
+        let now = now_secs();
+        self.track_status_changes(&runners, Instant::now());
+        let mut state = self.state.borrow_mut();
+        self.runners.items = runners.into_iter()
+            .map(|mut r| {
+                r.missing_labels = self.required_labels.missing_for(&r);
+                r.drained = r.labels.contains(&self.drain_label);
+                if r.status != RunnerStatus::Offline {
+                    state.record_seen_online(r.id, now);
+                }
+                r.last_active = state.last_seen_online(r.id);
+                r.previous_status = self.status_changes.get(&r.id).map(|(previous, _)| *previous);
+                Rc::new(r)
+            })
+            .collect();
+        drop(state);
+        self.summary = FleetSummary::from_runners(self.runners.items.iter().map(|r| r.as_ref()));
+        self.runners.filter_items();
     }
 
-    fn add_to_input(&mut self, c: char) {
-        self.input_buffer.borrow_mut().push(c);
+    /// Compares the statuses with the previous list, by id. Only going online or offline counts,
+    /// picking up jobs doesn't. Runners that just appeared aren't marked, those gone are forgotten.
+    fn track_status_changes(&mut self, runners: &[Runner], now: Instant) {
+        let online = |status: RunnerStatus| status != RunnerStatus::Offline;
+        for runner in runners {
+            match self.statuses.get(&runner.id) {
+                Some(&previous) if online(previous) != online(runner.status) => {
+                    self.status_changes.insert(runner.id, (previous, now));
+                }
+                _ => {}
+            }
+        }
+        self.statuses = runners.iter().map(|r| (r.id, r.status)).collect();
+        self.status_changes.retain(|id, _| self.statuses.contains_key(id));
+    }
+
+    /// Drops the online/offline markers shown for longer than `STATUS_CHANGE_DURATION`.
+    pub fn expire_status_changes(&mut self, now: Instant) {
+        let before = self.status_changes.len();
+        self.status_changes.retain(|_, (_, changed)| now.duration_since(*changed) < STATUS_CHANGE_DURATION);
+        if self.status_changes.len() == before {
+            return;
+        }
+        for runner in self.runners.items.iter_mut() {
+            if runner.previous_status.is_some() && !self.status_changes.contains_key(&runner.id) {
+                Rc::make_mut(runner).previous_status = None;
+            }
+        }
+        self.runners.filter_items();
+    }
+
+    /// Remembers the group names offered by the change group operation.
+    pub fn set_runner_groups(&mut self, groups: &[RunnerGroup]) {
+        self.group_names = groups.iter().map(|g| g.name.clone()).collect();
+        self.group_ids = groups.iter().map(|g| g.id).collect();
+        self.default_group = groups.iter().find(|g| g.default).map(|g| g.name.clone());
+    }
+
+    /// Replaces the runners of one group, keeping the rest of the list as is.
+    /// Runners that moved into the group are dropped from their previous group.
+    pub fn merge_group_runners(&mut self, group: &str, runners: Vec<Runner>) {
+        let ids: Vec<usize> = runners.iter().map(|r| r.id).collect();
+        let mut merged: Vec<Runner> = self.runners.items.iter()
+            .filter(|r| !ids.contains(&r.id) && r.group.as_deref() != Some(group))
+            .map(|r| r.deref().clone())
+            .collect();
+        merged.extend(runners);
+        self.set_runners(merged);
     }
 
-    fn remove_last_input(&mut self) {
-        self.input_buffer.borrow_mut().pop();
+    pub fn show_export_result(&mut self, result: Result<String, String>) {
+        self.popup_content = Some(match result {
+            Ok(path) => PopupInfo::new(String::from("Export"), format!("Report written to {}", path)),
+            Err(e) => PopupInfo::new(String::from("Export failed"), e),
+        });
+    }
+
+    pub fn show_registration_token(&mut self, result: Result<RegistrationToken, String>) {
+        self.popup_content = Some(match result {
+            Ok(token) => {
+                let content = format!("{}\nExpires at {}\n\nPress c to copy it, any other key to close.", token.token, token.expires_at);
+                self.copyable = Some((token.token, "Registration token"));
+                PopupInfo::new(String::from("Registration token"), content)
+            }
+            Err(e) => PopupInfo::new(String::from("Error"), e),
+        });
+    }
+
+    /// Asks for the name of a just-in-time runner, then for its group and labels.
+    fn start_jit_config(&mut self) {
+        if self.group_ids.is_empty() {
+            self.popup_content = Some(PopupInfo::new(String::from("JIT runner"), String::from("The runner groups aren't loaded yet")));
+            return;
+        }
+        self.jit_draft = Some(JitDraft::default());
+        self.input_buffer.borrow_mut().take();
+        let input_clone = Rc::clone(&self.input_buffer);
+        self.popup_content = Some(
+            PopupInfo::new_dynamic(String::from("Name of the new just-in-time runner:"),
+                                   Box::new(move || input_clone.borrow().render())
+            ));
+    }
+
+    /// Takes the typed name or labels and asks for what comes next, sending the request once
+    /// the labels are in. Empty inputs are ignored.
+    fn advance_jit_draft(&mut self) {
+        let text = self.input_buffer.borrow().text().trim().to_string();
+        let Some(draft) = &mut self.jit_draft else {
+            return;
+        };
+        match draft.group_id {
+            None if text.is_empty() => {}
+            None => {
+                draft.name = text;
+                self.input_buffer.borrow_mut().take();
+                self.popup_content = None;
+                let items = self.group_names.iter().map(|name| {
+                    let item = if self.default_group.as_ref() == Some(name) { format!("{} (default)", name) } else { name.clone() };
+                    Box::new(item) as Box<dyn Display>
+                }).collect();
+                self.dynamic_list.set_items(items);
+                let default = self.group_names.iter().position(|name| self.default_group.as_ref() == Some(name));
+                self.dynamic_list.state.select(Some(default.unwrap_or(0)));
+                self.stage = Stage::JitGroup;
+            }
+            Some(group_id) => {
+                let labels = parse_list(&text);
+                if labels.is_empty() {
+                    return;
+                }
+                let name = std::mem::take(&mut draft.name);
+                self.jit_draft = None;
+                self.input_buffer.borrow_mut().take();
+                self.popup_content = Some(PopupInfo::loading());
+                self.tx.send(BackendMessage::GenerateJitConfig(name, group_id, labels))
+                    .expect("Could not send JIT config command to backend");
+            }
+        }
+    }
+
+    fn pick_jit_group(&mut self) {
+        let Some(group_id) = self.dynamic_list.state.selected().and_then(|i| self.group_ids.get(i.min(self.group_ids.len().saturating_sub(1)))) else {
+            return;
+        };
+        let Some(draft) = &mut self.jit_draft else {
+            return;
+        };
+        draft.group_id = Some(*group_id);
+        self.stage = Stage::SelectRunner;
+        self.input_buffer.borrow_mut().set_text("self-hosted");
+        let input_clone = Rc::clone(&self.input_buffer);
+        self.popup_content = Some(
+            PopupInfo::new_dynamic(String::from("Labels of the new runner (comma separated):"),
+                                   Box::new(move || input_clone.borrow().render())
+            ));
+    }
+
+    fn cancel_jit_draft(&mut self) {
+        self.jit_draft = None;
+        self.input_buffer.borrow_mut().take();
+        self.popup_content = None;
+        self.stage = Stage::SelectRunner;
+    }
+
+    pub fn show_jit_config(&mut self, result: Result<JitConfig, String>) {
+        self.popup_content = Some(match result {
+            Ok(config) => {
+                let content = format!("Runner {} registered, start it for a single job with:\n\n./run.sh --jitconfig {}\n\nPress c to copy the config, any other key to close.",
+                                      config.runner.name, config.encoded_jit_config);
+                self.copyable = Some((config.encoded_jit_config, "JIT config"));
+                PopupInfo::new(String::from("JIT runner"), content)
+            }
+            Err(e) => PopupInfo::new(String::from("Error"), e),
+        });
+    }
+
+    /// Lists the runner packages to copy a download URL from, starting on the platform this runs on.
+    pub fn show_downloads(&mut self, result: Result<Vec<RunnerApplication>, String>) {
+        let downloads = match result {
+            Ok(downloads) if downloads.is_empty() => {
+                self.popup_content = Some(PopupInfo::new(String::from("Runner downloads"), String::from("GitHub lists no runner downloads")));
+                return;
+            }
+            Ok(downloads) => downloads,
+            Err(e) => {
+                self.popup_content = Some(PopupInfo::new(String::from("Error"), e));
+                return;
+            }
+        };
+        self.popup_content = None;
+        let (os, architecture) = local_platform();
+        let current = downloads.iter()
+            .position(|app| app.os == os && app.architecture == architecture)
+            .unwrap_or(0);
+        self.dynamic_list.set_items(downloads.iter()
+            .map(|app| Box::new(format!("{}/{} - {}", app.os, app.architecture, app.filename)) as Box<dyn Display>)
+            .collect());
+        self.dynamic_list.state.select(Some(current));
+        self.downloads = downloads;
+        self.stage = Stage::SelectDownload;
+    }
+
+    fn copy_download_url(&mut self) {
+        let Some(app) = self.dynamic_list.state.selected().and_then(|i| self.downloads.get(i.min(self.downloads.len().saturating_sub(1)))) else {
+            return;
+        };
+        let mut message = format!("Download URL for {}/{} copied to the clipboard", app.os, app.architecture);
+        if let Some(checksum) = &app.sha256_checksum {
+            message.push_str(&format!("\n\nSHA-256 of {}:\n{}", app.filename, checksum));
+        }
+        self.popup_content = Some(match clipboard::copy(&app.download_url) {
+            Ok(()) => PopupInfo::new(String::from("Copied"), message),
+            Err(e) => PopupInfo::new(String::from("Error"), e),
+        });
+        self.stage = Stage::SelectRunner;
+    }
+
+    /// Writes the runners currently listed, filters applied, to a JSON file.
+    fn export_json(&mut self) {
+        let runners = self.runners.filtered_items().iter().map(|r| r.as_ref());
+        self.popup_content = Some(match write_json_export(runners) {
+            Ok(path) => PopupInfo::new(String::from("Export"), format!("Runner inventory written to {}", path)),
+            Err(e) => PopupInfo::new(String::from("Export failed"), e.to_string()),
+        });
+    }
+
+    /// Copies a `name<TAB>id<TAB>labels` line for the selected runner.
+    fn copy_selected(&mut self) {
+        let Some(runner) = self.runners.selected() else {
+            return;
+        };
+        let summary = format!("{}\t{}\t{}", runner.name, runner.id, runner.sorted_labels().join(","));
+        let what = format!("Runner {}", runner.name);
+        self.popup_content = Some(clipboard::copy_with_popup(&summary, &what));
+    }
+
+    /// Replaces whatever popup is shown (usually the loading one) with the backend error.
+    pub fn show_error(&mut self, message: String) {
+        self.undoing = false;
+        self.editing_label_filter = false;
+        self.popup_content = Some(PopupInfo::new(String::from("Error"), message));
+    }
+
+    /// Adds or removes the drain label so schedulers stop (or resume) assigning jobs to the runner.
+    fn toggle_drain(&mut self) {
+        let Some(runner) = self.selected() else {
+            return;
+        };
+        let message = if runner.drained {
+            BackendMessage::DeleteLabel(runner.id, self.drain_label.clone())
+        } else {
+            BackendMessage::AddLabel(runner.id, self.drain_label.clone())
+        };
+        self.popup_content = Some(PopupInfo::loading());
+        self.tx.send(message)
+            .expect("Could not send drain command to backend");
+    }
+
+    fn toggle_non_compliant(&mut self) {
+        self.non_compliant_only = !self.non_compliant_only;
+        self.apply_filters();
+    }
+
+    fn jump_to_status(&mut self, forward: bool) {
+        let status = self.jump_status;
+        self.runners.select_matching(forward, |r| r.status == status);
+    }
+
+    fn toggle_ephemeral(&mut self) {
+        self.ephemeral_only = !self.ephemeral_only;
+        self.apply_filters();
+    }
+
+    /// Shows only the runners running a job, to triage stuck ones.
+    fn toggle_busy(&mut self) {
+        self.busy_only = !self.busy_only;
+        self.apply_filters();
+    }
+
+    /// Sorts runners by when they were last active, the ones never seen online first.
+    fn toggle_stale_first(&mut self) {
+        self.stale_first = !self.stale_first;
+        let order: Option<Order<Runner>> = if self.stale_first {
+            Some(Box::new(|a: &Runner, b: &Runner| a.last_active.cmp(&b.last_active)))
+        } else {
+            None
+        };
+        self.runners.set_order(order);
+    }
+
+    /// Opens a popup to edit the labels runners must carry, prefilled with the current ones.
+    fn edit_label_filter(&mut self) {
+        self.input_buffer.borrow_mut().set_text(self.label_filter.join(", "));
+        let input_clone = Rc::clone(&self.input_buffer);
+        self.popup_content = Some(
+            PopupInfo::new_dynamic(String::from("Only runners with all of these labels (comma separated), Esc to clear:"),
+                                   Box::new(move || input_clone.borrow().render())
+            ));
+        self.editing_label_filter = true;
+    }
+
+    fn set_label_filter(&mut self, labels: Vec<String>) {
+        self.label_filter = labels;
+        self.editing_label_filter = false;
+        self.popup_content = None;
+        self.input_buffer.borrow_mut().take();
+        self.apply_filters();
+    }
+
+    /// Lists the loaded groups to pick the one whose runners are shown, after an entry for all of them.
+    fn pick_group_filter(&mut self) {
+        let mut items: Vec<Box<dyn Display>> = vec![Box::new("All groups")];
+        items.extend(self.group_names.iter().map(|name| {
+            let item = if self.default_group.as_ref() == Some(name) { format!("{} (default)", name) } else { name.clone() };
+            Box::new(item) as Box<dyn Display>
+        }));
+        self.dynamic_list.set_items(items);
+        let current = self.group_filter.as_ref()
+            .and_then(|group| self.group_names.iter().position(|name| name == group))
+            .map_or(0, |i| i + 1);
+        self.dynamic_list.state.select(Some(current));
+        self.stage = Stage::FilterGroup;
+    }
+
+    fn set_group_filter(&mut self) {
+        let Some(index) = self.dynamic_list.state.selected().map(|i| i.min(self.group_names.len())) else {
+            return;
+        };
+        self.group_filter = index.checked_sub(1).and_then(|i| self.group_names.get(i)).cloned();
+        self.stage = Stage::SelectRunner;
+        self.apply_filters();
+    }
+
+    fn apply_filters(&mut self) {
+        let (non_compliant_only, ephemeral_only, busy_only) = (self.non_compliant_only, self.ephemeral_only, self.busy_only);
+        let label_filter = self.label_filter.clone();
+        let group_filter = self.group_filter.clone();
+        let default_group = self.default_group.clone();
+        let predicate: Option<Predicate<Runner>> = if non_compliant_only || ephemeral_only || busy_only || !label_filter.is_empty() || group_filter.is_some() {
+            Some(Box::new(move |r: &Runner| {
+                (!non_compliant_only || !r.missing_labels.is_empty())
+                    && (!ephemeral_only || r.ephemeral_state().is_some())
+                    && (!busy_only || r.status == RunnerStatus::Busy)
+                    && label_filter.iter().all(|wanted| r.labels.iter().chain(&r.default_labels).any(|l| l.eq_ignore_ascii_case(wanted)))
+                    && group_filter.as_ref().is_none_or(|group| match &r.group {
+                        Some(name) => name == group,
+                        None => default_group.as_ref() == Some(group),
+                    })
+            }))
+        } else {
+            None
+        };
+        self.runners.set_predicate(predicate);
+    }
+
+    fn ephemeral_summary(&self) -> String {
+        let count = |state: EphemeralState| self.runners.items.iter()
+            .filter(|r| r.ephemeral_state() == Some(state))
+            .count();
+        format!(" [ephemeral: {} pending, {} idle, {} running]",
+                count(EphemeralState::Pending), count(EphemeralState::Idle), count(EphemeralState::Running))
+    }
+
+    pub fn record_label_change(&mut self, runner_id: usize, label: String, added: bool) {
+        // Only a single label change can be undone, not one of many in a bulk operation.
+        self.last_label_change = (!std::mem::take(&mut self.undoing) && !self.bulk_pending)
+            .then(|| (runner_id, label.clone(), added));
+        let mut state = self.state.borrow_mut();
+        state.record_label_change(runner_id, label, added);
+        if let Err(e) = state.save() {
+            debug!("Could not save label history: {}", e);
+        }
+    }
+
+    /// Sends the inverse of the last label change. The runner is found by id, the list may have
+    /// been refreshed and reordered since.
+    fn undo_label_change(&mut self) {
+        let Some((runner_id, label, added)) = self.last_label_change.take() else {
+            self.popup_content = Some(PopupInfo::new(String::from("Undo"), String::from("There is no label change to undo")));
+            return;
+        };
+        self.undoing = true;
+        self.popup_content = Some(PopupInfo::loading());
+        let message = if added {
+            BackendMessage::DeleteLabel(runner_id, label)
+        } else {
+            BackendMessage::AddLabel(runner_id, label)
+        };
+        self.tx.send(message)
+            .expect("Could not send undo command to backend");
+    }
+
+    /// Asks the backend for the current state of the selected runner, the list entry may be stale.
+    fn show_details(&mut self) {
+        let Some(runner_id) = self.selected().map(|r| r.id) else {
+            return;
+        };
+        self.popup_content = Some(PopupInfo::loading());
+        self.tx.send(BackendMessage::GetRunner(runner_id))
+            .expect("Could not send get runner command to backend");
+    }
+
+    /// Updates the list entry with the fetched runner and shows its details,
+    /// or drops it from the list when it was deleted since the list was loaded.
+    pub fn set_runner_details(&mut self, runner_id: usize, runner: Option<Runner>) {
+        let Some(position) = self.runners.items.iter().position(|r| r.id == runner_id) else {
+            self.toggle_loading();
+            return;
+        };
+        let Some(mut runner) = runner else {
+            let gone = self.runners.items.remove(position);
+            self.summary = FleetSummary::from_runners(self.runners.items.iter().map(|r| r.as_ref()));
+            self.runners.filter_items();
+            self.runners.clamp_selection();
+            self.runners.retain_marks();
+            // Whatever was open was about that runner.
+            self.stage = Stage::SelectRunner;
+            self.popup_content = Some(PopupInfo::new(String::from("Runner not found"),
+                                                     format!("Runner {} no longer exists, it was removed from the list", gone.name)));
+            return;
+        };
+        let now = now_secs();
+        let mut state = self.state.borrow_mut();
+        if runner.status != RunnerStatus::Offline {
+            state.record_seen_online(runner.id, now);
+        }
+        runner.last_active = state.last_seen_online(runner.id);
+        drop(state);
+        // The single runner endpoint doesn't say which group it belongs to.
+        runner.group = self.runners.items[position].group.clone();
+        runner.missing_labels = self.required_labels.missing_for(&runner);
+        runner.drained = runner.labels.contains(&self.drain_label);
+        let runner = Rc::new(runner);
+        self.runners.items[position] = Rc::clone(&runner);
+        self.summary = FleetSummary::from_runners(self.runners.items.iter().map(|r| r.as_ref()));
+        self.runners.filter_items();
+        self.show_runner_details(&runner);
+    }
+
+    fn show_runner_details(&mut self, runner: &Runner) {
+        let group = runner.group.as_deref().unwrap_or("default");
+        let labels = match runner.sorted_labels() {
+            labels if labels.is_empty() => String::from("none"),
+            labels => labels.join(", "),
+        };
+        let mut details = format!("ID: {}\nName: {}\nStatus: {}\nBusy: {}\nGroup: {}\nOS: {}\nLabels: {}\nDefault labels: {}",
+                                  runner.id, runner.name, runner.status, runner.busy, group, runner.os, labels,
+                                  runner.default_labels.join(", "));
+        match runner.ephemeral_state() {
+            Some(state) => details.push_str(&format!("\nEphemeral: yes, {}", state)),
+            None => details.push_str("\nEphemeral: no"),
+        }
+        let now = now_secs();
+        match runner.last_active {
+            Some(timestamp) => details.push_str(&format!("\nLast seen online: {}", describe_age(timestamp, now))),
+            None => details.push_str("\nLast seen online: never"),
+        }
+        let state = self.state.borrow();
+        let history = state.label_history(runner.id);
+        if !history.is_empty() {
+            details.push_str("\nLabel history:");
+            for change in history.iter().rev() {
+                details.push_str(&format!("\n- {}", change.describe(now)));
+            }
+        }
+        let title = format!("Runner {}", runner.name);
+        drop(state);
+        self.popup_content = Some(PopupInfo::new(title, details));
+    }
+
+    fn confirm_delete(&mut self) {
+        let runner = self.selected().unwrap();
+        let prompt = format!("Deregister {} [{}] from the organization?", runner.name, runner.status);
+        self.confirm = Some(ConfirmPopup::new("Delete runner", prompt, BackendMessage::DeleteRunner(runner.id)));
+    }
+
+    /// Whether the runner list itself has the focus, with no operation or popup open.
+    pub fn is_browsing(&self) -> bool {
+        matches!(self.stage, Stage::SelectRunner) && self.popup_content.is_none() && self.confirm.is_none()
+    }
+
+    pub fn selected(&self) -> Option<&Runner> {
+        self.runners.selected()
     }
 
     pub fn render(&mut self, area: Rect, buf: &mut Buffer) {
         match self.stage {
             Stage::SelectRunner => {
-                let mut list_title = String::from("Runners - ");
+                let mut list_title = String::from("Runners");
+                if !self.required_labels.is_empty() {
+                    let non_compliant = self.runners.items.iter().filter(|r| !r.missing_labels.is_empty()).count();
+                    list_title.push_str(&format!(" ({} non-compliant)", non_compliant));
+                }
+                if self.non_compliant_only {
+                    list_title.push_str(" [missing required labels]");
+                }
+                if self.ephemeral_only {
+                    list_title.push_str(&self.ephemeral_summary());
+                }
+                if self.busy_only {
+                    list_title.push_str(" [busy only]");
+                }
+                if !self.label_filter.is_empty() {
+                    list_title.push_str(&format!(" [labels: {}]", self.label_filter.join(", ")));
+                }
+                if let Some(group) = &self.group_filter {
+                    list_title.push_str(&format!(" [group: {}]", group));
+                }
+                if self.stale_first {
+                    list_title.push_str(" [stale first]");
+                }
+                if self.runners.is_fuzzy() {
+                    list_title.push_str(" [fuzzy]");
+                }
+                if !self.runners.marked().is_empty() {
+                    list_title.push_str(&format!(" [{} selected]", self.runners.marked().len()));
+                }
+                if let Some((_, label, added)) = &self.last_label_change {
+                    list_title.push_str(&format!(" [^Z: undo {}{}]", if *added { "+" } else { "-" }, label));
+                }
+                list_title.push_str(&format!(" [^N/^P: {}]", self.jump_status));
+                list_title.push_str(" - ");
                 list_title.push_str(self.runners.input_buffer.as_str());
                 self.runners.render(area, buf, &list_title);
             }
@@ -77,23 +763,195 @@ impl <'a> RunnersTab<'a> {
                 let list_title = format!("Select operation - {}", runner.name);
                 self.operations.render(area, buf, &list_title);
             },
+            Stage::AddLabel => self.render_add_label(area, buf),
             Stage::RemoveLabels => {
                 let runner = self.selected().unwrap();
                 let list_title = format!("Remove labels - {}", runner.name);
                 self.dynamic_list.render(area, buf, &list_title);
             }
+            Stage::ChangeGroup => {
+                let runner = self.selected().unwrap();
+                let list_title = format!("Change group - {}", runner.name);
+                self.dynamic_list.render(area, buf, &list_title);
+            }
+            Stage::ManageLabels => self.render_manage_labels(area, buf),
+            Stage::FilterGroup => self.dynamic_list.render(area, buf, "Show the runners of group - Enter pick, Left cancel"),
+            Stage::SelectDownload => self.dynamic_list.render(area, buf, "Runner downloads - Enter copy the URL, Left cancel"),
+            Stage::JitGroup => {
+                let name = self.jit_draft.as_ref().map(|d| d.name.as_str()).unwrap_or_default();
+                let list_title = format!("Group of the new runner {} - Enter pick, Left cancel", name);
+                self.dynamic_list.render(area, buf, &list_title);
+            }
         }
         show_popup(&self.popup_content, area, buf);
+        if let Some(confirm) = &self.confirm {
+            confirm.render(area, buf);
+        }
     }
 
     fn add_label(&mut self) {
         self.popup_content = Some(PopupInfo::loading());
-        let input = std::mem::replace(&mut *self.input_buffer.borrow_mut(), String::new());
-        let runner = self.selected().unwrap();
-        self.tx.send(BackendMessage::AddLabel(runner.id, input))
+        let input = self.input_buffer.borrow_mut().take();
+        let marked = self.runners.marked();
+        let message = if marked.is_empty() {
+            BackendMessage::AddLabel(self.selected().unwrap().id, input)
+        } else {
+            self.bulk_pending = true;
+            let mut ids: Vec<usize> = marked.iter().copied().collect();
+            ids.sort_unstable();
+            BackendMessage::AddLabelBulk(ids, input)
+        };
+        self.tx.send(message)
             .expect("Could not send add label command to backend");
     }
 
+    /// Asks for the label in a popup, or next to the presets when some are configured.
+    fn start_add_label(&mut self) {
+        if self.label_presets.is_empty() {
+            let input_clone = Rc::clone(&self.input_buffer);
+            self.popup_content = Some(
+                PopupInfo::new_dynamic(String::from("Input new label:"),
+                                       Box::new(move || input_clone.borrow().render())
+                ));
+            return;
+        }
+        let items = self.label_presets.iter()
+            .map(|label| Box::new(label.clone()) as Box<dyn Display>)
+            .collect();
+        self.dynamic_list.set_items(items);
+        self.input_buffer.borrow_mut().take();
+        self.stage = Stage::AddLabel;
+    }
+
+    /// Moves through the presets, filling the input with the one picked.
+    fn pick_label_preset(&mut self, next: bool) {
+        if next {
+            self.dynamic_list.select_next();
+        } else {
+            self.dynamic_list.select_previous();
+        }
+        if let Some(preset) = self.dynamic_list.selected() {
+            self.input_buffer.borrow_mut().set_text(preset.to_string());
+        }
+    }
+
+    fn render_add_label(&mut self, area: Rect, buf: &mut Buffer) {
+        let [list_area, input_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+        let target = match self.runners.marked().len() {
+            0 => self.selected().map(|r| r.name.clone()).unwrap_or_default(),
+            marked => format!("{} runners", marked),
+        };
+        let list_title = format!("Label presets - {} - Up/Down pick", target);
+        self.dynamic_list.render(list_area, buf, &list_title);
+        Paragraph::new(self.input_buffer.borrow().render())
+            .block(Block::new().borders(Borders::ALL).border_style(TODO_HEADER_STYLE).title("New label - Enter to add, Left on an empty label to cancel"))
+            .render(input_area, buf);
+    }
+
+    fn edit_labels(&mut self) {
+        let runner = self.selected().unwrap();
+        self.input_buffer.borrow_mut().set_text(runner.sorted_labels().join(", "));
+        let input_clone = Rc::clone(&self.input_buffer);
+        self.popup_content = Some(
+            PopupInfo::new_dynamic(String::from("Labels (comma separated):"),
+                                   Box::new(move || input_clone.borrow().render())
+            ));
+    }
+
+    fn set_labels(&mut self) {
+        self.last_label_change = None;
+        self.popup_content = Some(PopupInfo::loading());
+        let input = self.input_buffer.borrow_mut().take();
+        let runner = self.selected().unwrap();
+        self.tx.send(BackendMessage::SetLabels(runner.id, parse_list(&input)))
+            .expect("Could not send set labels command to backend");
+    }
+
+    fn start_manage_labels(&mut self) {
+        let runner = self.selected().unwrap();
+        self.label_draft = runner.sorted_labels().into_iter().map(str::to_string).collect();
+        self.label_input_focused = self.label_draft.is_empty();
+        self.label_error = None;
+        self.input_buffer.borrow_mut().take();
+        self.show_label_draft();
+        self.dynamic_list.select_first();
+        self.stage = Stage::ManageLabels;
+    }
+
+    fn show_label_draft(&mut self) {
+        let items = self.label_draft.iter()
+            .map(|label| Box::new(label.clone()) as Box<dyn Display>)
+            .collect();
+        self.dynamic_list.set_items(items);
+    }
+
+    fn add_draft_label(&mut self) {
+        let label = self.input_buffer.borrow().text().trim().to_string();
+        if label.is_empty() {
+            return;
+        }
+        if self.label_draft.iter().any(|l| l.eq_ignore_ascii_case(&label)) {
+            self.label_error = Some(format!("The runner already has label {}", label));
+            return;
+        }
+        self.input_buffer.borrow_mut().take();
+        self.label_error = None;
+        self.label_draft.push(label);
+        self.show_label_draft();
+        self.dynamic_list.select_last();
+    }
+
+    fn remove_draft_label(&mut self) {
+        let Some(index) = self.dynamic_list.state.selected().filter(|&i| i < self.label_draft.len()) else {
+            return;
+        };
+        self.label_draft.remove(index);
+        self.show_label_draft();
+        if !self.label_draft.is_empty() {
+            self.dynamic_list.state.select(Some(index.min(self.label_draft.len() - 1)));
+        }
+    }
+
+    /// Asks before replacing the custom labels with the draft, if anything changed.
+    fn confirm_label_draft(&mut self) {
+        let runner = self.selected().unwrap();
+        let current = runner.sorted_labels();
+        let added: Vec<&str> = self.label_draft.iter().map(String::as_str).filter(|l| !current.contains(l)).collect();
+        let removed: Vec<&str> = current.iter().copied().filter(|l| !self.label_draft.iter().any(|d| d == l)).collect();
+        if added.is_empty() && removed.is_empty() {
+            self.popup_content = Some(PopupInfo::new(String::from("Manage labels"), String::from("The labels weren't changed")));
+            return;
+        }
+        let mut changes = vec![];
+        if !added.is_empty() {
+            changes.push(format!("add {}", added.join(", ")));
+        }
+        if !removed.is_empty() {
+            changes.push(format!("remove {}", removed.join(", ")));
+        }
+        let prompt = format!("Update the labels of {}: {}?", runner.name, changes.join(" and "));
+        let action = BackendMessage::SetLabels(runner.id, self.label_draft.clone());
+        self.confirm = Some(ConfirmPopup::new("Manage labels", prompt, action));
+        self.stage = Stage::SelectOp;
+    }
+
+    fn render_manage_labels(&mut self, area: Rect, buf: &mut Buffer) {
+        let [list_area, input_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+        let runner_name = self.selected().map(|r| r.name.clone()).unwrap_or_default();
+        let list_title = format!("Manage labels - {} - Enter/d remove, Tab add, ^S apply, Left cancel", runner_name);
+        self.dynamic_list.render(list_area, buf, &list_title);
+        let input = self.input_buffer.borrow();
+        let text = if self.label_input_focused { input.render() } else { input.text().to_string() };
+        let title = match &self.label_error {
+            Some(error) => format!("New label - {}", error),
+            None => String::from("New label - Enter to add, Tab back to the list"),
+        };
+        let style = if self.label_input_focused { TODO_HEADER_STYLE } else { Style::default() };
+        Paragraph::new(text)
+            .block(Block::new().borders(Borders::ALL).border_style(style).title(title))
+            .render(input_area, buf);
+    }
+
     fn remove_label(&mut self) {
         self.popup_content = Some(PopupInfo::loading());
         let runner = self.selected().unwrap();
@@ -103,18 +961,115 @@ impl <'a> RunnersTab<'a> {
             .expect("Could not send delete label command to backend");
     }
 
+    fn list_groups(&mut self) {
+        let runner = self.selected().unwrap();
+        let current = runner.group.clone();
+        let items: Vec<Box<dyn Display>> = self.group_names.iter()
+            .filter(|name| current.as_ref() != Some(*name))
+            .map(|name| Box::new(name.clone()) as Box<dyn Display>)
+            .collect();
+        if items.is_empty() {
+            self.popup_content = Some(PopupInfo::new(String::from("Change group"), String::from("There is no other runner group to move to")));
+            return;
+        }
+        self.dynamic_list.set_items(items);
+        self.dynamic_list.select_first();
+        self.stage = Stage::ChangeGroup;
+    }
+
     fn add_to_group(&mut self) {
+        let Some(group_name) = self.dynamic_list.selected().map(|g| g.to_string()) else {
+            return;
+        };
+        let runner_id = self.selected().unwrap().id;
+        self.last_label_change = None;
         self.popup_content = Some(PopupInfo::loading());
-        let input = std::mem::replace(&mut *self.input_buffer.borrow_mut(), String::new());
-        let runner = self.selected().unwrap();
-        self.tx.send(BackendMessage::ChangeGroup(runner.id, input))
+        self.tx.send(BackendMessage::ChangeGroup(runner_id, group_name))
             .expect("Could not send change group command to backend");
     }
 
+    fn remove_from_group(&mut self) {
+        let runner = self.selected().unwrap();
+        if runner.group.is_some() && runner.group == self.default_group {
+            let message = format!("{} is already in the default group", runner.name);
+            self.popup_content = Some(PopupInfo::new(String::from("Move to default group"), message));
+            return;
+        }
+        let runner_id = runner.id;
+        self.last_label_change = None;
+        self.popup_content = Some(PopupInfo::loading());
+        self.tx.send(BackendMessage::RemoveFromGroup(runner_id))
+            .expect("Could not send remove from group command to backend");
+    }
+
     pub fn handle_input(&mut self, event: KeyEvent) -> bool {
+        if let Some(confirm) = &self.confirm {
+            match confirm.answer(event.code) {
+                Some(true) => {
+                    let action = self.confirm.take().unwrap().into_action();
+                    self.last_label_change = None;
+                    self.popup_content = Some(PopupInfo::loading());
+                    self.tx.send(action)
+                        .expect("Could not send confirmed command to backend");
+                }
+                Some(false) => self.confirm = None,
+                None => {}
+            }
+            return false;
+        }
+        if self.editing_label_filter {
+            match event.code {
+                KeyCode::Enter => {
+                    let labels = parse_list(self.input_buffer.borrow().text());
+                    self.set_label_filter(labels);
+                }
+                KeyCode::Esc => self.set_label_filter(vec![]),
+                code => {
+                    self.input_buffer.borrow_mut().handle_key(code);
+                }
+            }
+            return false;
+        }
+        if self.jit_draft.is_some() && !matches!(self.stage, Stage::JitGroup) {
+            match event.code {
+                KeyCode::Enter => self.advance_jit_draft(),
+                KeyCode::Esc => self.cancel_jit_draft(),
+                code => {
+                    self.input_buffer.borrow_mut().handle_key(code);
+                }
+            }
+            return false;
+        }
+        // The label filter is cleared before Esc leaves the tab.
+        if event.code == KeyCode::Esc && self.popup_content.is_none() && matches!(self.stage, Stage::SelectRunner) && !self.label_filter.is_empty() {
+            self.set_label_filter(vec![]);
+            return false;
+        }
         if event.code == KeyCode::Esc && self.popup_content.is_none() {
             return true;
         }
+        // Quitting while the backend works is up to the app, which asks first.
+        if event.code == KeyCode::Esc && self.is_busy() {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable && p.scroll(event.code)) {
+            return false;
+        }
+        if self.popup_content.as_ref().is_some_and(|p| p.dismissable) {
+            self.popup_content = None;
+            if let Some((secret, what)) = self.copyable.take() && event.code == KeyCode::Char('c') {
+                self.popup_content = Some(clipboard::copy_with_popup(&secret, what));
+            }
+            return false;
+        }
+        // Lists that don't filter jump to the item starting with a typed letter, unless h/j/k/l
+        // are set to move instead.
+        let event = match self.stage {
+            _ if !self.vim_keys => event,
+            Stage::SelectOp if self.popup_content.is_none() => vim_navigation(event),
+            Stage::RemoveLabels | Stage::ChangeGroup | Stage::FilterGroup | Stage::SelectDownload | Stage::JitGroup => vim_navigation(event),
+            _ => event,
+        };
         match self.stage {
             Stage::SelectRunner => {
                 match event.code {
@@ -123,74 +1078,894 @@ impl <'a> RunnersTab<'a> {
                     KeyCode::Up => self.runners.select_previous(),
                     KeyCode::Home => self.runners.select_first(),
                     KeyCode::End => self.runners.select_last(),
+                    KeyCode::PageDown => self.runners.select_page(true, false),
+                    KeyCode::PageUp => self.runners.select_page(false, false),
+                    KeyCode::Char('d') if event.modifiers.contains(KeyModifiers::CONTROL) => self.runners.select_page(true, true),
+                    KeyCode::Char('u') if event.modifiers.contains(KeyModifiers::CONTROL) => self.runners.select_page(false, true),
                     KeyCode::Right | KeyCode::Enter => {
                         self.stage = Stage::SelectOp;
                     },
+                    KeyCode::Char('a') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.popup_content = Some(PopupInfo::loading());
+                        self.tx.send(BackendMessage::CreateRegistrationToken)
+                            .expect("Could not send registration token command to backend");
+                    }
+                    KeyCode::Char('j') if event.modifiers.contains(KeyModifiers::CONTROL) => self.start_jit_config(),
+                    KeyCode::Char('q') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.popup_content = Some(PopupInfo::loading());
+                        self.tx.send(BackendMessage::FetchRunnerDownloads)
+                            .expect("Could not send runner downloads command to backend");
+                    }
+                    KeyCode::Char('y') if event.modifiers.contains(KeyModifiers::CONTROL) => self.copy_selected(),
+                    KeyCode::Char('e') if event.modifiers.contains(KeyModifiers::CONTROL) => self.export_json(),
+                    KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.popup_content = Some(PopupInfo::loading());
+                        self.tx.send(BackendMessage::FetchRunners { skip_cache: true })
+                            .expect("Could not send refresh command to backend");
+                    }
+                    KeyCode::Char('w') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.popup_content = Some(PopupInfo::loading());
+                        self.tx.send(BackendMessage::ExportMarkdown)
+                            .expect("Could not send export command to backend");
+                    }
+                    KeyCode::Char('k') if event.modifiers.contains(KeyModifiers::CONTROL) => self.toggle_non_compliant(),
+                    KeyCode::Char('x') if event.modifiers.contains(KeyModifiers::CONTROL) => self.toggle_drain(),
+                    KeyCode::Char('f') if event.modifiers.contains(KeyModifiers::CONTROL) => self.toggle_ephemeral(),
+                    KeyCode::Char('b') if event.modifiers.contains(KeyModifiers::CONTROL) => self.toggle_busy(),
+                    KeyCode::Char('l') if event.modifiers.contains(KeyModifiers::CONTROL) => self.edit_label_filter(),
+                    KeyCode::Char('v') if event.modifiers.contains(KeyModifiers::CONTROL) => self.pick_group_filter(),
+                    KeyCode::Char('n') if event.modifiers.contains(KeyModifiers::CONTROL) => self.jump_to_status(true),
+                    KeyCode::Char('p') if event.modifiers.contains(KeyModifiers::CONTROL) => self.jump_to_status(false),
+                    KeyCode::Char('t') if event.modifiers.contains(KeyModifiers::CONTROL) => self.jump_status = self.jump_status.next(),
+                    KeyCode::Char('s') if event.modifiers.contains(KeyModifiers::CONTROL) => self.toggle_stale_first(),
+                    KeyCode::Char('z') if event.modifiers.contains(KeyModifiers::CONTROL) => self.undo_label_change(),
                     KeyCode::Backspace => self.runners.remove_last_input(),
+                    KeyCode::Char(' ') => self.runners.toggle_mark(),
+                    KeyCode::Char('o') if event.modifiers.contains(KeyModifiers::CONTROL) => self.runners.toggle_fuzzy(),
                     KeyCode::Char(c) => self.runners.update_filter(c),
                     _ => {}
                 }
             }
             Stage::SelectOp => {
+                // An open input popup gets the keys that edit it, Enter still submits.
+                if self.popup_content.as_ref().is_some_and(|p| !p.is_loading) && self.input_buffer.borrow_mut().handle_key(event.code) {
+                    return false;
+                }
                 match event.code {
                     KeyCode::Up => self.operations.select_previous(),
                     KeyCode::Down => self.operations.select_next(),
                     KeyCode::Left => self.stage = Stage::SelectRunner,
-                    KeyCode::Char(c) => match self.popup_content {
-                        Some(_) => self.add_to_input(c),
-                        _ => {}
-                    }
-                    KeyCode::Backspace => self.remove_last_input(),
                     KeyCode::Right | KeyCode::Enter => match self.operations.selected() {
-                        Some(RunnerOperation::AddLabel) => {
-                            match self.popup_content {
-                                Some(_) => self.add_label(),
-                                None => {
-                                    let input_clone = Rc::clone(&self.input_buffer);
-                                    self.popup_content = Some(
-                                        PopupInfo::new_dynamic(String::from("Input new label:"),
-                                                               Box::new(move || format!("{}_", input_clone.borrow()))
-                                        ))
-                                }
-                            }
+                        Some(RunnerOperation::AddLabel) => match self.popup_content {
+                            Some(_) => self.add_label(),
+                            None => self.start_add_label(),
+                        },
+                        Some(RunnerOperation::SetLabels) => match self.popup_content {
+                            Some(_) => self.set_labels(),
+                            None => self.edit_labels(),
                         },
                         Some(RunnerOperation::RemoveLabel) => {
                             let runner = self.selected().unwrap();
-                            let label_items = runner.labels
-                                .iter()
-                                .cloned()
-                                .map(|label| Box::new(label) as Box<dyn Display>)
+                            let label_items = runner.sorted_labels()
+                                .into_iter()
+                                .map(|label| Box::new(label.to_string()) as Box<dyn Display>)
                                 .collect();
                             self.dynamic_list.set_items(label_items);
                             self.stage = Stage::RemoveLabels
                         },
-                        Some(RunnerOperation::ChangeGroup) => {
-                            match self.popup_content {
-                                Some(_) => self.add_to_group(),
-                                None => {
-                                    let input_clone = Rc::clone(&self.input_buffer);
-                                    self.popup_content = Some(
-                                        PopupInfo::new_dynamic(String::from("Input group name:"),
-                                                               Box::new(move || format!("{}_", input_clone.borrow()))
-                                        ))
-                                }
-                            }
-                        }
+                        Some(RunnerOperation::ManageLabels) => self.start_manage_labels(),
+                        Some(RunnerOperation::Details) => self.show_details(),
+                        Some(RunnerOperation::Delete) => self.confirm_delete(),
+                        Some(RunnerOperation::ChangeGroup) => self.list_groups(),
+                        Some(RunnerOperation::RemoveFromGroup) => self.remove_from_group(),
                         _ => {}
                     },
+                    KeyCode::Char(c) => self.operations.select_by_letter(c),
                     _ => {}
                 }
             }
+            Stage::AddLabel => {
+                match event.code {
+                    KeyCode::Up => self.pick_label_preset(false),
+                    KeyCode::Down => self.pick_label_preset(true),
+                    KeyCode::Enter if !self.input_buffer.borrow().text().trim().is_empty() => {
+                        self.stage = Stage::SelectOp;
+                        self.add_label();
+                    }
+                    KeyCode::Left if self.input_buffer.borrow().text().is_empty() => self.stage = Stage::SelectOp,
+                    code => {
+                        self.input_buffer.borrow_mut().handle_key(code);
+                    }
+                }
+            }
             Stage::RemoveLabels => {
                 match event.code {
                     KeyCode::Up => self.dynamic_list.select_previous(),
                     KeyCode::Down => self.dynamic_list.select_next(),
                     KeyCode::Left => self.stage = Stage::SelectOp,
                     KeyCode::Enter => self.remove_label(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::ChangeGroup => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectOp,
+                    KeyCode::Enter => self.add_to_group(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::FilterGroup => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.stage = Stage::SelectRunner,
+                    KeyCode::Enter => self.set_group_filter(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::JitGroup => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::Left => self.cancel_jit_draft(),
+                    KeyCode::Enter => self.pick_jit_group(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::SelectDownload => {
+                match event.code {
+                    KeyCode::Up => self.dynamic_list.select_previous(),
+                    KeyCode::Down => self.dynamic_list.select_next(),
+                    KeyCode::PageUp => self.dynamic_list.select_page(false, false),
+                    KeyCode::PageDown => self.dynamic_list.select_page(true, false),
+                    KeyCode::Left => self.stage = Stage::SelectRunner,
+                    KeyCode::Enter => self.copy_download_url(),
+                    KeyCode::Char(c) => self.dynamic_list.select_by_letter(c),
+                    _ => {}
+                }
+            }
+            Stage::ManageLabels => {
+                match event.code {
+                    KeyCode::Char('s') if event.modifiers.contains(KeyModifiers::CONTROL) => self.confirm_label_draft(),
+                    KeyCode::Tab | KeyCode::BackTab => self.label_input_focused = !self.label_input_focused,
+                    KeyCode::Enter if self.label_input_focused => self.add_draft_label(),
+                    code if self.label_input_focused && self.input_buffer.borrow_mut().handle_key(code) => self.label_error = None,
+                    KeyCode::Up | KeyCode::Char('k') => self.dynamic_list.select_previous(),
+                    KeyCode::Down | KeyCode::Char('j') => self.dynamic_list.select_next(),
+                    KeyCode::Left | KeyCode::Char('h') => self.stage = Stage::SelectOp,
+                    KeyCode::Enter | KeyCode::Delete | KeyCode::Char('d') => self.remove_draft_label(),
                     _ => {}
                 }
             }
         }
         false
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::{runner_group_json, ApiRunner};
+    use crossterm::event::KeyModifiers;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    fn runner(id: usize, name: &str) -> Runner {
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "os": "linux",
+            "status": "online",
+            "busy": false,
+            "ephemeral": false,
+            "labels": [{ "id": 1, "name": "self-hosted", "type": "read-only" }],
+        })).unwrap();
+        Runner::from(api_runner)
+    }
+
+    fn press(tab: &mut RunnersTab, code: KeyCode) -> bool {
+        tab.handle_input(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(tab: &mut RunnersTab, text: &str) {
+        text.chars().for_each(|c| { press(tab, KeyCode::Char(c)); });
+    }
+
+    fn setup(tx: &mpsc::UnboundedSender<BackendMessage>) -> RunnersTab<'_> {
+        let mut tab = RunnersTab::new(tx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        tab.set_runners(vec![runner(1, "web-01"), runner(2, "db-01")]);
+        tab
+    }
+
+    fn channel() -> (mpsc::UnboundedSender<BackendMessage>, UnboundedReceiver<BackendMessage>) {
+        mpsc::unbounded_channel()
+    }
+
+    #[test]
+    fn runners_that_went_online_or_offline_are_marked_for_a_while() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let with_status = |id, name, status| Runner { status, ..runner(id, name) };
+        let trend = |tab: &RunnersTab, id| tab.runners.items.iter().find(|r| r.id == id).unwrap().previous_status;
+
+        tab.refresh_runners(vec![
+            with_status(1, "web-01", RunnerStatus::Offline),
+            with_status(2, "db-01", RunnerStatus::Busy),
+            with_status(3, "new-01", RunnerStatus::Offline),
+        ]);
+        assert_eq!(trend(&tab, 1), Some(RunnerStatus::Online));
+        assert!(tab.runners.items[0].to_string().starts_with("↓ web-01"));
+        // Picking up a job isn't a change worth pointing out, nor is appearing.
+        assert_eq!(trend(&tab, 2), None);
+        assert_eq!(trend(&tab, 3), None);
+
+        tab.refresh_runners(vec![with_status(1, "web-01", RunnerStatus::Offline), with_status(3, "new-01", RunnerStatus::Online)]);
+        assert_eq!(trend(&tab, 1), Some(RunnerStatus::Online));
+        assert!(tab.runners.items[1].to_string().starts_with("↑ new-01"));
+
+        tab.expire_status_changes(Instant::now() + STATUS_CHANGE_DURATION);
+        assert_eq!(trend(&tab, 1), None);
+        assert_eq!(trend(&tab, 3), None);
+        assert!(tab.status_changes.is_empty());
+        assert!(!tab.runners.filtered_items().iter().any(|r| r.previous_status.is_some()));
+    }
+
+    #[test]
+    fn label_filter_lists_runners_carrying_every_label() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let labeled = |id, name, labels: &[&str]| Runner { labels: labels.iter().map(|l| l.to_string()).collect(), ..runner(id, name) };
+        tab.set_runners(vec![
+            labeled(1, "gpu-small", &["gpu"]),
+            labeled(2, "gpu-large", &["gpu", "large"]),
+            labeled(3, "gpus-large", &["gpus", "large"]),
+        ]);
+        let listed = |tab: &RunnersTab| tab.runners.filtered_items().iter().map(|r| r.id).collect::<Vec<_>>();
+
+        tab.handle_input(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
+        type_text(&mut tab, "GPU, large");
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(listed(&tab), vec![2]);
+        assert_eq!(tab.label_filter, vec!["GPU", "large"]);
+        assert!(tab.popup_content.is_none());
+
+        // Default labels count too, and the text filter still applies on top.
+        tab.handle_input(KeyEvent::new(KeyCode::Char('l'), KeyModifiers::CONTROL));
+        assert_eq!(tab.input_buffer.borrow().text(), "GPU, large");
+        tab.input_buffer.borrow_mut().set_text("self-hosted");
+        press(&mut tab, KeyCode::Enter);
+        type_text(&mut tab, "gpu-");
+        assert_eq!(listed(&tab), vec![1, 2]);
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(tab.label_filter.is_empty());
+        assert!(press(&mut tab, KeyCode::Esc));
+    }
+
+    #[test]
+    fn runners_arrive_and_clear_the_initial_loading_popup() {
+        let (tx, _rx) = channel();
+        let mut tab = RunnersTab::new(&tx, &Config::default(), Rc::new(RefCell::new(State::default())));
+        tab.set_loading();
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+
+        tab.set_runners(vec![runner(1, "web-01")]);
+
+        assert!(tab.popup_content.is_none());
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert_eq!(tab.runners.filtered_items().len(), 1);
+    }
+
+    #[test]
+    fn add_label_opens_input_and_submits_to_backend() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::SelectOp));
+        assert!(tab.popup_content.is_none());
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.popup_content.as_ref().is_some_and(|p| !p.is_loading));
+
+        type_text(&mut tab, "gpux");
+        press(&mut tab, KeyCode::Backspace);
+        assert_eq!(tab.input_buffer.borrow().text(), "gpu");
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        assert!(tab.input_buffer.borrow().text().is_empty());
+        match rx.try_recv() {
+            Ok(BackendMessage::AddLabel(id, label)) => {
+                assert_eq!(id, 1);
+                assert_eq!(label, "gpu");
+            }
+            _ => panic!("expected an AddLabel message"),
+        }
+    }
+
+    #[test]
+    fn label_presets_fill_the_input_and_can_still_be_edited() {
+        let (tx, mut rx) = channel();
+        let config = Config { label_presets: vec![String::from("gpu"), String::from("large")], ..Config::default() };
+        let mut tab = RunnersTab::new(&tx, &config, Rc::new(RefCell::new(State::default())));
+        tab.set_runners(vec![runner(1, "web-01")]);
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::AddLabel));
+
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Down);
+        assert_eq!(tab.input_buffer.borrow().text(), "large");
+        type_text(&mut tab, "-x");
+
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::SelectOp));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        match rx.try_recv() {
+            Ok(BackendMessage::AddLabel(1, label)) => assert_eq!(label, "large-x"),
+            _ => panic!("expected an AddLabel message"),
+        }
+    }
+
+    #[test]
+    fn undo_reverts_the_last_label_change_by_runner_id() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        tab.record_label_change(2, String::from("gpu"), true);
+        // The list was refreshed and reordered meanwhile.
+        tab.set_runners(vec![runner(3, "new-01"), runner(2, "db-01"), runner(1, "web-01")]);
+
+        tab.handle_input(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        match rx.try_recv() {
+            Ok(BackendMessage::DeleteLabel(2, label)) => assert_eq!(label, "gpu"),
+            _ => panic!("expected a DeleteLabel message"),
+        }
+        // The revert itself can't be undone.
+        tab.record_label_change(2, String::from("gpu"), false);
+        assert!(tab.last_label_change.is_none());
+
+        tab.set_runners(vec![runner(1, "web-01")]);
+        tab.handle_input(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert!(rx.try_recv().is_err());
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.dismissable));
+    }
+
+    #[test]
+    fn esc_without_popup_exits_and_left_goes_back() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Left);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+
+        assert!(press(&mut tab, KeyCode::Esc));
+    }
+
+    #[test]
+    fn esc_while_loading_does_not_exit() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        tab.set_loading();
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(tab.is_busy());
+    }
+
+    #[test]
+    fn esc_with_input_popup_does_not_exit() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Enter);
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn typing_filters_the_runner_list() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+
+        type_text(&mut tab, "db");
+        assert_eq!(tab.runners.filtered_items().len(), 1);
+        assert_eq!(tab.selected().unwrap().name, "db-01");
+
+        press(&mut tab, KeyCode::Backspace);
+        press(&mut tab, KeyCode::Backspace);
+        assert_eq!(tab.runners.filtered_items().len(), 2);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+    }
+
+    #[test]
+    fn message_popup_is_dismissed_by_any_key() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        tab.show_export_result(Ok(String::from("report.md")));
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(tab.popup_content.is_none());
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+    }
+
+    /// A "Default" group flagged as the org default and a "gpu" one.
+    fn groups() -> Vec<RunnerGroup> {
+        ["Default", "gpu"].iter().enumerate()
+            .map(|(id, name)| RunnerGroup::from(serde_json::from_value::<crate::client::api::ApiRunnerGroup>(runner_group_json(id, name, "all", id == 0)).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn change_group_lists_groups_and_sends_selection() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        tab.set_runner_groups(&groups());
+
+        press(&mut tab, KeyCode::Enter);
+        while !matches!(tab.operations.selected(), Some(RunnerOperation::ChangeGroup)) {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::ChangeGroup));
+
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        match rx.try_recv() {
+            Ok(BackendMessage::ChangeGroup(id, group)) => {
+                assert_eq!(id, 1);
+                assert_eq!(group, "gpu");
+            }
+            _ => panic!("expected a ChangeGroup message"),
+        }
+    }
+
+    #[test]
+    fn ctrl_q_lists_the_downloads_starting_on_this_platform() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        tab.handle_input(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::FetchRunnerDownloads)));
+        assert!(tab.is_busy());
+
+        let (os, architecture) = local_platform();
+        let app = |os: &str, architecture: &str| RunnerApplication {
+            os: os.to_string(),
+            architecture: architecture.to_string(),
+            download_url: format!("https://example.com/actions-runner-{}-{}.tar.gz", os, architecture),
+            filename: format!("actions-runner-{}-{}.tar.gz", os, architecture),
+            sha256_checksum: None,
+        };
+        tab.show_downloads(Ok(vec![app("plan9", "mips"), app(os, architecture)]));
+        assert!(matches!(tab.stage, Stage::SelectDownload));
+        assert!(!tab.is_busy());
+        assert_eq!(tab.dynamic_list.state.selected(), Some(1));
+
+        press(&mut tab, KeyCode::Left);
+        assert!(tab.is_browsing());
+
+        tab.show_downloads(Err(String::from("Could not list the runner downloads")));
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert!(tab.popup_content.is_some());
+    }
+
+    #[test]
+    fn jit_runner_is_named_then_put_in_a_group_with_labels() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        let ctrl_j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL);
+        tab.handle_input(ctrl_j);
+        assert!(tab.jit_draft.is_none());
+        press(&mut tab, KeyCode::Enter);
+
+        tab.set_runner_groups(&groups());
+        tab.handle_input(ctrl_j);
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::SelectRunner), "an empty name is not accepted");
+        type_text(&mut tab, "jit-01");
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::JitGroup));
+        assert_eq!(tab.dynamic_list.selected().map(|g| g.to_string()).as_deref(), Some("Default (default)"));
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.input_buffer.borrow().text(), "self-hosted");
+        type_text(&mut tab, ", gpu");
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.is_busy());
+        assert!(tab.jit_draft.is_none());
+        match rx.try_recv() {
+            Ok(BackendMessage::GenerateJitConfig(name, group_id, labels)) => {
+                assert_eq!((name.as_str(), group_id), ("jit-01", 1));
+                assert_eq!(labels, vec!["self-hosted", "gpu"]);
+            }
+            _ => panic!("expected a GenerateJitConfig message"),
+        }
+
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 3, "name": "jit-01", "os": "unknown", "status": "offline", "busy": false, "labels": [],
+        })).unwrap();
+        tab.show_jit_config(Ok(JitConfig { runner: api_runner, encoded_jit_config: String::from("c2VjcmV0") }));
+        assert_eq!(tab.copyable, Some((String::from("c2VjcmV0"), "JIT config")));
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.copyable.is_none());
+
+        // Esc drops the draft at any step.
+        tab.handle_input(ctrl_j);
+        type_text(&mut tab, "jit-02");
+        press(&mut tab, KeyCode::Esc);
+        assert!(tab.jit_draft.is_none() && tab.is_browsing());
+    }
+
+    #[test]
+    fn group_filter_is_picked_from_the_loaded_groups() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        tab.set_runner_groups(&groups());
+        let in_group = |id, name, group: Option<&str>| Runner { group: group.map(str::to_string), ..runner(id, name) };
+        tab.set_runners(vec![in_group(1, "web-01", Some("Default")), in_group(2, "gpu-01", Some("gpu")), in_group(3, "old-01", None), in_group(4, "gpu-02", Some("gpu"))]);
+        let listed = |tab: &RunnersTab| tab.runners.filtered_items().iter().map(|r| r.id).collect::<Vec<_>>();
+        let ctrl_v = KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL);
+
+        tab.handle_input(ctrl_v);
+        assert!(matches!(tab.stage, Stage::FilterGroup));
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.group_filter.as_deref(), Some("gpu"));
+        assert_eq!(listed(&tab), vec![2, 4]);
+
+        // Combines with the other filters.
+        tab.toggle_busy();
+        assert!(listed(&tab).is_empty());
+        tab.toggle_busy();
+        type_text(&mut tab, "02");
+        assert_eq!(listed(&tab), vec![4]);
+        press(&mut tab, KeyCode::Backspace);
+        press(&mut tab, KeyCode::Backspace);
+
+        // The default group also holds the runners without one.
+        tab.handle_input(ctrl_v);
+        press(&mut tab, KeyCode::Up);
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(listed(&tab), vec![1, 3]);
+
+        tab.handle_input(ctrl_v);
+        press(&mut tab, KeyCode::Up);
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.group_filter, None);
+        assert_eq!(listed(&tab), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn move_to_default_group_is_skipped_for_runners_already_there() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        tab.set_runner_groups(&groups());
+        let open_remove_from_group = |tab: &mut RunnersTab| {
+            press(tab, KeyCode::Enter);
+            while !matches!(tab.operations.selected(), Some(RunnerOperation::RemoveFromGroup)) {
+                press(tab, KeyCode::Down);
+            }
+            press(tab, KeyCode::Enter);
+        };
+
+        let mut in_default = runner(1, "web-01");
+        in_default.group = Some(String::from("Default"));
+        tab.set_runners(vec![in_default]);
+        open_remove_from_group(&mut tab);
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.dismissable));
+        assert!(rx.try_recv().is_err());
+
+        press(&mut tab, KeyCode::Enter);
+        let mut in_gpu = runner(1, "web-01");
+        in_gpu.group = Some(String::from("gpu"));
+        tab.set_runners(vec![in_gpu]);
+        open_remove_from_group(&mut tab);
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::RemoveFromGroup(1))));
+    }
+
+    #[test]
+    fn delete_waits_for_confirmation() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Enter);
+        while !matches!(tab.operations.selected(), Some(RunnerOperation::Delete)) {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.confirm.is_some());
+
+        assert!(!press(&mut tab, KeyCode::Esc));
+        assert!(tab.confirm.is_none());
+        assert!(rx.try_recv().is_err());
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Char('x'));
+        assert!(rx.try_recv().is_err());
+        press(&mut tab, KeyCode::Char('y'));
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::DeleteRunner(1))));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+    }
+
+    #[test]
+    fn manage_labels_batches_changes_into_one_request() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        let mut labelled = runner(1, "web-01");
+        labelled.labels = vec![String::from("gpu"), String::from("arm64")];
+        tab.set_runners(vec![labelled]);
+
+        press(&mut tab, KeyCode::Enter);
+        while !matches!(tab.operations.selected(), Some(RunnerOperation::ManageLabels)) {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Enter);
+        assert!(matches!(tab.stage, Stage::ManageLabels));
+        assert_eq!(tab.label_draft, vec!["arm64", "gpu"]);
+
+        press(&mut tab, KeyCode::Char('d'));
+        press(&mut tab, KeyCode::Tab);
+        type_text(&mut tab, "GPU");
+        press(&mut tab, KeyCode::Enter);
+        assert!(tab.label_error.is_some());
+        press(&mut tab, KeyCode::Backspace);
+        press(&mut tab, KeyCode::Backspace);
+        press(&mut tab, KeyCode::Backspace);
+        type_text(&mut tab, "docker");
+        press(&mut tab, KeyCode::Enter);
+        assert_eq!(tab.label_draft, vec!["gpu", "docker"]);
+        assert!(rx.try_recv().is_err());
+
+        tab.handle_input(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        press(&mut tab, KeyCode::Char('y'));
+        match rx.try_recv() {
+            Ok(BackendMessage::SetLabels(id, labels)) => {
+                assert_eq!(id, 1);
+                assert_eq!(labels, vec![String::from("gpu"), String::from("docker")]);
+            }
+            _ => panic!("expected a SetLabels message"),
+        }
+    }
+
+    #[test]
+    fn set_labels_is_prefilled_and_sends_the_parsed_list() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        let labels = vec![String::from("gpu"), String::from("cuda")];
+        tab.set_runners(vec![Runner { labels, ..runner(1, "web-01") }, runner(2, "db-01")]);
+
+        press(&mut tab, KeyCode::Enter);
+        while !matches!(tab.operations.selected(), Some(RunnerOperation::SetLabels)) {
+            press(&mut tab, KeyCode::Down);
+        }
+        press(&mut tab, KeyCode::Enter);
+        // Read-only labels can't be replaced, so only custom ones are pre-filled.
+        assert_eq!(tab.input_buffer.borrow().text(), "cuda, gpu");
+
+        type_text(&mut tab, ", , arm64 ");
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::SetLabels(id, labels)) => {
+                assert_eq!(id, 1);
+                assert_eq!(labels, vec![String::from("cuda"), String::from("gpu"), String::from("arm64")]);
+            }
+            _ => panic!("expected a SetLabels message"),
+        }
+        assert!(tab.input_buffer.borrow().text().is_empty());
+    }
+
+    #[test]
+    fn add_label_goes_to_every_marked_runner() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Char(' '));
+        press(&mut tab, KeyCode::Down);
+        press(&mut tab, KeyCode::Char(' '));
+        assert_eq!(tab.runners.marked().len(), 2);
+        assert!(tab.runners.input_buffer.is_empty());
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Enter);
+        type_text(&mut tab, "gpu");
+        press(&mut tab, KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BackendMessage::AddLabelBulk(ids, label)) => {
+                assert_eq!(ids, vec![1, 2]);
+                assert_eq!(label, "gpu");
+            }
+            _ => panic!("expected an AddLabelBulk message"),
+        }
+
+        assert_eq!(tab.runners.marked().len(), 2);
+        tab.set_runners(vec![runner(1, "web-01"), runner(2, "db-01")]);
+        assert!(tab.runners.marked().is_empty());
+    }
+
+    fn open_details(tab: &mut RunnersTab) {
+        press(tab, KeyCode::Enter);
+        while !matches!(tab.operations.selected(), Some(RunnerOperation::Details)) {
+            press(tab, KeyCode::Down);
+        }
+        press(tab, KeyCode::Enter);
+    }
+
+    #[test]
+    fn details_list_custom_and_default_labels_apart() {
+        let (tx, mut rx) = channel();
+        let mut tab = setup(&tx);
+        let id = tab.selected().unwrap().id;
+
+        open_details(&mut tab);
+        assert!(matches!(rx.try_recv(), Ok(BackendMessage::GetRunner(runner_id)) if runner_id == id));
+        assert!(tab.popup_content.as_ref().is_some_and(|p| p.is_loading));
+
+        let mut fresh = runner(id, "web-01");
+        fresh.status = RunnerStatus::Busy;
+        fresh.busy = true;
+        tab.set_runner_details(id, Some(fresh));
+        let content = (tab.popup_content.as_ref().unwrap().content)();
+        assert!(content.contains("Labels: none\nDefault labels: self-hosted"));
+        assert!(content.contains("Busy: true"));
+        assert_eq!(tab.summary.busy, 1);
+    }
+
+    #[test]
+    fn details_of_a_deleted_runner_drop_it_from_the_list() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let id = tab.selected().unwrap().id;
+
+        open_details(&mut tab);
+        tab.set_runner_details(id, None);
+
+        let content = (tab.popup_content.as_ref().unwrap().content)();
+        assert!(content.contains("no longer exists"));
+        assert_eq!(tab.runners.filtered_items().len(), 1);
+        assert!(tab.runners.items.iter().all(|r| r.id != id));
+        assert_eq!(tab.summary.total, 1);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert!(tab.selected().is_some_and(|r| r.id != id));
+        let area = Rect::new(0, 0, 80, 20);
+        tab.render(area, &mut Buffer::empty(area));
+
+        // Filtered down to the runner that is gone, nothing is left to select.
+        press(&mut tab, KeyCode::Esc);
+        let id = tab.selected().unwrap().id;
+        let name = tab.selected().unwrap().name.clone();
+        type_text(&mut tab, &name);
+        open_details(&mut tab);
+        tab.set_runner_details(id, None);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert!(tab.selected().is_none());
+        tab.render(area, &mut Buffer::empty(area));
+    }
+
+    #[test]
+    fn refresh_closes_an_operation_on_a_runner_that_is_gone() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let area = Rect::new(0, 0, 80, 20);
+        press(&mut tab, KeyCode::Enter);
+        let id = tab.selected().unwrap().id;
+
+        tab.refresh_runners(vec![runner(3, "api-01"), runner(1, "web-01"), runner(2, "db-01")]);
+        assert!(matches!(tab.stage, Stage::SelectOp));
+        assert_eq!(tab.selected().map(|r| r.id), Some(id));
+
+        let other = if id == 1 { runner(2, "db-01") } else { runner(1, "web-01") };
+        let other_id = other.id;
+        tab.refresh_runners(vec![other]);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert_eq!(tab.selected().map(|r| r.id), Some(other_id));
+        tab.render(area, &mut Buffer::empty(area));
+
+        press(&mut tab, KeyCode::Enter);
+        tab.refresh_runners(vec![]);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+        assert!(tab.selected().is_none());
+        tab.render(area, &mut Buffer::empty(area));
+    }
+
+    #[test]
+    fn long_popup_scrolls_before_being_dismissed() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let lines: Vec<String> = (0..50).map(|i| format!("line {}", i)).collect();
+        tab.show_error(lines.join("\n"));
+        let area = Rect::new(0, 0, 80, 20);
+        tab.render(area, &mut Buffer::empty(area));
+
+        assert!(!press(&mut tab, KeyCode::PageDown));
+        assert!(!press(&mut tab, KeyCode::Up));
+        let popup = tab.popup_content.as_ref().unwrap();
+        assert_eq!(popup.scroll.get(), popup.page_height.get() - 1);
+
+        press(&mut tab, KeyCode::Char('x'));
+        assert!(tab.popup_content.is_none());
+    }
+
+    #[test]
+    fn vim_keys_navigate_operations_but_filter_runners() {
+        let (tx, _rx) = channel();
+        let config = Config { vim_keys: true, ..Config::default() };
+        let mut tab = RunnersTab::new(&tx, &config, Rc::new(RefCell::new(State::default())));
+        tab.set_runners(vec![runner(1, "web-01"), runner(2, "db-01")]);
+
+        press(&mut tab, KeyCode::Char('j'));
+        assert_eq!(tab.runners.input_buffer, "j");
+        press(&mut tab, KeyCode::Backspace);
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Char('j'));
+        assert!(matches!(tab.operations.selected(), Some(RunnerOperation::RemoveLabel)));
+        press(&mut tab, KeyCode::Char('k'));
+        assert!(matches!(tab.operations.selected(), Some(RunnerOperation::AddLabel)));
+        press(&mut tab, KeyCode::Char('h'));
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+    }
+
+    #[test]
+    fn letters_filter_runners_but_jump_between_operations() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+
+        press(&mut tab, KeyCode::Char('j'));
+        assert_eq!(tab.runners.input_buffer, "j");
+        press(&mut tab, KeyCode::Backspace);
+
+        press(&mut tab, KeyCode::Enter);
+        press(&mut tab, KeyCode::Char('r'));
+        assert!(matches!(tab.operations.selected(), Some(RunnerOperation::RemoveLabel)));
+        press(&mut tab, KeyCode::Char('l'));
+        assert!(matches!(tab.stage, Stage::SelectOp));
+        press(&mut tab, KeyCode::Left);
+        assert!(matches!(tab.stage, Stage::SelectRunner));
+    }
+
+    #[test]
+    fn status_line_counts_the_whole_fleet_regardless_of_filters() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        type_text(&mut tab, "web");
+
+        assert_eq!(tab.runners.filtered_items().len(), 1);
+        assert_eq!(tab.status_line(), " 2 runners: 2 online, 0 busy, 0 offline");
+    }
+
+    #[test]
+    fn busy_filter_composes_with_the_text_filter() {
+        let (tx, _rx) = channel();
+        let mut tab = setup(&tx);
+        let runners = [(1, "linux-1", RunnerStatus::Busy), (2, "linux-2", RunnerStatus::Online), (3, "mac-1", RunnerStatus::Busy)]
+            .into_iter()
+            .map(|(id, name, status)| {
+                let mut runner = runner(id, name);
+                runner.status = status;
+                runner
+            })
+            .collect();
+        tab.set_runners(runners);
+        let names = |tab: &RunnersTab| tab.runners.filtered_items().iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+        let ctrl_b = KeyEvent::new(KeyCode::Char('b'), KeyModifiers::CONTROL);
+
+        tab.handle_input(ctrl_b);
+        assert_eq!(names(&tab), vec!["linux-1", "mac-1"]);
+        // Every fixture runner has os=linux, so match on the name.
+        type_text(&mut tab, "linux-");
+        assert_eq!(names(&tab), vec!["linux-1"]);
+
+        tab.handle_input(ctrl_b);
+        assert_eq!(names(&tab), vec!["linux-1", "linux-2"]);
+    }
+}
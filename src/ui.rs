@@ -1,24 +1,161 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::fmt::{Display, Write};
 use std::ops::Deref;
 use crate::{ALT_ROW_BG_COLOR, NORMAL_ROW_BG, SELECTED_STYLE};
-use ratatui::layout::Rect;
-use ratatui::prelude::{Buffer, Color, Line, StatefulWidget, Style, Stylize, Text, Widget};
-use ratatui::widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::layout::{Margin, Rect};
+use ratatui::prelude::{Buffer, Color, Line, Modifier, Span, StatefulWidget, Style, Stylize, Text, Widget};
+use ratatui::widgets::{Block, Borders, Clear, HighlightSpacing, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap};
 use std::rc::{Rc};
 use color_eyre::owo_colors::OwoColorize;
 use ratatui::symbols;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Cuts `text` so it occupies at most `max_width` terminal columns, marking the cut with `…`.
+/// Wide characters (emoji, CJK) count as two columns and are never split in half: when one
+/// doesn't fit, a space after the `…` keeps a cut text exactly `max_width` wide. Text that
+/// already fits is returned as is, without padding.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if width + char_width > max_width - 1 {
+            break;
+        }
+        width += char_width;
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated.extend(std::iter::repeat_n(' ', max_width - 1 - width));
+    truncated
+}
+
+/// Maps vim-style `h`/`j`/`k`/`l` to Left/Down/Up/Right, leaving any other key untouched.
+///
+/// Only apply this to lists where letters aren't typed: stages that filter as you type (the
+/// runner and group lists) or show a text input popup keep the plain characters, so the letters
+/// always win over navigation there. The other lists jump to the item starting with a typed
+/// letter, and only take h/j/k/l as navigation when `Config::vim_keys` is set.
+pub fn vim_navigation(event: KeyEvent) -> KeyEvent {
+    if event.modifiers != KeyModifiers::NONE {
+        return event;
+    }
+    let code = match event.code {
+        KeyCode::Char('h') => KeyCode::Left,
+        KeyCode::Char('j') => KeyCode::Down,
+        KeyCode::Char('k') => KeyCode::Up,
+        KeyCode::Char('l') => KeyCode::Right,
+        code => code,
+    };
+    KeyEvent { code, ..event }
+}
+
+/// Scores `needle` as a case-insensitive subsequence of `haystack`, `None` if it isn't one.
+/// Consecutive characters and a match right at the start score higher, so "ubnt" ranks
+/// "ubuntu-latest" above "my-ubuntu-runner".
+pub fn fuzzy_score(haystack: &str, needle: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut haystack = haystack.chars().flat_map(char::to_lowercase).enumerate();
+    for c in needle.chars().flat_map(char::to_lowercase) {
+        let (idx, _) = haystack.find(|(_, h)| *h == c)?;
+        score += 1;
+        if idx == 0 {
+            score += 3;
+        }
+        if previous.is_some_and(|p| p + 1 == idx) {
+            score += 2;
+        }
+        previous = Some(idx);
+    }
+    Some(score)
+}
+
+const EMPTY_LIST_HINT: &str = "Nothing to show";
+
+/// Condition an item must satisfy to be listed.
+pub type Predicate<T> = Box<dyn Fn(&T) -> bool>;
+/// Sort order of the listed items.
+pub type Order<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+/// Style of a row, if it differs from the list's.
+pub type ItemStyle<T> = Box<dyn Fn(&T) -> Option<Style>>;
+/// Dimmed text after a row, if any.
+pub type ItemSuffix<T> = Box<dyn Fn(&T) -> Option<String>>;
+/// What identifies an item across refreshes, so marks survive them.
+type MarkKey<T> = Box<dyn Fn(&T) -> usize>;
 
 pub struct FilterableList<T> where T: Display {
     list: SelectableList<T>,
     pub items: Vec<Rc<T>>,
     pub input_buffer: String,
+    predicate: Option<Predicate<T>>,
+    order: Option<Order<T>>,
+    mark_key: Option<MarkKey<T>>,
+    marked: HashSet<usize>,
+    /// Match the input as a subsequence instead of a substring, best matches first.
+    fuzzy: bool,
+    /// Shown when there are no items at all, as opposed to none matching the filters.
+    empty_hint: String,
 }
 
 impl <T: Display> FilterableList<T> {
     pub fn new(items: Vec<T>, style: Style) -> Self {
         let list = SelectableList::new(items, style);
         let cloned_items = list.items.iter().map(|x| Rc::clone(x)).collect();
-        FilterableList { list, items: cloned_items, input_buffer: String::new() }
+        FilterableList {
+            list,
+            items: cloned_items,
+            input_buffer: String::new(),
+            predicate: None,
+            order: None,
+            mark_key: None,
+            marked: HashSet::new(),
+            fuzzy: false,
+            empty_hint: String::from(EMPTY_LIST_HINT),
+        }
+    }
+
+    pub fn with_empty_hint(mut self, hint: &str) -> Self {
+        self.empty_hint = hint.to_string();
+        self
+    }
+
+    fn current_empty_hint(&self) -> String {
+        if self.items.is_empty() {
+            self.empty_hint.clone()
+        } else if self.input_buffer.is_empty() {
+            String::from("No matches for the current filters")
+        } else {
+            format!("No matches for '{}'", self.input_buffer)
+        }
+    }
+
+    /// Sets an extra condition items must satisfy on top of the text filter.
+    pub fn set_predicate(&mut self, predicate: Option<Predicate<T>>) {
+        self.predicate = predicate;
+        self.filter_items();
+    }
+
+    pub fn set_item_style(&mut self, item_style: Option<ItemStyle<T>>) {
+        self.list.set_item_style(item_style);
+    }
+
+    pub fn set_item_suffix(&mut self, item_suffix: Option<ItemSuffix<T>>) {
+        self.list.set_item_suffix(item_suffix);
+    }
+
+    /// Sorts the filtered items without touching the original order of `items`.
+    pub fn set_order(&mut self, order: Option<Order<T>>) {
+        self.order = order;
+        self.filter_items();
     }
 
     pub fn with_first_selected(mut self) -> Self {
@@ -26,15 +163,90 @@ impl <T: Display> FilterableList<T> {
         self
     }
 
+    /// Enables multi-select, identifying marked items by `key` so marks survive refiltering.
+    pub fn with_marks(mut self, key: impl Fn(&T) -> usize + 'static) -> Self {
+        self.mark_key = Some(Box::new(key));
+        self
+    }
+
+    /// Marks the selected item, or unmarks it if it already was.
+    pub fn toggle_mark(&mut self) {
+        let Some(key) = self.mark_key.as_ref().zip(self.selected()).map(|(key, item)| key(item)) else {
+            return;
+        };
+        if !self.marked.remove(&key) {
+            self.marked.insert(key);
+        }
+    }
+
+    pub fn marked(&self) -> &HashSet<usize> {
+        &self.marked
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Drops the marks of items that are gone, e.g. after reloading `items`.
+    pub fn retain_marks(&mut self) {
+        if let Some(key) = &self.mark_key {
+            let existing: HashSet<usize> = self.items.iter().map(|it| key(it)).collect();
+            self.marked.retain(|k| existing.contains(k));
+        }
+    }
+
     pub fn render(&mut self, area: Rect, buf: &mut Buffer, title: &str) {
-        self.list.render(area, buf, title);
+        let hint = self.current_empty_hint();
+        match &self.mark_key {
+            Some(key) => self.list.render_with_hint(area, buf, title, |it| self.marked.contains(&key(it)), &hint),
+            None => self.list.render_with_hint(area, buf, title, |_| false, &hint),
+        }
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy
+    }
+
+    pub fn toggle_fuzzy(&mut self) {
+        self.fuzzy = !self.fuzzy;
+        self.filter_items();
+        self.select_first();
     }
 
+    /// Rebuilds the visible items from `items`. Matching ignores case; in fuzzy mode the
+    /// best matches come first and `order` only breaks ties.
     pub fn filter_items(&mut self) {
-        self.list.items = self.items.iter()
-            .filter(|it| it.to_string().contains(&self.input_buffer))
-            .map(|it| Rc::clone(it))
+        let input = self.input_buffer.to_lowercase();
+        let mut matches: Vec<(usize, Rc<T>)> = self.items.iter()
+            .filter(|it| self.predicate.as_ref().is_none_or(|p| p(it)))
+            .filter_map(|it| {
+                let text = it.to_string();
+                let score = if self.fuzzy {
+                    fuzzy_score(&text, &input)?
+                } else if text.to_lowercase().contains(&input) {
+                    0
+                } else {
+                    return None;
+                };
+                Some((score, Rc::clone(it)))
+            })
             .collect();
+        if let Some(order) = &self.order {
+            matches.sort_by(|(_, a), (_, b)| order(a, b));
+        }
+        if self.fuzzy {
+            matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+        }
+        self.list.items = matches.into_iter().map(|(_, it)| it).collect();
+    }
+
+    /// Keeps the selection on a listed row after items went away, the last one if it fell
+    /// past the end.
+    pub fn clamp_selection(&mut self) {
+        let len = self.list.items.len();
+        if let Some(idx) = self.list.state.selected() {
+            self.list.state.select((len > 0).then(|| idx.min(len - 1)));
+        }
     }
 
     pub fn state(&self) -> &ListState {
@@ -64,13 +276,44 @@ impl <T: Display> FilterableList<T> {
         self.list.select_none();
     }
 
+    pub fn select_page(&mut self, forward: bool, half: bool) {
+        self.list.select_page(forward, half);
+    }
+
     pub fn selected(&self) -> Option<&T> {
         self.list.selected()
     }
 
+    /// Moves the selection to the next (or previous) filtered item matching `predicate`,
+    /// wrapping around. The selection is left untouched when nothing matches.
+    pub fn select_matching(&mut self, forward: bool, predicate: impl Fn(&T) -> bool) {
+        let len = self.list.items.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.list.state.selected()
+            .map(|idx| idx.min(len - 1))
+            .unwrap_or(if forward { len - 1 } else { 0 });
+        let found = (1..=len)
+            .map(|step| if forward { (current + step) % len } else { (current + len - step) % len })
+            .find(|&idx| predicate(&self.list.items[idx]));
+        if let Some(idx) = found {
+            self.list.state.select(Some(idx));
+        }
+    }
+
+    /// Selects the first filtered item matching `predicate`, returns whether there was one.
+    pub fn select_where(&mut self, predicate: impl Fn(&T) -> bool) -> bool {
+        let found = self.list.items.iter().position(|it| predicate(it));
+        if found.is_some() {
+            self.list.state.select(found);
+        }
+        found.is_some()
+    }
+
     pub fn update_filter(&mut self, c: char) {
         self.add_to_input(c);
-        self.filter_items();
+        self.refilter();
     }
 
     pub fn add_to_input(&mut self, c: char) {
@@ -79,7 +322,24 @@ impl <T: Display> FilterableList<T> {
 
     pub fn remove_last_input(&mut self) {
         self.input_buffer.pop();
+        self.refilter();
+    }
+
+    /// Filters again after the input changed, keeping the selected item highlighted if it
+    /// still matches and falling back to the first row otherwise, also when nothing was
+    /// selected, e.g. because the previous filter matched nothing.
+    fn refilter(&mut self) {
+        let previous = self.list.selected_rc().cloned();
         self.filter_items();
+        if self.list.items.is_empty() {
+            self.list.state.select(None);
+            return;
+        }
+        let idx = match previous {
+            Some(previous) if !self.fuzzy => self.list.items.iter().position(|it| Rc::ptr_eq(it, &previous)),
+            _ => None,
+        };
+        self.list.state.select(Some(idx.unwrap_or(0)));
     }
 }
 
@@ -87,6 +347,10 @@ pub struct SelectableList<T> where T: Display {
     pub items: Vec<Rc<T>>,
     pub state: ListState,
     pub border_style: Style,
+    item_style: Option<ItemStyle<T>>,
+    item_suffix: Option<ItemSuffix<T>>,
+    /// Rows the last render had room for, what a page move skips.
+    page_height: usize,
 }
 
 impl <T: Display> SelectableList<T> {
@@ -96,9 +360,22 @@ impl <T: Display> SelectableList<T> {
             items,
             state: ListState::default(),
             border_style,
+            item_style: None,
+            item_suffix: None,
+            page_height: 0,
         }
     }
 
+    /// Per-row style laid over the alternating background, e.g. to color rows by status.
+    pub fn set_item_style(&mut self, item_style: Option<ItemStyle<T>>) {
+        self.item_style = item_style;
+    }
+
+    /// Extra text shown dimmed after each row, not part of what filters match.
+    pub fn set_item_suffix(&mut self, item_suffix: Option<ItemSuffix<T>>) {
+        self.item_suffix = item_suffix;
+    }
+
     pub fn set_items(&mut self, vec: Vec<T>) {
         self.items = vec.into_iter().map(Rc::new).collect();
         self.select_none();
@@ -127,18 +404,78 @@ impl <T: Display> SelectableList<T> {
         self.state.select(None);
     }
 
+    /// Selects the next item whose text starts with `c`, ignoring case, after the selected one
+    /// and wrapping around, so pressing the same letter again cycles through those items.
+    /// The selection is left untouched when none does.
+    pub fn select_by_letter(&mut self, c: char) {
+        let len = self.items.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().map(|idx| idx.min(len - 1));
+        let starts_with = |it: &Rc<T>| it.to_string().chars().next()
+            .is_some_and(|first| first.to_lowercase().eq(c.to_lowercase()));
+        let found = (1..=len)
+            .map(|step| current.map_or(step - 1, |idx| (idx + step) % len))
+            .find(|&idx| starts_with(&self.items[idx]));
+        if found.is_some() {
+            self.state.select(found);
+        }
+    }
+
+    /// Moves the selection down (or up) by the rows visible in the last render, or half of
+    /// them, stopping at the first and last item.
+    pub fn select_page(&mut self, forward: bool, half: bool) {
+        let rows = if half { self.page_height / 2 } else { self.page_height };
+        let target = page_target(self.state.selected(), self.items.len(), rows, forward);
+        if target.is_some() {
+            self.state.select(target);
+        }
+    }
+
+    /// The selected item. Indexes past the end, like the one left by `select_last` before the
+    /// next render, point at the last item the same way ratatui draws them.
     pub fn selected(&self) -> Option<&T> {
-        self.state.selected().map(|idx| self.items[idx].deref())
+        self.selected_rc().map(|it| it.deref())
+    }
+
+    fn selected_rc(&self) -> Option<&Rc<T>> {
+        self.state.selected()
+            .and_then(|idx| self.items.get(idx.min(self.items.len().saturating_sub(1))))
     }
 
     pub fn render(&mut self, area: Rect, buf: &mut Buffer, title: &str) {
+        self.render_marked(area, buf, title, |_| false);
+    }
+
+    /// Renders the list prefixing the rows for which `is_marked` holds with a checkmark.
+    pub fn render_marked(&mut self, area: Rect, buf: &mut Buffer, title: &str, is_marked: impl Fn(&T) -> bool) {
+        self.render_with_hint(area, buf, title, is_marked, EMPTY_LIST_HINT);
+    }
+
+    /// Renders the rows, or `hint` centered below the title when there are none.
+    fn render_with_hint(&mut self, area: Rect, buf: &mut Buffer, title: &str, is_marked: impl Fn(&T) -> bool, hint: &str) {
         let block = Block::new()
             .title(Line::raw(title).centered())
             .borders(Borders::TOP)
             .border_set(symbols::border::EMPTY)
             .border_style(self.border_style)
             .bg(NORMAL_ROW_BG);
+        self.page_height = block.inner(area).height as usize;
 
+        if self.items.is_empty() {
+            let inner = block.inner(area);
+            block.render(area, buf);
+            let hint_area = Rect { y: inner.y + inner.height / 2, height: inner.height.min(1), ..inner };
+            Paragraph::new(truncate_to_width(hint, inner.width as usize))
+                .centered()
+                .style(Style::default().add_modifier(Modifier::DIM))
+                .render(hint_area, buf);
+            return;
+        }
+
+        // Leave room for the highlight symbol column.
+        let max_width = area.width.saturating_sub(1) as usize;
         // Iterate through all elements in the `items` and stylize them.
         let items: Vec<ListItem> = self
             .items
@@ -147,8 +484,25 @@ impl <T: Display> SelectableList<T> {
             .map(|(i, it)| {
                 let color = alternate_colors(i);
                 let item = it.deref();
-                let line = Line::from(item.to_string());
-                ListItem::new(line).bg(color)
+                let marked = is_marked(item);
+                let text = if marked { format!("✓ {}", item) } else { item.to_string() };
+                let suffix = self.item_suffix.as_ref().and_then(|f| f(item)).unwrap_or_default();
+                let truncated = truncate_to_width(&format!("{}{}", text, suffix), max_width);
+                let line = match truncated.strip_prefix(&text) {
+                    Some(rest) if !rest.is_empty() => Line::from(vec![
+                        Span::raw(text.clone()),
+                        Span::styled(rest.to_string(), Style::default().add_modifier(Modifier::DIM)),
+                    ]),
+                    _ => Line::from(truncated),
+                };
+                let mut style = Style::default().bg(color);
+                if let Some(item_style) = self.item_style.as_ref().and_then(|f| f(item)) {
+                    style = style.patch(item_style);
+                }
+                if marked {
+                    style = style.bold();
+                }
+                ListItem::new(line).style(style)
             })
             .collect();
 
@@ -165,6 +519,17 @@ impl <T: Display> SelectableList<T> {
     }
 }
 
+/// Index `rows` away from `selected` in a list of `len` items, clamped to the list. Moves at
+/// least one row, and starts from the top when nothing is selected.
+fn page_target(selected: Option<usize>, len: usize, rows: usize, forward: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let current = selected.map(|idx| idx.min(len - 1)).unwrap_or(0);
+    let rows = rows.max(1);
+    Some(if forward { (current + rows).min(len - 1) } else { current.saturating_sub(rows) })
+}
+
 const fn alternate_colors(i: usize) -> Color {
     if i % 2 == 0 {
         NORMAL_ROW_BG
@@ -180,6 +545,8 @@ pub struct Popup<'a> {
     border_style: Style,
     title_style: Style,
     style: Style,
+    scroll: u16,
+    total_lines: u16,
 }
 
 impl <'a> Popup<'a> {
@@ -195,21 +562,369 @@ impl <'a> Popup<'a> {
         self.style = style;
         self
     }
+
+    /// Starts the content `offset` rows down. A scrollbar is drawn when `total_lines`
+    /// doesn't fit the popup.
+    pub fn scroll(mut self, offset: u16, total_lines: u16) -> Self {
+        self.scroll = offset;
+        self.total_lines = total_lines;
+        self
+    }
 }
 
 impl Widget for Popup<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // ensure that all cells under the popup are cleared to avoid leaking content
         Clear.render(area, buf);
+        let title = truncate_to_width(&self.title.to_string(), area.width.saturating_sub(2) as usize);
         let block = Block::new()
-            .title(self.title)
+            .title(title)
             .title_style(self.title_style)
             .borders(Borders::ALL)
             .border_style(self.border_style);
         Paragraph::new(self.content)
             .wrap(Wrap { trim: true })
+            .scroll((self.scroll, 0))
             .style(self.style)
             .block(block)
             .render(area, buf);
+        let visible = area.height.saturating_sub(2);
+        if self.total_lines > visible {
+            let mut state = ScrollbarState::new((self.total_lines - visible) as usize + 1)
+                .position(self.scroll as usize);
+            StatefulWidget::render(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                area.inner(Margin { vertical: 1, horizontal: 0 }),
+                buf,
+                &mut state,
+            );
+        }
+    }
+}
+
+/// Yes/no question guarding a destructive `action`.
+///
+/// Tabs keep an `Option<ConfirmPopup<_>>` next to their current stage rather than adding a
+/// confirmation stage of their own: while it is `Some`, every key goes to [`ConfirmPopup::answer`]
+/// first and the stage underneath keeps rendering. On `Some(true)` the tab takes the popup and
+/// dispatches `into_action()`, on `Some(false)` it just drops it.
+pub struct ConfirmPopup<A> {
+    title: String,
+    prompt: String,
+    action: A,
+}
+
+impl <A> ConfirmPopup<A> {
+    pub fn new(title: impl Into<String>, prompt: impl Into<String>, action: A) -> Self {
+        ConfirmPopup { title: title.into(), prompt: prompt.into(), action }
+    }
+
+    /// `y`/Enter confirm, `n`/Esc cancel, anything else is ignored.
+    pub fn answer(&self, code: KeyCode) -> Option<bool> {
+        match code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(true),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn into_action(self) -> A {
+        self.action
+    }
+
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let content = format!("{}\n\n[y] yes  [n] no", self.prompt);
+        let width = area.width / 2;
+        let wrapped_lines: u16 = content.lines()
+            .map(|line| (line.width() as u16).div_ceil(width.saturating_sub(2).max(1)).max(1))
+            .sum();
+        let height = (wrapped_lines + 2).min(area.height);
+        let popup_area = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        Popup::default()
+            .title(self.title.as_str())
+            .content(content)
+            .render(popup_area, buf);
+    }
+}
+
+/// Text typed into an input popup, with a cursor that can move inside it.
+#[derive(Debug, Default)]
+pub struct InputField {
+    text: String,
+    /// Position in chars, from 0 (before the first one) to the text length.
+    cursor: usize,
+}
+
+impl InputField {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, e.g. to pre-fill it, leaving the cursor at the end.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.chars().count();
+    }
+
+    /// Returns the text and leaves the field empty.
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    fn byte_offset(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map_or(self.text.len(), |(idx, _)| idx)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let offset = self.byte_offset(self.cursor);
+        self.text.insert(offset, c);
+        self.cursor += 1;
+    }
+
+    /// Removes the char before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    /// Removes the char under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.chars().count() {
+            self.text.remove(self.byte_offset(self.cursor));
+        }
+    }
+
+    /// Edits the text or moves the cursor, returns false for keys it doesn't handle.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.chars().count(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The text with `│` drawn at the cursor.
+    pub fn render(&self) -> String {
+        let offset = self.byte_offset(self.cursor);
+        format!("{}│{}", &self.text[..offset], &self.text[offset..])
+    }
+
+    /// Same as `render` with every char shown as `*`, for secrets.
+    pub fn render_masked(&self) -> String {
+        let len = self.text.chars().count();
+        format!("{}│{}", "*".repeat(self.cursor), "*".repeat(len - self.cursor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> FilterableList<String> {
+        FilterableList::new(items.iter().map(|it| it.to_string()).collect(), Style::default())
+    }
+
+    fn visible(list: &FilterableList<String>) -> Vec<&str> {
+        list.filtered_items().iter().map(|it| it.as_str()).collect()
+    }
+
+    fn type_filter(list: &mut FilterableList<String>, text: &str) {
+        text.chars().for_each(|c| list.update_filter(c));
+    }
+
+    #[test]
+    fn truncation_counts_wide_characters_as_two_columns() {
+        assert_eq!(truncate_to_width("runner-01", 20), "runner-01");
+        assert_eq!(truncate_to_width("runner-01", 7), "runner…");
+        assert_eq!(truncate_to_width("runner-01", 0), "");
+
+        // A wide character that would straddle the cut is dropped and padded for.
+        assert_eq!(truncate_to_width("ab漢字", 5), "ab漢…");
+        assert_eq!(truncate_to_width("ab漢字", 4), "ab… ");
+        assert_eq!(truncate_to_width("🚀🚀🚀", 4), "🚀… ");
+        for width in 1..8 {
+            assert_eq!(truncate_to_width("ab漢字🚀", width).width(), width);
+        }
+        // Fitting text isn't padded.
+        assert_eq!(truncate_to_width("漢字", 6), "漢字");
+    }
+
+    #[test]
+    fn filter_ignores_case() {
+        let mut list = list(&["Ubuntu-Latest", "windows"]);
+        type_filter(&mut list, "ubuntu-l");
+        assert_eq!(visible(&list), vec!["Ubuntu-Latest"]);
+    }
+
+    #[test]
+    fn item_suffix_is_dimmed_and_not_filtered_on() {
+        let mut list = list(&["web-01"]);
+        list.set_item_suffix(Some(Box::new(|_: &String| Some(String::from(" · self-hosted")))));
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+        list.render(area, &mut buf, "Runners");
+        // Row 1, after the highlight column: "web-01" then the dimmed suffix.
+        assert!(!buf[(1, 1)].modifier.contains(Modifier::DIM));
+        assert!(buf[(8, 1)].modifier.contains(Modifier::DIM));
+
+        type_filter(&mut list, "self");
+        assert!(visible(&list).is_empty());
+    }
+
+    fn rendered_text(list: &mut FilterableList<String>) -> String {
+        let area = Rect::new(0, 0, 40, 5);
+        let mut buf = Buffer::empty(area);
+        list.render(area, &mut buf, "Runners");
+        buf.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn empty_list_tells_no_data_apart_from_no_matches() {
+        let mut empty = list(&[]).with_empty_hint("No runners found");
+        let text = rendered_text(&mut empty);
+        assert!(text.contains("Runners"));
+        assert!(text.contains("No runners found"));
+
+        let mut runners = list(&["web-01"]).with_empty_hint("No runners found");
+        type_filter(&mut runners, "gpu");
+        let text = rendered_text(&mut runners);
+        assert!(text.contains("No matches for 'gpu'"));
+        assert!(!text.contains("No runners found"));
+    }
+
+    #[test]
+    fn fuzzy_filter_matches_subsequences_best_first() {
+        let mut list = list(&["my-ubuntu-runner", "ubuntu-latest", "windows"]);
+        list.toggle_fuzzy();
+        type_filter(&mut list, "UBNT");
+        assert_eq!(visible(&list), vec!["ubuntu-latest", "my-ubuntu-runner"]);
+        assert_eq!(list.selected().map(String::as_str), Some("ubuntu-latest"));
+
+        type_filter(&mut list, "z");
+        assert!(visible(&list).is_empty());
+    }
+
+    #[test]
+    fn letters_jump_to_matching_items_and_wrap_around() {
+        let mut list = SelectableList::new(vec!["Add label", "Change group", "add to group", "Delete runner"], Style::default());
+        list.select_by_letter('a');
+        assert_eq!(list.selected(), Some(&"Add label"));
+        list.select_by_letter('A');
+        assert_eq!(list.selected(), Some(&"add to group"));
+        list.select_by_letter('a');
+        assert_eq!(list.selected(), Some(&"Add label"));
+        list.select_by_letter('d');
+        assert_eq!(list.selected(), Some(&"Delete runner"));
+        list.select_by_letter('c');
+        assert_eq!(list.selected(), Some(&"Change group"));
+        list.select_by_letter('x');
+        assert_eq!(list.selected(), Some(&"Change group"));
+    }
+
+    #[test]
+    fn page_moves_are_clamped_to_the_ends() {
+        assert_eq!(page_target(Some(2), 20, 8, true), Some(10));
+        assert_eq!(page_target(Some(15), 20, 8, true), Some(19));
+        assert_eq!(page_target(Some(10), 20, 8, false), Some(2));
+        assert_eq!(page_target(Some(3), 20, 8, false), Some(0));
+        assert_eq!(page_target(None, 20, 8, true), Some(8));
+        assert_eq!(page_target(Some(usize::MAX), 20, 8, false), Some(11));
+        assert_eq!(page_target(Some(4), 20, 0, true), Some(5));
+        assert_eq!(page_target(None, 0, 8, true), None);
+    }
+
+    #[test]
+    fn page_size_comes_from_the_rendered_height() {
+        let items: Vec<String> = (0..30).map(|i| format!("runner-{:02}", i)).collect();
+        let mut list = FilterableList::new(items, Style::default()).with_first_selected();
+        // Ten rows below the title.
+        let area = Rect::new(0, 0, 30, 11);
+        list.render(area, &mut Buffer::empty(area), "Runners");
+
+        list.select_page(true, false);
+        assert_eq!(list.selected().map(String::as_str), Some("runner-10"));
+        list.select_page(true, true);
+        assert_eq!(list.selected().map(String::as_str), Some("runner-15"));
+        list.select_page(false, false);
+        assert_eq!(list.selected().map(String::as_str), Some("runner-05"));
+        list.select_page(false, false);
+        assert_eq!(list.selected().map(String::as_str), Some("runner-00"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn selection_stays_valid_while_filtering() {
+        let mut list = list(&["linux-1", "linux-2", "mac-1", "windows-1"]);
+        list.select_last();
+        assert_eq!(list.selected().map(String::as_str), Some("windows-1"));
+
+        type_filter(&mut list, "linux");
+        assert_eq!(list.list.state.selected(), Some(0));
+        assert_eq!(list.selected().map(String::as_str), Some("linux-1"));
+
+        list.select_next();
+        type_filter(&mut list, "-2");
+        assert_eq!(list.selected().map(String::as_str), Some("linux-2"));
+
+        type_filter(&mut list, "x");
+        assert_eq!(list.selected(), None);
+
+        list.remove_last_input();
+        assert_eq!(list.selected().map(String::as_str), Some("linux-2"));
+
+        list.input_buffer.clear();
+        list.remove_last_input();
+        assert_eq!(list.filtered_items().len(), 4);
+        assert_eq!(list.selected().map(String::as_str), Some("linux-2"));
+    }
+
+    #[test]
+    fn input_field_edits_at_the_cursor() {
+        let mut input = InputField::default();
+        "gpu".chars().for_each(|c| input.insert(c));
+        input.handle_key(KeyCode::Home);
+        input.insert('x');
+        assert_eq!(input.render(), "x│gpu");
+
+        input.handle_key(KeyCode::Right);
+        input.handle_key(KeyCode::Right);
+        input.insert('-');
+        assert_eq!(input.text(), "xgp-u");
+
+        input.handle_key(KeyCode::Delete);
+        assert_eq!(input.render(), "xgp-│");
+        input.handle_key(KeyCode::Delete);
+        assert_eq!(input.text(), "xgp-");
+
+        input.handle_key(KeyCode::Home);
+        input.handle_key(KeyCode::Backspace);
+        input.handle_key(KeyCode::Delete);
+        assert_eq!(input.render(), "│gp-");
+        assert!(!input.handle_key(KeyCode::Enter));
+    }
+
+    #[test]
+    fn input_field_handles_multibyte_chars() {
+        let mut input = InputField::default();
+        input.set_text("añb");
+        input.handle_key(KeyCode::Left);
+        input.handle_key(KeyCode::Backspace);
+        assert_eq!(input.render(), "a│b");
+        assert_eq!(input.take(), "ab");
+        assert!(input.text().is_empty());
+        assert_eq!(input.render(), "│");
+    }
+}
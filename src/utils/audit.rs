@@ -0,0 +1,51 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+/// One change, as written to the audit log.
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    /// UTC, RFC 3339.
+    timestamp: String,
+    /// Organization changed, the enterprise slug at the enterprise scope.
+    organization: &'a str,
+    target: &'a str,
+    operation: &'a str,
+}
+
+/// Appends every change made to the fleet to a file, one JSON object per line,
+/// for whoever has to account for them later.
+pub struct AuditLog {
+    path: PathBuf,
+    /// Opened on the first change, a session that changes nothing doesn't create the file.
+    file: Option<LineWriter<File>>,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        AuditLog { path: path.into(), file: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a line saying `operation` was done to `target` in `organization`.
+    pub fn record(&mut self, organization: &str, target: &str, operation: &str) -> io::Result<()> {
+        let entry = AuditEntry {
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            organization,
+            target,
+            operation,
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => self.file.insert(LineWriter::new(OpenOptions::new().create(true).append(true).open(&self.path)?)),
+        };
+        file.write_all(line.as_bytes())
+    }
+}
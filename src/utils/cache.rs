@@ -1,49 +1,234 @@
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use cli_log::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
+/// Seconds an entry stays fresh unless a TTL is given on insert.
+pub const DEFAULT_TTL: usize = 300;
+
+/// Where persistent caches are stored, e.g. `~/.cache/runners-rs` on Linux.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("runners-rs"))
+}
+
+fn system_clock() -> usize {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as usize
+}
+
+#[derive(Serialize, Deserialize)]
 struct CacheEntry<T> {
     timestamp: usize,
     ttl: usize,
     item: T,
+    etag: Option<String>,
 }
 
 impl<T> CacheEntry<T> {
-    fn new(item: T, ttl: usize) -> Self {
+    fn new(item: T, ttl: usize, etag: Option<String>, now: usize) -> Self {
         CacheEntry {
             item,
             ttl,
-            timestamp: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as usize,
+            etag,
+            timestamp: now,
         }
     }
 
-    fn is_expired(&self) -> bool {
-        let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as usize;
-        self.timestamp <= now && now < self.timestamp + self.ttl
+    fn is_expired(&self, now: usize) -> bool {
+        now >= self.timestamp + self.ttl
     }
 }
 
 pub struct Cache<T> {
-    entries: HashMap<String, CacheEntry<T>>
+    entries: HashMap<String, CacheEntry<T>>,
+    clock: Box<dyn Fn() -> usize + Send>,
+    file: Option<PathBuf>,
 }
 
-impl<T> Cache<T> {
+impl<T: Serialize + DeserializeOwned> Cache<T> {
     pub fn new() -> Cache<T> {
+        Cache::with_clock(system_clock)
+    }
+
+    /// Cache reading the current time (in seconds) from `clock` instead of the system clock.
+    pub fn with_clock(clock: impl Fn() -> usize + Send + 'static) -> Cache<T> {
         Cache {
-            entries: HashMap::new()
+            entries: HashMap::new(),
+            clock: Box::new(clock),
+            file: None,
         }
     }
 
-    pub fn insert(&mut self, key: String, value: T) {
-        self.insert_with_ttl(key, value, None);
+    /// Cache backed by a JSON file, written on every insert and read back here.
+    pub fn new_persistent(path: impl Into<PathBuf>) -> Cache<T> {
+        Cache::new().with_file(path)
+    }
+
+    /// Loads the still fresh entries of `path` and keeps saving to it from now on.
+    /// A missing or unreadable file just starts an empty cache.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Cache<T> {
+        let path = path.into();
+        let now = (self.clock)();
+        match fs::read_to_string(&path).map(|contents| serde_json::from_str::<HashMap<String, CacheEntry<T>>>(&contents)) {
+            Ok(Ok(entries)) => self.entries = entries.into_iter().filter(|(_, e)| !e.is_expired(now)).collect(),
+            Ok(Err(e)) => warn!("Ignoring invalid cache file {}: {}", path.display(), e),
+            Err(_) => {}
+        }
+        self.file = Some(path);
+        self
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.file else {
+            return;
+        };
+        if let Err(e) = write_entries(path, &self.entries) {
+            warn!("Could not save cache file {}: {}", path.display(), e);
+        }
     }
 
-    pub fn insert_with_ttl(&mut self, key: String, value: T, ttl: Option<usize>) {
-        let entry = CacheEntry::new(value, ttl.unwrap_or(300));
+    pub fn insert_with_ttl_and_etag(&mut self, key: String, value: T, ttl: Option<usize>, etag: Option<String>) {
+        let entry = CacheEntry::new(value, ttl.unwrap_or(DEFAULT_TTL), etag, (self.clock)());
         self.entries.insert(key, entry);
+        self.save();
     }
 
+    /// ETag of the stored entry, even if it is no longer fresh.
+    pub fn etag(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).and_then(|x| x.etag.as_deref())
+    }
+
+    /// Marks an entry as fresh again after the server answered `304 Not Modified`.
+    pub fn revalidate(&mut self, key: &str) -> Option<&T> {
+        let now = (self.clock)();
+        self.entries.get_mut(key)?.timestamp = now;
+        self.save();
+        self.entries.get(key).map(|x| &x.item)
+    }
+
+    /// Drops every entry, e.g. after a change that can affect any cached response.
+    pub fn invalidate_all(&mut self) {
+        if !self.entries.is_empty() {
+            self.entries.clear();
+            self.save();
+        }
+    }
+
+    /// The stored value, as long as it is still within its TTL.
     pub fn get(&self, key: &str) -> Option<&T> {
-        let entry = self.entries.get(key);
-        entry.filter(|x| x.is_expired()).map(|x| &x.item)
+        let now = (self.clock)();
+        self.entries.get(key)
+            .filter(|x| !x.is_expired(now))
+            .map(|x| &x.item)
+    }
+}
+
+fn write_entries<T: Serialize>(path: &Path, entries: &HashMap<String, CacheEntry<T>>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(entries)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn mocked_cache(start: usize) -> (Cache<String>, Arc<AtomicUsize>) {
+        let now = Arc::new(AtomicUsize::new(start));
+        let clock = Arc::clone(&now);
+        (Cache::with_clock(move || clock.load(Ordering::SeqCst)), now)
+    }
+
+    fn value() -> String {
+        String::from("value")
+    }
+
+    #[test]
+    fn entry_is_evicted_once_its_ttl_elapses() {
+        let (mut cache, now) = mocked_cache(1_000);
+        cache.insert_with_ttl_and_etag(String::from("key"), value(), Some(1), None);
+        assert_eq!(cache.get("key"), Some(&value()));
+
+        now.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(cache.get("key"), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn entry_is_fresh_until_the_boundary() {
+        let (mut cache, now) = mocked_cache(1_000);
+        cache.insert_with_ttl_and_etag(String::from("key"), value(), None, None);
+
+        now.store(1_000 + DEFAULT_TTL - 1, Ordering::SeqCst);
+        assert_eq!(cache.get("key"), Some(&value()));
+        now.store(1_000 + DEFAULT_TTL, Ordering::SeqCst);
+        assert_eq!(cache.get("key"), None);
+    }
+
+    #[test]
+    fn short_ttl_entry_expires_before_a_long_one() {
+        let (mut cache, now) = mocked_cache(1_000);
+        cache.insert_with_ttl_and_etag(String::from("runners"), value(), Some(30), None);
+        cache.insert_with_ttl_and_etag(String::from("groups"), value(), Some(600), Some(String::from("\"abc\"")));
+
+        now.store(1_000 + 30, Ordering::SeqCst);
+        assert_eq!(cache.get("runners"), None);
+        assert_eq!(cache.get("groups"), Some(&value()));
+
+        now.store(1_000 + 600, Ordering::SeqCst);
+        assert_eq!(cache.get("groups"), None);
+    }
+
+    #[test]
+    fn revalidate_makes_an_expired_entry_fresh_again() {
+        let (mut cache, now) = mocked_cache(1_000);
+        cache.insert_with_ttl_and_etag(String::from("key"), value(), None, Some(String::from("\"abc\"")));
+        now.fetch_add(DEFAULT_TTL, Ordering::SeqCst);
+        assert_eq!(cache.get("key"), None);
+        assert_eq!(cache.etag("key"), Some("\"abc\""));
+
+        assert_eq!(cache.revalidate("key"), Some(&value()));
+        assert_eq!(cache.get("key"), Some(&value()));
+    }
+
+    #[test]
+    fn invalidated_entries_are_gone_including_their_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let (mut cache, _) = mocked_cache(1_000);
+        cache = cache.with_file(&path);
+        cache.insert_with_ttl_and_etag(String::from("a"), value(), None, Some(String::from("\"abc\"")));
+        cache.insert_with_ttl_and_etag(String::from("b"), value(), None, None);
+
+        cache.invalidate_all();
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.etag("a"), None);
+        assert_eq!(cache.get("b"), None);
+        let reloaded: Cache<String> = Cache::with_clock(|| 1_000).with_file(&path);
+        assert_eq!(reloaded.get("b"), None);
+    }
+
+    #[test]
+    fn persistent_cache_round_trips_through_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("cache.json");
+        let (mut cache, _) = mocked_cache(1_000);
+        cache = cache.with_file(&path);
+        cache.insert_with_ttl_and_etag(String::from("fresh"), value(), None, Some(String::from("\"abc\"")));
+        cache.insert_with_ttl_and_etag(String::from("short"), value(), Some(10), None);
+
+        let now = Arc::new(AtomicUsize::new(1_100));
+        let clock = Arc::clone(&now);
+        let reloaded: Cache<String> = Cache::with_clock(move || clock.load(Ordering::SeqCst)).with_file(&path);
+
+        assert_eq!(reloaded.get("fresh"), Some(&value()));
+        assert_eq!(reloaded.etag("fresh"), Some("\"abc\""));
+        assert_eq!(reloaded.get("short"), None);
+        assert_eq!(reloaded.etag("short"), None);
+    }
+}
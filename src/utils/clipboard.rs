@@ -0,0 +1,36 @@
+use crate::PopupInfo;
+#[cfg(feature = "clipboard")]
+use std::cell::RefCell;
+
+#[cfg(feature = "clipboard")]
+thread_local! {
+    // On X11 and Wayland the copied text is served by its owner, so the
+    // clipboard has to outlive the call instead of being dropped right away.
+    static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+}
+
+/// Puts `text` in the system clipboard. Fails on headless sessions or when
+/// built without the `clipboard` feature.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<(), String> {
+    CLIPBOARD.with_borrow_mut(|clipboard| {
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new().map_err(|e| format!("Clipboard unavailable: {}", e))?);
+        }
+        clipboard.as_mut().unwrap().set_text(text)
+            .map_err(|e| format!("Could not copy to clipboard: {}", e))
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<(), String> {
+    Err(String::from("This build has no clipboard support"))
+}
+
+/// Copies `text` and returns the popup telling whether `what` made it to the clipboard.
+pub fn copy_with_popup(text: &str, what: &str) -> PopupInfo {
+    match copy(text) {
+        Ok(()) => PopupInfo::new(String::from("Copied"), format!("{} copied to the clipboard", what)),
+        Err(e) => PopupInfo::new(String::from("Error"), e),
+    }
+}
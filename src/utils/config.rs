@@ -1,29 +1,913 @@
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{bail, Result};
+use reqwest::Url;
 use serde::Deserialize;
+use crate::model::runners::RequiredLabels;
+use crate::utils::cache::DEFAULT_TTL;
+use crate::client::api::DEFAULT_SERVER_ERROR_RETRIES;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// Organization whose repositories are listed. Also where runners live at the org scope.
+    #[serde(default)]
     pub organization: String,
+    /// Whether runners and runner groups are managed for the organization or the enterprise.
+    #[serde(default)]
+    pub scope: ApiScope,
+    /// Enterprise slug, only used at the enterprise scope.
+    #[serde(default)]
+    pub enterprise: String,
+    /// Organizations offered by the org switcher. The first one is used when `organization` is unset.
+    #[serde(default)]
+    pub organizations: Vec<String>,
+    #[serde(default)]
     pub token: String,
+    /// Where the token comes from instead of `token`: `gh` asks the GitHub CLI for it.
+    #[serde(default)]
+    pub token_source: Option<String>,
+    /// REST API root, e.g. `https://ghe.internal/api/v3` for GitHub Enterprise Server.
+    /// `https://api.github.com` is used when unset.
+    #[serde(default)]
+    pub api_base: Option<String>,
+    #[serde(default)]
+    pub required_labels: RequiredLabels,
+    #[serde(default)]
+    pub profiles: Profiles,
+    #[serde(default = "default_drain_label")]
+    pub drain_label: String,
+    /// Labels offered when adding one, next to typing it.
+    #[serde(default)]
+    pub label_presets: Vec<String>,
+    /// Tabs to show and their order, by key: `runners`, `groups` and `repos`.
+    /// All of them in that order when empty.
+    #[serde(default)]
+    pub tabs: Vec<String>,
+    #[serde(default)]
+    pub operations: OperationsConfig,
+    /// Keep API responses on disk between runs.
+    #[serde(default)]
+    pub persist_cache: bool,
+    /// Runner groups whose runners are fetched at the same time.
+    #[serde(default = "default_fetch_concurrency")]
+    pub fetch_concurrency: usize,
+    /// Seconds before an API request is abandoned.
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: u64,
+    /// Seconds fetched runners are reused before asking GitHub again.
+    #[serde(default = "default_cache_ttl")]
+    pub runners_cache_ttl: usize,
+    /// Same for runner groups, which change far less often than runner status.
+    #[serde(default = "default_cache_ttl")]
+    pub groups_cache_ttl: usize,
+    /// Times a GET answered with a 5xx is retried before the error is shown.
+    #[serde(default = "default_server_error_retries")]
+    pub server_error_retries: u32,
+    /// Seconds between automatic refreshes of the runner list, never when unset.
+    #[serde(default)]
+    pub refresh_interval_secs: Option<u64>,
+    /// Log changes instead of sending them, GitHub is only read from.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// File every change that went through is appended to, one JSON object per line.
+    /// Nothing is logged when unset.
+    #[serde(default)]
+    pub audit_log: Option<String>,
+    /// Move with h/j/k/l in lists that don't filter as you type, instead of jumping to the
+    /// item starting with the letter. Filters and text inputs always take the letters.
+    #[serde(default)]
+    pub vim_keys: bool,
 }
 
-pub fn read_dot_env() -> Option<Config> {
-    let contents = fs::read_to_string(".env")
-        .expect("Something went wrong reading .env file");
-    let attributes = contents.split("\n");
-    let mut props = HashMap::<String, String>::new();
-    attributes
-        .map(|a| a.split_once("="))
-        .for_each(|field| {
-            match field {
-                Some((key, value)) => props.insert(key.trim().to_string(), value.trim().to_string()),
-                None => None
-            };
-        });
-    Some(Config {
-        organization: props.get("organization")?.to_string(),
-        token: props.get("token")?.to_string(),
+/// Level the runner groups and runners are administered at.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    #[default]
+    Org,
+    Enterprise,
+}
+
+impl ApiScope {
+    /// Path segment the endpoints of this scope live under.
+    pub fn path_segment(&self) -> &'static str {
+        match self {
+            ApiScope::Org => "orgs",
+            ApiScope::Enterprise => "enterprises",
+        }
+    }
+
+    /// What runner groups grant access to: repositories of an org, organizations of an enterprise.
+    pub fn access_segment(&self) -> &'static str {
+        match self {
+            ApiScope::Org => "repositories",
+            ApiScope::Enterprise => "organizations",
+        }
+    }
+}
+
+/// Which menu operations are shown, by their `key()`. Hidden operations win over allowed ones.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct OperationsConfig {
+    #[serde(default)]
+    pub allowed: Option<Vec<String>>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+}
+
+impl OperationsConfig {
+    pub fn is_enabled(&self, key: &str) -> bool {
+        self.allowed.as_ref().is_none_or(|allowed| allowed.iter().any(|k| k == key))
+            && !self.hidden.iter().any(|k| k == key)
+    }
+
+    /// Configured names that don't match any of the `known` operation keys.
+    pub fn unknown(&self, known: &[&str]) -> Vec<&str> {
+        self.allowed.iter().flatten()
+            .chain(self.hidden.iter())
+            .map(String::as_str)
+            .filter(|key| !known.contains(key))
+            .collect()
+    }
+}
+
+fn default_drain_label() -> String {
+    String::from("maintenance")
+}
+
+fn default_fetch_concurrency() -> usize {
+    8
+}
+
+fn default_request_timeout() -> u64 {
+    30
+}
+
+fn default_cache_ttl() -> usize {
+    DEFAULT_TTL
+}
+
+fn default_server_error_retries() -> u32 {
+    DEFAULT_SERVER_ERROR_RETRIES
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            organization: String::new(),
+            scope: ApiScope::default(),
+            enterprise: String::new(),
+            organizations: vec![],
+            token: String::new(),
+            token_source: None,
+            api_base: None,
+            required_labels: RequiredLabels::default(),
+            profiles: Profiles::default(),
+            drain_label: default_drain_label(),
+            label_presets: vec![],
+            tabs: vec![],
+            operations: OperationsConfig::default(),
+            persist_cache: false,
+            fetch_concurrency: default_fetch_concurrency(),
+            request_timeout: default_request_timeout(),
+            runners_cache_ttl: default_cache_ttl(),
+            groups_cache_ttl: default_cache_ttl(),
+            server_error_retries: default_server_error_retries(),
+            refresh_interval_secs: None,
+            dry_run: false,
+            audit_log: None,
+            vim_keys: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub organization: String,
+    pub token: String,
+    #[serde(default)]
+    pub api_base: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Profiles(pub Vec<Profile>);
+
+impl Profiles {
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.0.iter().find(|p| p.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0.iter().map(|p| p.name.clone()).collect()
+    }
+}
+
+impl Config {
+    /// Switches organization, token and API base to the named profile.
+    /// Returns false (leaving the config untouched) when the profile doesn't exist.
+    pub fn use_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return false;
+        };
+        self.organization = profile.organization;
+        self.token = profile.token;
+        self.api_base = profile.api_base;
+        true
+    }
+
+    /// Base URL for the runner and runner group endpoints, ending in `/orgs/{org}/`
+    /// or `/enterprises/{enterprise}/` depending on the scope.
+    pub fn api_url(&self) -> Result<Url> {
+        match self.scope {
+            ApiScope::Org => scoped_api_url(self.api_base.as_deref(), self.scope, &self.organization),
+            ApiScope::Enterprise => scoped_api_url(self.api_base.as_deref(), self.scope, &self.enterprise),
+        }
+    }
+}
+
+pub const DEFAULT_API_BASE: &str = "https://api.github.com";
+
+pub fn org_api_url(api_base: Option<&str>, organization: &str) -> Result<Url> {
+    scoped_api_url(api_base, ApiScope::Org, organization)
+}
+
+pub fn scoped_api_url(api_base: Option<&str>, scope: ApiScope, name: &str) -> Result<Url> {
+    let api_base = api_base.unwrap_or(DEFAULT_API_BASE);
+    let url = Url::parse(api_base.trim_end_matches('/'))?;
+    if url.scheme() != "https" && url.scheme() != "http" {
+        bail!("api_base must be an http(s) URL, got {}", api_base);
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        bail!("api_base must not have a query or fragment, got {}", api_base);
+    }
+    Ok(Url::parse(&format!("{}/{}/{}/", url.as_str().trim_end_matches('/'), scope.path_segment(), name))?)
+}
+
+/// Splits a comma-separated value, trimming entries and dropping empty ones.
+pub fn parse_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file exists but couldn't be read.
+    Read(&'static str, std::io::Error),
+    MissingOrganization,
+    MissingToken,
+    InvalidApiBase(String, String),
+    /// `token_source=gh` is set but `gh auth token` failed.
+    GhToken(String),
+    /// `runners.toml` exists but isn't valid.
+    Toml(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "Could not read {}: {}", path, e),
+            ConfigError::MissingOrganization => write!(f, "No organization configured, set `organization` in {} or {} or the {} variable", CONFIG_TOML, DOT_ENV, ORG_VARS.join("/")),
+            ConfigError::MissingToken => write!(f, "No token configured, set `token` in {} or {} or the {} variable", CONFIG_TOML, DOT_ENV, TOKEN_VARS.join("/")),
+            ConfigError::InvalidApiBase(value, reason) => write!(f, "Invalid api_base {}: {}", value, reason),
+            ConfigError::GhToken(reason) => write!(f, "Could not get a token from the GitHub CLI: {}", reason),
+            ConfigError::Toml(reason) => write!(f, "Invalid {}: {}", CONFIG_TOML, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+pub const DOT_ENV: &str = ".env";
+pub const CONFIG_TOML: &str = "runners.toml";
+const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+const ORG_VARS: [&str; 2] = ["ORG", "GITHUB_ORG"];
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Reads `runners.toml` from the working directory, or `.env` when there is none. Environment
+/// variables override the organization and token of either.
+pub fn read_config() -> Result<Config, ConfigError> {
+    match fs::read_to_string(CONFIG_TOML) {
+        Ok(contents) => parse_config_toml(&contents, env_var),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => read_dot_env(),
+        Err(e) => Err(ConfigError::Read(CONFIG_TOML, e)),
+    }
+}
+
+/// Reads `.env` from the working directory, letting environment variables override the
+/// organization and token. A missing file is fine as long as the variables are set.
+pub fn read_dot_env() -> Result<Config, ConfigError> {
+    let contents = match fs::read_to_string(DOT_ENV) {
+        Ok(contents) => Some(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(ConfigError::Read(DOT_ENV, e)),
+    };
+    parse_config(contents.as_deref(), env_var)
+}
+
+/// Builds the config from `runners.toml`, whose keys are the `Config` fields. Settings a `.env`
+/// can only flatten get their own tables: `[[profiles]]`, `[required_labels]` (with
+/// `[required_labels.groups]`) and `[operations]`. As with the `.env`, `env` overrides the
+/// organization and token, which otherwise default to the first profile's.
+fn parse_config_toml(contents: &str, env: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+    parse_config_toml_with(contents, env, gh_auth_token)
+}
+
+/// Same as `parse_config_toml`, resolving `token_source = "gh"` through `gh_token`.
+fn parse_config_toml_with(contents: &str, env: impl Fn(&str) -> Option<String>, gh_token: impl Fn(Option<&str>) -> Result<String, ConfigError>) -> Result<Config, ConfigError> {
+    let mut config: Config = toml::from_str(contents)
+        .map_err(|e| ConfigError::Toml(e.message().to_string()))?;
+    if !config.enterprise.is_empty() {
+        config.scope = ApiScope::Enterprise;
+    }
+    let default_profile = config.profiles.0.first().cloned();
+    if let Some(organization) = ORG_VARS.iter().find_map(|name| env(name)) {
+        config.organization = organization;
+    } else if config.organization.is_empty() {
+        config.organization = default_profile.as_ref().map(|p| p.organization.clone())
+            .or_else(|| config.organizations.first().cloned())
+            .unwrap_or_default();
+    }
+    if config.organization.is_empty() && config.scope == ApiScope::Org {
+        return Err(ConfigError::MissingOrganization);
+    }
+    if config.api_base.is_none() {
+        config.api_base = default_profile.as_ref().and_then(|p| p.api_base.clone());
+    }
+    if let Some(token) = TOKEN_VARS.iter().find_map(|name| env(name)) {
+        config.token = token;
+    } else if config.token_source.as_deref() == Some("gh") {
+        config.token = gh_token(gh_hostname(config.api_base.as_deref()).as_deref())?;
+    } else if config.token.is_empty() {
+        config.token = default_profile.map(|p| p.token)
+            .ok_or(ConfigError::MissingToken)?;
+    }
+    for api_base in config.api_base.iter().chain(config.profiles.0.iter().filter_map(|p| p.api_base.as_ref())) {
+        if let Err(e) = org_api_url(Some(api_base), "") {
+            return Err(ConfigError::InvalidApiBase(api_base.clone(), e.to_string()));
+        }
+    }
+    // Zero means unset, as in the `.env`.
+    if config.fetch_concurrency == 0 {
+        config.fetch_concurrency = default_fetch_concurrency();
+    }
+    if config.request_timeout == 0 {
+        config.request_timeout = default_request_timeout();
+    }
+    config.refresh_interval_secs = config.refresh_interval_secs.filter(|&n| n > 0);
+    Ok(config)
+}
+
+/// Sets `organization` and `token` in the `.env` at `path`, keeping its other lines, and
+/// leaves it readable by the owner only. The file is created if it doesn't exist yet.
+pub fn write_dot_env(path: &Path, organization: &str, token: &str) -> std::io::Result<()> {
+    let existing = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files.
+    #[cfg(unix)]
+    fs::set_permissions(path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    for line in existing.lines().filter(|line| !matches!(split_line(line), Some(("organization" | "token", _)))) {
+        writeln!(file, "{}", line)?;
+    }
+    writeln!(file, "organization={}", quote_value(organization))?;
+    writeln!(file, "token={}", quote_value(token))
+}
+
+/// Double quotes `value` so `parse_dot_env` reads it back unchanged.
+fn quote_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Where the token in use comes from when it isn't the `token` of `.env`, the one the new token
+/// form replaces, `None` when it is. `profile` is the profile switched to, if any.
+pub fn token_origin(config: &Config, profile: Option<&str>) -> Option<String> {
+    let dot_env = fs::read_to_string(DOT_ENV).unwrap_or_default();
+    token_origin_with(config, profile, Path::new(CONFIG_TOML).exists(), &dot_env, env_var)
+}
+
+/// Where the token of a profile switched to is configured.
+pub fn profile_token_origin(profile: &str) -> String {
+    let file = if Path::new(CONFIG_TOML).exists() { CONFIG_TOML } else { DOT_ENV };
+    format!("the {} profile in {}", profile, file)
+}
+
+/// Same as `token_origin`, given whether `runners.toml` exists and the `.env` contents.
+fn token_origin_with(config: &Config, profile: Option<&str>, toml: bool, dot_env: &str, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    // A profile switched to replaces whatever token was read at startup.
+    if let Some(profile) = profile {
+        return Some(profile_token_origin(profile));
+    }
+    if let Some(name) = TOKEN_VARS.iter().find(|name| env(name).is_some()) {
+        return Some(format!("the {} environment variable", name));
+    }
+    if config.token_source.as_deref() == Some("gh") {
+        return Some(String::from("the GitHub CLI (`gh auth login`)"));
+    }
+    if toml {
+        return Some(CONFIG_TOML.to_string());
+    }
+    if parse_dot_env(dot_env).contains_key("token") {
+        return None;
+    }
+    config.profiles.0.first().map(|p| format!("the {} profile in {}", p.name, DOT_ENV))
+}
+
+/// Host `gh` keeps the token of `api_base` under, `None` for github.com.
+fn gh_hostname(api_base: Option<&str>) -> Option<String> {
+    api_base
+        .and_then(|base| Url::parse(base).ok())
+        .and_then(|url| url.host_str().map(str::to_string))
+        .filter(|host| host != "api.github.com")
+}
+
+/// Asks the GitHub CLI for the token it stores, for `hostname` when given (GitHub Enterprise
+/// Server) and github.com otherwise.
+pub fn gh_auth_token(hostname: Option<&str>) -> Result<String, ConfigError> {
+    let mut command = Command::new("gh");
+    command.args(["auth", "token"]);
+    if let Some(hostname) = hostname {
+        command.args(["--hostname", hostname]);
+    }
+    let output = command.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => ConfigError::GhToken(String::from("`gh` is not installed or not in PATH")),
+        _ => ConfigError::GhToken(e.to_string()),
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ConfigError::GhToken(format!("`gh auth token` exited with {}: {}", output.status, stderr.trim())));
+    }
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        return Err(ConfigError::GhToken(String::from("`gh auth token` printed no token, run `gh auth login` first")));
+    }
+    Ok(token)
+}
+
+/// `KEY=value` pairs of a `.env` file. Blank lines and `#` comments are skipped, an `export `
+/// prefix is allowed and values may be wrapped in single or double quotes, which keeps `#` and
+/// surrounding spaces in them. Double quoted values understand `\"` and `\\` escapes.
+pub fn parse_dot_env(contents: &str) -> HashMap<String, String> {
+    contents.lines()
+        .filter_map(split_line)
+        .map(|(key, value)| (key.to_string(), parse_value(value)))
+        .collect()
+}
+
+/// Key and raw value of a `.env` line, `None` for blank lines and comments.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.strip_prefix("export ").unwrap_or(line).split_once('=')?;
+    Some((key.trim(), value.trim()))
+}
+
+fn parse_value(value: &str) -> String {
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest.split_once('\'').map_or(rest, |(quoted, _)| quoted).to_string();
+    }
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => parsed.extend(chars.next()),
+                c => parsed.push(c),
+            }
+        }
+        return parsed;
+    }
+    // Unquoted values end at a comment, which has to be separated by whitespace.
+    let end = value.char_indices()
+        .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+        .map_or(value.len(), |(i, _)| i);
+    value[..end].trim_end().to_string()
+}
+
+fn parse_config(contents: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+    parse_config_with(contents, env, gh_auth_token)
+}
+
+/// Same as `parse_config`, resolving `token_source=gh` through `gh_token`.
+fn parse_config_with(contents: Option<&str>, env: impl Fn(&str) -> Option<String>, gh_token: impl Fn(Option<&str>) -> Result<String, ConfigError>) -> Result<Config, ConfigError> {
+    let props = parse_dot_env(contents.unwrap_or_default());
+    let required_labels = RequiredLabels {
+        all: props.get("required_labels").map(|v| parse_list(v)).unwrap_or_default(),
+        groups: props.iter()
+            .filter_map(|(key, value)| key.strip_prefix("required_labels.")
+                .map(|group| (group.to_string(), parse_list(value))))
+            .collect(),
+    };
+    let profiles = Profiles(props.get("profiles").map(|v| parse_list(v)).unwrap_or_default()
+        .into_iter()
+        .filter_map(|name| Some(Profile {
+            organization: props.get(&format!("{}.organization", name))?.to_string(),
+            token: props.get(&format!("{}.token", name))?.to_string(),
+            api_base: props.get(&format!("{}.api_base", name)).cloned(),
+            name,
+        }))
+        .collect());
+    // Top-level keys may be omitted when profiles are defined, the first profile is used then.
+    let default_profile = profiles.0.first();
+    for api_base in props.get("api_base").into_iter().chain(profiles.0.iter().filter_map(|p| p.api_base.as_ref())) {
+        if let Err(e) = org_api_url(Some(api_base), "") {
+            return Err(ConfigError::InvalidApiBase(api_base.clone(), e.to_string()));
+        }
+    }
+    let organizations = props.get("organizations").map(|v| parse_list(v)).unwrap_or_default();
+    // Setting `enterprise` manages its runners instead, an organization is then only needed for the repositories tab.
+    let enterprise = props.get("enterprise").cloned().unwrap_or_default();
+    let scope = if enterprise.is_empty() { ApiScope::Org } else { ApiScope::Enterprise };
+    let organization = ORG_VARS.iter().find_map(|name| env(name))
+        .or_else(|| props.get("organization").or(default_profile.map(|p| &p.organization)).or(organizations.first()).cloned())
+        .or_else(|| (scope == ApiScope::Enterprise).then(String::new))
+        .ok_or(ConfigError::MissingOrganization)?;
+    let api_base = props.get("api_base").cloned().or(default_profile.and_then(|p| p.api_base.clone()));
+    let token = match TOKEN_VARS.iter().find_map(|name| env(name)) {
+        Some(token) => token,
+        None if props.get("token_source").is_some_and(|v| v == "gh") => gh_token(gh_hostname(api_base.as_deref()).as_deref())?,
+        None => props.get("token").or(default_profile.map(|p| &p.token)).cloned()
+            .ok_or(ConfigError::MissingToken)?,
+    };
+    Ok(Config {
+        organization,
+        scope,
+        enterprise,
+        organizations,
+        token,
+        token_source: props.get("token_source").cloned(),
+        api_base,
+        required_labels,
+        profiles,
+        drain_label: props.get("drain_label").cloned().unwrap_or_else(default_drain_label),
+        label_presets: props.get("label_presets").map(|v| parse_list(v)).unwrap_or_default(),
+        tabs: props.get("tabs").map(|v| parse_list(v)).unwrap_or_default(),
+        operations: OperationsConfig {
+            allowed: props.get("operations").map(|v| parse_list(v)),
+            hidden: props.get("hidden_operations").map(|v| parse_list(v)).unwrap_or_default(),
+        },
+        persist_cache: props.get("persist_cache").is_some_and(|v| v == "true"),
+        fetch_concurrency: props.get("fetch_concurrency")
+            .and_then(|v| v.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(default_fetch_concurrency),
+        request_timeout: props.get("request_timeout")
+            .and_then(|v| v.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or_else(default_request_timeout),
+        runners_cache_ttl: props.get("runners_cache_ttl")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_cache_ttl),
+        groups_cache_ttl: props.get("groups_cache_ttl")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_cache_ttl),
+        server_error_retries: props.get("server_error_retries")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(default_server_error_retries),
+        refresh_interval_secs: props.get("refresh_interval_secs")
+            .and_then(|v| v.parse().ok())
+            .filter(|&n| n > 0),
+        dry_run: props.get("dry_run").is_some_and(|v| v == "true"),
+        audit_log: props.get("audit_log").cloned().filter(|v| !v.is_empty()),
+        vim_keys: props.get("vim_keys").is_some_and(|v| v == "true"),
     })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn org_url_defaults_to_github_com() {
+        let url = org_api_url(None, "acme").unwrap();
+        assert_eq!(url.as_str(), "https://api.github.com/orgs/acme/");
+    }
+
+    #[test]
+    fn org_url_keeps_the_enterprise_server_path() {
+        for base in ["https://ghe.internal/api/v3", "https://ghe.internal/api/v3/"] {
+            let url = org_api_url(Some(base), "acme").unwrap();
+            assert_eq!(url.as_str(), "https://ghe.internal/api/v3/orgs/acme/");
+            assert_eq!(url.join("actions/runners").unwrap().as_str(), "https://ghe.internal/api/v3/orgs/acme/actions/runners");
+        }
+    }
+
+    #[test]
+    fn org_url_rejects_invalid_bases() {
+        assert!(org_api_url(Some("ghe.internal/api/v3"), "acme").is_err());
+        assert!(org_api_url(Some("ftp://ghe.internal"), "acme").is_err());
+        assert!(org_api_url(Some("https://ghe.internal/api/v3?x=1"), "acme").is_err());
+    }
+
+    #[test]
+    fn api_url_follows_the_scope() {
+        let mut config = Config { organization: String::from("acme"), enterprise: String::from("acme-corp"), ..Config::default() };
+        assert_eq!(config.api_url().unwrap().as_str(), "https://api.github.com/orgs/acme/");
+
+        config.scope = ApiScope::Enterprise;
+        config.api_base = Some(String::from("https://ghe.internal/api/v3"));
+        let url = config.api_url().unwrap();
+        assert_eq!(url.as_str(), "https://ghe.internal/api/v3/enterprises/acme-corp/");
+        assert_eq!(url.join("actions/runner-groups").unwrap().as_str(), "https://ghe.internal/api/v3/enterprises/acme-corp/actions/runner-groups");
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn config_from_file_only() {
+        let config = parse_config(Some("organization=acme\ntoken=file-token\n"), env(&[])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.token, "file-token");
+    }
+
+    #[test]
+    fn config_from_env_only() {
+        let config = parse_config(None, env(&[("GITHUB_ORG", "acme"), ("GH_TOKEN", "env-token")])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.token, "env-token");
+    }
+
+    #[test]
+    fn gh_token_source_uses_the_resolver_for_the_api_host() {
+        let file = "organization=acme\ntoken=file-token\ntoken_source=gh\napi_base=https://ghe.internal/api/v3\n";
+        let config = parse_config_with(Some(file), env(&[]), |host| {
+            assert_eq!(host, Some("ghe.internal"));
+            Ok(String::from("gh-token"))
+        }).unwrap();
+        assert_eq!(config.token, "gh-token");
+
+        let failing = |_: Option<&str>| Err(ConfigError::GhToken(String::from("`gh` is not installed or not in PATH")));
+        let error = parse_config_with(Some(file), env(&[]), failing).unwrap_err();
+        assert!(matches!(error, ConfigError::GhToken(_)));
+        assert!(error.to_string().contains("not installed"));
+
+        let config = parse_config_with(Some(file), env(&[("GH_TOKEN", "env-token")]), failing).unwrap();
+        assert_eq!(config.token, "env-token");
+    }
+
+    #[test]
+    fn toml_gh_token_source_uses_the_resolver_for_the_api_host() {
+        let file = "organization = \"acme\"\ntoken_source = \"gh\"\napi_base = \"https://ghe.internal/api/v3\"\n";
+        let config = parse_config_toml_with(file, env(&[]), |host| {
+            assert_eq!(host, Some("ghe.internal"));
+            Ok(String::from("gh-token"))
+        }).unwrap();
+        assert_eq!(config.token, "gh-token");
+        assert_eq!(config.token_source.as_deref(), Some("gh"));
+
+        let failing = |_: Option<&str>| Err(ConfigError::GhToken(String::from("`gh` is not installed or not in PATH")));
+        assert!(matches!(parse_config_toml_with(file, env(&[]), failing), Err(ConfigError::GhToken(_))));
 
-}
\ No newline at end of file
+        let config = parse_config_toml_with(file, env(&[("GH_TOKEN", "env-token")]), failing).unwrap();
+        assert_eq!(config.token, "env-token");
+    }
+
+    #[test]
+    fn token_origin_names_sources_the_token_form_cant_replace() {
+        let dot_env = "organization=acme\ntoken=t\n";
+        let config = parse_config(Some(dot_env), env(&[])).unwrap();
+        assert_eq!(token_origin_with(&config, None, false, dot_env, env(&[])), None);
+        assert_eq!(token_origin_with(&config, None, false, dot_env, env(&[("GH_TOKEN", "e")])).as_deref(), Some("the GH_TOKEN environment variable"));
+        assert_eq!(token_origin_with(&config, None, true, dot_env, env(&[])).as_deref(), Some(CONFIG_TOML));
+
+        let config = Config { token_source: Some(String::from("gh")), ..config };
+        assert!(token_origin_with(&config, None, false, dot_env, env(&[])).is_some_and(|o| o.contains("gh auth login")));
+
+        let dot_env = "profiles=work\nwork.organization=acme\nwork.token=t\n";
+        let config = parse_config(Some(dot_env), env(&[])).unwrap();
+        assert_eq!(token_origin_with(&config, None, false, dot_env, env(&[])).as_deref(), Some("the work profile in .env"));
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let file = "organization=acme\ntoken=file-token\ndrain_label=drain\n";
+        let config = parse_config(Some(file), env(&[("GITHUB_TOKEN", "env-token"), ("GH_TOKEN", "other")])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.token, "env-token");
+        assert_eq!(config.drain_label, "drain");
+    }
+
+    const PROFILES_TOML: &str = r#"
+[[profiles]]
+name = "work"
+organization = "acme"
+token = "toml-token"
+
+[[profiles]]
+name = "ghe"
+organization = "internal"
+token = "ghe-token"
+api_base = "https://ghe.internal/api/v3"
+"#;
+
+    #[test]
+    fn toml_profiles_are_parsed_with_an_optional_api_base() {
+        let config = parse_config_toml(PROFILES_TOML, env(&[])).unwrap();
+        let profiles = &config.profiles.0;
+        assert_eq!(profiles.len(), 2);
+        assert_eq!((profiles[0].name.as_str(), profiles[0].organization.as_str(), profiles[0].token.as_str()), ("work", "acme", "toml-token"));
+        assert_eq!(profiles[0].api_base, None);
+        assert_eq!(profiles[1].api_base.as_deref(), Some("https://ghe.internal/api/v3"));
+
+        let error = parse_config_toml("[[profiles]]\nname = \"work\"\norganization = \"acme\"\n", env(&[])).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid runners.toml: missing field `token`"), "{}", error);
+    }
+
+    #[test]
+    fn toml_without_top_level_keys_uses_the_first_profile() {
+        let mut config = parse_config_toml(PROFILES_TOML, env(&[])).unwrap();
+        assert_eq!((config.organization.as_str(), config.token.as_str()), ("acme", "toml-token"));
+        assert!(config.use_profile("ghe"));
+        assert_eq!(config.api_url().unwrap().as_str(), "https://ghe.internal/api/v3/orgs/internal/");
+
+        let config = parse_config_toml(PROFILES_TOML, env(&[("GITHUB_TOKEN", "env-token"), ("ORG", "other")])).unwrap();
+        assert_eq!((config.organization.as_str(), config.token.as_str()), ("other", "env-token"));
+    }
+
+    #[test]
+    fn toml_sets_every_field() {
+        let file = r#"
+organization = "acme"
+organizations = ["acme", "acme-labs"]
+token = "t"
+api_base = "https://ghe.internal/api/v3"
+drain_label = "on-hold"
+label_presets = ["gpu", "large"]
+tabs = ["repos", "runners"]
+persist_cache = true
+vim_keys = true
+fetch_concurrency = 8
+request_timeout = 10
+runners_cache_ttl = 30
+groups_cache_ttl = 600
+server_error_retries = 5
+refresh_interval_secs = 60
+dry_run = true
+audit_log = "/var/log/runners-audit.jsonl"
+
+[required_labels]
+all = ["self-hosted"]
+groups = { gpu = ["cuda"] }
+
+[operations]
+hidden = ["delete"]
+"#;
+        let config = parse_config_toml(file, env(&[])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.organizations, vec!["acme", "acme-labs"]);
+        assert_eq!(config.token, "t");
+        assert_eq!(config.scope, ApiScope::Org);
+        assert_eq!(config.api_base.as_deref(), Some("https://ghe.internal/api/v3"));
+        assert_eq!(config.drain_label, "on-hold");
+        assert_eq!(config.label_presets, vec!["gpu", "large"]);
+        assert_eq!(config.tabs, vec!["repos", "runners"]);
+        assert!(config.persist_cache && config.vim_keys);
+        assert_eq!(config.fetch_concurrency, 8);
+        assert_eq!(config.request_timeout, 10);
+        assert_eq!(config.runners_cache_ttl, 30);
+        assert_eq!(config.groups_cache_ttl, 600);
+        assert_eq!(config.server_error_retries, 5);
+        assert_eq!(config.refresh_interval_secs, Some(60));
+        assert!(config.dry_run);
+        assert_eq!(config.audit_log.as_deref(), Some("/var/log/runners-audit.jsonl"));
+        assert_eq!(config.required_labels.all, vec!["self-hosted"]);
+        assert_eq!(config.required_labels.groups["gpu"], vec!["cuda"]);
+        assert!(!config.operations.is_enabled("delete"));
+        assert!(config.operations.allowed.is_none());
+    }
+
+    #[test]
+    fn toml_omitted_keys_get_the_dot_env_defaults() {
+        let config = parse_config_toml("enterprise = \"acme-corp\"\ntoken = \"t\"\nfetch_concurrency = 0\n", env(&[])).unwrap();
+        let defaults = parse_config(Some("enterprise=acme-corp\ntoken=t\n"), env(&[])).unwrap();
+        assert_eq!(config.scope, ApiScope::Enterprise);
+        assert_eq!(config.organization, "");
+        assert_eq!(config.api_base, None);
+        assert_eq!(config.drain_label, defaults.drain_label);
+        assert_eq!(config.fetch_concurrency, defaults.fetch_concurrency);
+        assert_eq!(config.request_timeout, defaults.request_timeout);
+        assert_eq!(config.runners_cache_ttl, defaults.runners_cache_ttl);
+        assert_eq!(config.groups_cache_ttl, defaults.groups_cache_ttl);
+        assert_eq!(config.server_error_retries, defaults.server_error_retries);
+        assert_eq!(config.refresh_interval_secs, None);
+        assert!(!config.persist_cache && !config.dry_run && !config.vim_keys);
+        assert_eq!(config.audit_log, None);
+        assert!(config.profiles.0.is_empty() && config.label_presets.is_empty() && config.required_labels.is_empty());
+
+        assert!(matches!(parse_config_toml("token = \"t\"", env(&[])), Err(ConfigError::MissingOrganization)));
+        assert!(matches!(parse_config_toml("organization = \"acme\"", env(&[])), Err(ConfigError::MissingToken)));
+    }
+
+    #[test]
+    fn label_presets_are_a_comma_separated_list() {
+        let config = parse_config(Some("organization=acme\ntoken=t\nlabel_presets=gpu, large,,prod \n"), env(&[])).unwrap();
+        assert_eq!(config.label_presets, vec!["gpu", "large", "prod"]);
+
+        let config = parse_config(Some("organization=acme\ntoken=t\n"), env(&[])).unwrap();
+        assert!(config.label_presets.is_empty());
+    }
+
+    #[test]
+    fn tabs_keep_the_configured_order() {
+        let config = parse_config(Some("organization=acme\ntoken=t\ntabs=repos,runners\n"), env(&[])).unwrap();
+        assert_eq!(config.tabs, vec!["repos", "runners"]);
+
+        let config = parse_config(Some("organization=acme\ntoken=t\n"), env(&[])).unwrap();
+        assert!(config.tabs.is_empty());
+    }
+
+    #[test]
+    fn missing_keys_are_reported() {
+        assert!(matches!(parse_config(None, env(&[])), Err(ConfigError::MissingOrganization)));
+        assert!(matches!(parse_config(Some("organization=acme"), env(&[])), Err(ConfigError::MissingToken)));
+        assert!(matches!(parse_config(Some("api_base=nope"), env(&[])), Err(ConfigError::InvalidApiBase(..))));
+    }
+
+    #[test]
+    fn dot_env_values_may_be_quoted_exported_and_commented() {
+        let file = "# credentials\r\n\r\nexport organization=acme # the main org\r\ntoken=\"ghp_a#b=c \\\"x\\\" 'y'\" # quoted\r\ndrain_label='on hold #2'\r\n  # indented comment\r\n";
+        let props = parse_dot_env(file);
+        assert_eq!(props.len(), 3);
+        assert_eq!(props["organization"], "acme");
+        assert_eq!(props["token"], "ghp_a#b=c \"x\" 'y'");
+        assert_eq!(props["drain_label"], "on hold #2");
+
+        let config = parse_config(Some(file), env(&[])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.token, "ghp_a#b=c \"x\" 'y'");
+    }
+
+    #[test]
+    fn written_dot_env_is_private_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        write_dot_env(&path, "acme", "ghp_a#b \"c\" \\d").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let config = parse_config(Some(&fs::read_to_string(&path).unwrap()), env(&[])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.token, "ghp_a#b \"c\" \\d");
+
+        // A new token replaces the old one and keeps the other settings.
+        fs::write(&path, "# runners\nexport token=old\ndrain_label=drain\norganization=acme\n").unwrap();
+        write_dot_env(&path, "acme", "new").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "# runners\ndrain_label=drain\norganization=\"acme\"\ntoken=\"new\"\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn unquoted_values_keep_hashes_that_are_not_comments() {
+        let props = parse_dot_env("token=ghp_abc#123\napi_base=https://ghe.internal/api/v3#\n");
+        assert_eq!(props["token"], "ghp_abc#123");
+        assert_eq!(props["api_base"], "https://ghe.internal/api/v3#");
+    }
+
+    #[test]
+    fn organizations_list_provides_the_default_organization() {
+        let config = parse_config(Some("organizations=acme, globex\ntoken=t"), env(&[])).unwrap();
+        assert_eq!(config.organization, "acme");
+        assert_eq!(config.organizations, vec!["acme", "globex"]);
+    }
+
+    #[test]
+    fn enterprise_switches_the_scope_and_makes_the_organization_optional() {
+        let config = parse_config(Some("enterprise=acme-corp\ntoken=t"), env(&[])).unwrap();
+        assert_eq!(config.scope, ApiScope::Enterprise);
+        assert_eq!(config.enterprise, "acme-corp");
+        assert_eq!(config.organization, "");
+
+        let config = parse_config(Some("organization=acme\ntoken=t"), env(&[])).unwrap();
+        assert_eq!(config.scope, ApiScope::Org);
+    }
+}
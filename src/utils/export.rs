@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::fs;
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::client::api::{ApiRepository, RunnerGroupVisibility};
+use crate::model::runners::{FleetSummary, Runner, RunnerGroup};
+
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+pub fn markdown_report(organization: &str, runners: &[Runner]) -> String {
+    let summary = FleetSummary::from_runners(runners);
+    let mut by_group: BTreeMap<&str, Vec<&Runner>> = BTreeMap::new();
+    for runner in runners {
+        let group = runner.group.as_deref().unwrap_or("default");
+        by_group.entry(group).or_default().push(runner);
+    }
+
+    let mut report = format!("# Runner inventory - {}\n\n", escape_cell(organization));
+    report.push_str(&format!("Generated at {}\n\n", Utc::now().format("%Y-%m-%d %H:%M:%S UTC")));
+    report.push_str("## Summary\n\n");
+    report.push_str("| Total | Online | Busy | Offline | Groups |\n|---|---|---|---|---|\n");
+    report.push_str(&format!("| {} | {} | {} | {} | {} |\n",
+                             summary.total, summary.online, summary.busy, summary.offline, by_group.len()));
+    for (group, runners) in by_group {
+        report.push_str(&format!("\n## {}\n\n", escape_cell(group)));
+        report.push_str("| Name | Status | Labels |\n|---|---|---|\n");
+        for runner in runners {
+            let labels = runner.sorted_labels().into_iter().map(escape_cell).collect::<Vec<_>>().join(", ");
+            report.push_str(&format!("| {} | {} | {} |\n", escape_cell(&runner.name), runner.status, labels));
+        }
+    }
+    report
+}
+
+/// Writes the Markdown report to a timestamped file in the working directory and returns its path.
+pub fn write_markdown_report(organization: &str, runners: &[Runner]) -> Result<String> {
+    let path = format!("runners-{}-{}.md", organization, Utc::now().format("%Y%m%d%H%M%S"));
+    fs::write(&path, markdown_report(organization, runners))?;
+    Ok(path)
+}
+
+/// Snapshot of a runner as written to JSON exports.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunnerExport {
+    pub id: usize,
+    pub name: String,
+    pub os: String,
+    pub status: String,
+    pub group: String,
+    pub labels: Vec<String>,
+}
+
+impl From<&Runner> for RunnerExport {
+    fn from(runner: &Runner) -> Self {
+        RunnerExport {
+            id: runner.id,
+            name: runner.name.clone(),
+            os: runner.os.clone(),
+            status: runner.status.to_string(),
+            group: runner.group.clone().unwrap_or_else(|| String::from("default")),
+            labels: runner.sorted_labels().into_iter().map(str::to_string).collect(),
+        }
+    }
+}
+
+pub fn json_export<'r>(runners: impl IntoIterator<Item = &'r Runner>) -> Result<String> {
+    let exported: Vec<RunnerExport> = runners.into_iter().map(RunnerExport::from).collect();
+    Ok(serde_json::to_string_pretty(&exported)?)
+}
+
+/// Writes the runners as JSON to a timestamped file in the working directory and returns its path.
+pub fn write_json_export<'r>(runners: impl IntoIterator<Item = &'r Runner>) -> Result<String> {
+    let path = format!("runners-{}.json", Utc::now().format("%Y%m%d%H%M%S"));
+    fs::write(&path, json_export(runners)?)?;
+    Ok(path)
+}
+
+/// One row per group and repository with access to it, `group_id,group_name,visibility,repo_id,repo_name`.
+/// Groups open to all repositories, or without any repository, get a single row with empty repo columns.
+pub fn groups_csv(groups: &[(RunnerGroup, Vec<ApiRepository>)]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(["group_id", "group_name", "visibility", "repo_id", "repo_name"])?;
+    for (group, repos) in groups {
+        let visibility = match group.visibility {
+            RunnerGroupVisibility::All => "all",
+            RunnerGroupVisibility::Selected => "selected",
+        };
+        let group_id = group.id.to_string();
+        if repos.is_empty() {
+            writer.write_record([group_id.as_str(), &group.name, visibility, "", ""])?;
+        }
+        for repo in repos {
+            writer.write_record([group_id.as_str(), &group.name, visibility, &repo.id.to_string(), &repo.name])?;
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// Writes the group access CSV to a timestamped file in the working directory and returns its path.
+pub fn write_groups_csv(organization: &str, groups: &[(RunnerGroup, Vec<ApiRepository>)]) -> Result<String> {
+    let path = format!("runner-groups-{}-{}.csv", organization, Utc::now().format("%Y%m%d%H%M%S"));
+    fs::write(&path, groups_csv(groups)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::api::{runner_group_json, ApiRunner, ApiRunnerGroup};
+
+    #[test]
+    fn json_export_round_trips() {
+        let api_runner: ApiRunner = serde_json::from_value(serde_json::json!({
+            "id": 7,
+            "name": "web-01",
+            "os": "linux",
+            "status": "offline",
+            "busy": false,
+            "labels": [
+                { "id": 1, "name": "self-hosted", "type": "read-only" },
+                { "id": 2, "name": "gpu", "type": "custom" },
+            ],
+        })).unwrap();
+        let mut runner = Runner::from(api_runner);
+        runner.group = Some(String::from("linux"));
+
+        let json = json_export([&runner]).unwrap();
+        let exported: Vec<RunnerExport> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(exported, vec![RunnerExport {
+            id: 7,
+            name: String::from("web-01"),
+            os: String::from("linux"),
+            status: String::from("offline"),
+            group: String::from("linux"),
+            labels: vec![String::from("gpu")],
+        }]);
+    }
+
+    fn group(id: usize, name: &str, visibility: &str) -> RunnerGroup {
+        let api_group: ApiRunnerGroup = serde_json::from_value(runner_group_json(id, name, visibility, false)).unwrap();
+        RunnerGroup::from(api_group)
+    }
+
+    fn repo(id: usize, name: &str) -> ApiRepository {
+        serde_json::from_value(serde_json::json!({ "id": id, "name": name })).unwrap()
+    }
+
+    #[test]
+    fn groups_csv_has_a_row_per_repo_access() {
+        let groups = vec![
+            (group(1, "linux, arm", "selected"), vec![repo(10, "web"), repo(11, "api")]),
+            (group(2, "shared", "all"), vec![]),
+        ];
+
+        let csv = groups_csv(&groups).unwrap();
+
+        assert_eq!(csv.lines().collect::<Vec<_>>(), vec![
+            "group_id,group_name,visibility,repo_id,repo_name",
+            "1,\"linux, arm\",selected,10,web",
+            "1,\"linux, arm\",selected,11,api",
+            "2,shared,all,,",
+        ]);
+    }
+}
@@ -1,2 +1,6 @@
+pub mod audit;
 pub mod cache;
-pub mod config;
\ No newline at end of file
+pub mod clipboard;
+pub mod config;
+pub mod export;
+pub mod state;
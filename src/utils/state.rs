@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use anyhow::Result;
+use cli_log::*;
+use serde::{Deserialize, Serialize};
+
+const STATE_FILE: &str = ".runners-state.json";
+const MAX_LABEL_HISTORY: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelChange {
+    pub label: String,
+    pub added: bool,
+    pub timestamp: u64,
+}
+
+impl LabelChange {
+    /// Human readable form, e.g. "added `gpu` 2h ago".
+    pub fn describe(&self, now: u64) -> String {
+        let action = if self.added { "added" } else { "removed" };
+        format!("{} `{}` {}", action, self.label, describe_age(self.timestamp, now))
+    }
+}
+
+/// Relative age of a unix timestamp, e.g. "2h ago".
+pub fn describe_age(timestamp: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(timestamp);
+    match elapsed {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{}m ago", elapsed / 60),
+        3600..86400 => format!("{}h ago", elapsed / 3600),
+        _ => format!("{}d ago", elapsed / 86400),
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    protected_groups: HashSet<usize>,
+    #[serde(default)]
+    pub last_profile: Option<String>,
+    #[serde(default)]
+    label_history: HashMap<usize, Vec<LabelChange>>,
+    #[serde(default)]
+    last_seen_online: HashMap<usize, u64>,
+    /// Where `save` writes, set by `load` or `with_path`.
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl State {
+    /// Reads `.runners-state.json` from the working directory.
+    pub fn load() -> State {
+        State::load_from(PathBuf::from(STATE_FILE))
+    }
+
+    /// Reads the state file at `path`, starting empty if it is missing or invalid.
+    pub fn load_from(path: PathBuf) -> State {
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return State::with_path(path);
+        };
+        match serde_json::from_str::<State>(&contents) {
+            Ok(state) => State { path, ..state },
+            Err(e) => {
+                warn!("Ignoring invalid state file {}: {}", path.display(), e);
+                State::with_path(path)
+            }
+        }
+    }
+
+    /// An empty state that will be saved to `path`.
+    pub fn with_path(path: PathBuf) -> State {
+        State { path, ..State::default() }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_protected(&self, group_id: usize) -> bool {
+        self.protected_groups.contains(&group_id)
+    }
+
+    pub fn record_label_change(&mut self, runner_id: usize, label: String, added: bool) {
+        let history = self.label_history.entry(runner_id).or_default();
+        history.push(LabelChange { label, added, timestamp: now_secs() });
+        if history.len() > MAX_LABEL_HISTORY {
+            history.drain(..history.len() - MAX_LABEL_HISTORY);
+        }
+    }
+
+    pub fn label_history(&self, runner_id: usize) -> &[LabelChange] {
+        self.label_history.get(&runner_id).map(|h| h.as_slice()).unwrap_or_default()
+    }
+
+    pub fn record_seen_online(&mut self, runner_id: usize, timestamp: u64) {
+        self.last_seen_online.insert(runner_id, timestamp);
+    }
+
+    pub fn last_seen_online(&self, runner_id: usize) -> Option<u64> {
+        self.last_seen_online.get(&runner_id).copied()
+    }
+
+    /// Flips the protection flag of a group and returns the new value.
+    pub fn toggle_protected(&mut self, group_id: usize) -> bool {
+        if !self.protected_groups.remove(&group_id) {
+            self.protected_groups.insert(group_id);
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_protected_flips_the_flag_and_survives_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        let mut state = State::with_path(path.clone());
+
+        assert!(state.toggle_protected(3));
+        assert!(state.is_protected(3));
+        state.save().unwrap();
+        assert!(State::load_from(path.clone()).is_protected(3));
+
+        assert!(!state.toggle_protected(3));
+        assert!(!state.is_protected(3));
+        state.save().unwrap();
+        assert!(!State::load_from(path).is_protected(3));
+    }
+
+    #[test]
+    fn label_history_keeps_only_the_latest_changes() {
+        let mut state = State::default();
+        for i in 0..MAX_LABEL_HISTORY + 5 {
+            state.record_label_change(1, format!("label-{}", i), true);
+        }
+
+        let history = state.label_history(1);
+        assert_eq!(history.len(), MAX_LABEL_HISTORY);
+        assert_eq!(history[0].label, "label-5");
+        assert_eq!(history.last().unwrap().label, format!("label-{}", MAX_LABEL_HISTORY + 4));
+        assert!(state.label_history(2).is_empty());
+    }
+
+    #[test]
+    fn invalid_state_file_starts_empty_but_keeps_its_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        fs::write(&path, "not json").unwrap();
+
+        let mut state = State::load_from(path.clone());
+        assert!(!state.is_protected(1));
+        state.toggle_protected(1);
+        state.save().unwrap();
+        assert!(State::load_from(path).is_protected(1));
+    }
+}